@@ -1,45 +1,1546 @@
+use std::io::{self, BufRead, IsTerminal, Write};
 use std::path::PathBuf;
+use std::process::Command as ProcessCommand;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration as StdDuration, Instant};
 
-use tcalc_core::{Calendar, calendar_from_holidays, calendar_from_toml, run};
+use tcalc_core::{
+    AssumeMissing, Calendar, DateOrder, DateStyle, DurationStyle, EvaluatedLine, HourFormat, Lexer,
+    Locale, MissingDay, MonthArithmetic, Options, Overflow, Precision, Rotation, Session, Severity,
+    Syntax, Token, TwoDigitYears, Value, Zone, calendar_from_holidays, calendar_from_toml,
+    compile_with_options, diagnose, eval_lines, find_timestamps, format_source, lint,
+    meeting_slots, options_from_toml, readings, split_comment,
+};
+use time::format_description::well_known::{Rfc2822, Rfc3339};
+use time::{Duration, OffsetDateTime, Time, UtcOffset};
 
-use clap::Parser;
+use clap::{Args, Parser, Subcommand};
+
+/// How often the stopwatch and the timer redraw on a terminal.
+const REDRAW_INTERVAL: StdDuration = StdDuration::from_millis(100);
+
+/// Columns a redrawn status line is padded to, so it covers the one before
+/// without the escape codes older Windows consoles do not understand.
+const STATUS_WIDTH: usize = 79;
+
+/// Columns `extract --deltas` pads the time since the previous timestamp to.
+const DELTA_WIDTH: usize = 10;
+
+/// How many columns a timeline spans.
+const TIMELINE_WIDTH: usize = 60;
+
+/// How far apart the ticks on a timeline can be, the shortest that leaves at
+/// most `TIMELINE_TICKS` of them being used.
+const TIMELINE_STEPS: [Duration; 13] = [
+    Duration::MINUTE,
+    Duration::minutes(5),
+    Duration::minutes(15),
+    Duration::minutes(30),
+    Duration::HOUR,
+    Duration::hours(3),
+    Duration::hours(6),
+    Duration::hours(12),
+    Duration::DAY,
+    Duration::WEEK,
+    Duration::days(30),
+    Duration::days(91),
+    Duration::days(365),
+];
+const TIMELINE_TICKS: i64 = 5;
 
 #[derive(Parser)]
 #[command(name = "tcalc", author, version, about, long_about = None)]
 struct Cli {
-    #[arg(long, value_name = "PATH")]
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[command(flatten)]
+    settings: Settings,
+
+    #[command(flatten)]
+    eval: Eval,
+}
+
+/// How expressions are read and results shown, for every subcommand.
+#[derive(Args)]
+struct Settings {
+    #[arg(long, global = true, value_name = "PATH")]
+    config: Option<PathBuf>,
+
+    #[arg(long, global = true, value_name = "PATH")]
     calendar: Option<PathBuf>,
 
-    #[arg(long, value_name = "NAME", requires = "calendar")]
+    #[arg(long, global = true, value_name = "NAME", requires = "calendar")]
     calendar_name: Option<String>,
 
-    #[arg(long, value_name = "DATE")]
+    #[arg(long, global = true, value_name = "DATE")]
     holiday: Vec<String>,
 
-    #[arg(required = true, value_name = "EXPRESSION")]
+    #[arg(long, global = true, value_name = "12|24")]
+    hour_format: Option<HourFormat>,
+
+    #[arg(long, global = true, value_name = "TAG")]
+    locale: Option<Locale>,
+
+    #[arg(long, global = true, value_name = "numeric|long")]
+    date_style: Option<DateStyle>,
+
+    /// Follow dates in the result with their weekday
+    #[arg(long, global = true)]
+    weekday: bool,
+
+    /// Print results in plain ASCII, for consoles without Unicode
+    #[arg(long, global = true, alias = "no-emoji")]
+    ascii: bool,
+
+    #[arg(long, global = true, value_name = "signed|relative|words")]
+    duration_style: Option<DurationStyle>,
+
+    #[arg(long, global = true, value_name = "full|minutes|seconds|ms")]
+    precision: Option<Precision>,
+
+    /// Read inputs as GNU `date -d` strings, such as `2 days ago`
+    #[arg(long, global = true, value_name = "tcalc|gnu")]
+    syntax: Option<Syntax>,
+
+    #[arg(long, global = true, value_name = "ymd|dmy|mdy")]
+    date_order: Option<DateOrder>,
+
+    #[arg(long, global = true, value_name = "strict|PIVOT")]
+    two_digit_years: Option<TwoDigitYears>,
+
+    #[arg(long, global = true, value_name = "strict|current")]
+    assume_missing: Option<AssumeMissing>,
+
+    #[arg(long, global = true, value_name = "calendar|approximate|error")]
+    month_arithmetic: Option<MonthArithmetic>,
+
+    #[arg(long, global = true, value_name = "clamp|roll|error")]
+    missing_day: Option<MissingDay>,
+
+    #[arg(long, global = true, value_name = "error|saturate")]
+    overflow: Option<Overflow>,
+}
+
+/// What to evaluate and how to report it.
+#[derive(Args)]
+struct Eval {
+    /// Print every evaluated sub-expression before the result
+    #[arg(long)]
+    explain: bool,
+
+    /// Point out likely mistakes in the expression before evaluating it
+    #[arg(long)]
+    lint: bool,
+
+    /// Evaluate several expressions, one per flag, as a batch
+    #[arg(
+        short = 'e',
+        long = "expr",
+        value_name = "EXPRESSION",
+        conflicts_with = "expression"
+    )]
+    exprs: Vec<String>,
+
+    /// Lay batch results out as columns; without -e, read the batch from stdin
+    #[arg(long, value_name = "table|markdown|tsv", conflicts_with = "expression")]
+    output: Option<Output>,
+
+    /// Draw the batch on a timeline, each line a moment or `START .. END`
+    #[arg(long, conflicts_with_all = ["expression", "output"])]
+    timeline: bool,
+
+    /// Report errors, warnings and lints as JSON lines instead of results
+    #[arg(long, value_name = "human|json")]
+    diagnostics: Option<Diagnostics>,
+
+    /// Quote results for pasting into a shell, POSIX unless PowerShell is named
+    #[arg(
+        long,
+        value_name = "posix|powershell",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "posix",
+        conflicts_with_all = ["output", "timeline", "diagnostics"]
+    )]
+    shell_quote: Option<Shell>,
+
+    /// Expression to evaluate; without one, read expressions interactively
+    #[arg(value_name = "EXPRESSION", allow_hyphen_values = true)]
     expression: Vec<String>,
 }
 
+/// How problems with expressions are reported.
+#[derive(Clone, Copy, Default, PartialEq)]
+enum Diagnostics {
+    /// As `error:` and `note:` lines next to the results.
+    #[default]
+    Human,
+    /// One JSON object per diagnostic, in place of the results.
+    Json,
+}
+
+impl FromStr for Diagnostics {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "human" => Ok(Diagnostics::Human),
+            "json" => Ok(Diagnostics::Json),
+            _ => Err(format!(
+                "invalid diagnostics format '{}', expected human or json",
+                value
+            )),
+        }
+    }
+}
+
+/// The shell results are quoted for.
+#[derive(Clone, Copy, PartialEq)]
+enum Shell {
+    /// sh, bash, zsh and the like: single quotes, with `'\''` for a quote.
+    Posix,
+    /// Single quotes, with each quote doubled.
+    PowerShell,
+}
+
+impl Shell {
+    /// `text` as a single word in this shell, quoted unless every character
+    /// in it is safe without.
+    fn quote(self, text: &str) -> String {
+        let safe = |c: char| c.is_ascii_alphanumeric() || "_@%+=:,./-".contains(c);
+        if !text.is_empty() && text.chars().all(safe) {
+            return text.to_string();
+        }
+        match self {
+            Shell::Posix => format!("'{}'", text.replace('\'', "'\\''")),
+            Shell::PowerShell => {
+                // PowerShell also closes a string at typographic single quotes.
+                let mut quoted = String::from("'");
+                for c in text.chars() {
+                    if matches!(c, '\'' | '\u{2018}' | '\u{2019}' | '\u{201a}' | '\u{201b}') {
+                        quoted.push(c);
+                    }
+                    quoted.push(c);
+                }
+                quoted.push('\'');
+                quoted
+            }
+        }
+    }
+}
+
+impl FromStr for Shell {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "posix" | "sh" => Ok(Shell::Posix),
+            "powershell" | "pwsh" => Ok(Shell::PowerShell),
+            _ => Err(format!(
+                "invalid shell '{}', expected posix or powershell",
+                value
+            )),
+        }
+    }
+}
+
+/// How the results of a batch of expressions are printed.
+#[derive(Clone, Copy, Default, PartialEq)]
+enum Output {
+    /// Each result on its own line.
+    #[default]
+    Plain,
+    /// Expressions and results in aligned columns.
+    Table,
+    Markdown,
+    Tsv,
+}
+
+impl FromStr for Output {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "plain" => Ok(Output::Plain),
+            "table" => Ok(Output::Table),
+            "markdown" | "md" => Ok(Output::Markdown),
+            "tsv" => Ok(Output::Tsv),
+            _ => Err(format!(
+                "invalid output '{}', expected plain, table, markdown or tsv",
+                value
+            )),
+        }
+    }
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Evaluate expressions, as without a subcommand
+    Eval(Eval),
+    /// Read expressions interactively, one per line
+    Repl,
+    /// Rewrite expression files in canonical form
+    Fmt {
+        /// Report files that would change instead of rewriting them
+        #[arg(long)]
+        check: bool,
+
+        #[arg(required = true, value_name = "FILE")]
+        files: Vec<PathBuf>,
+    },
+    /// Show a Unix timestamp in several formats
+    Ts {
+        /// Seconds since 1970-01-01 00:00 UTC
+        #[arg(allow_hyphen_values = true)]
+        timestamp: i64,
+
+        /// Time zone to show the instant in
+        #[arg(long, value_name = "ZONE", default_value = "UTC")]
+        tz: String,
+    },
+    /// Copy text such as a log from standard input, with the age of each
+    /// timestamp found in it written after the timestamp
+    Extract {
+        /// Start each line with the time since the previous line's first
+        /// timestamp instead
+        #[arg(long)]
+        deltas: bool,
+    },
+    /// Look up time zones
+    Tz {
+        #[command(subcommand)]
+        command: TzCommand,
+    },
+    /// Time something from zero: each line entered marks a lap, q or end of input stops
+    Stopwatch,
+    /// List the times on a day within working hours in every zone
+    Meet {
+        /// Zones to meet across, the first one setting the day
+        #[arg(long, required = true, value_delimiter = ',', value_name = "ZONE,...")]
+        zones: Vec<String>,
+
+        /// Local working hours in each zone
+        #[arg(long, default_value = "9-17", value_name = "START-END")]
+        hours: String,
+
+        /// Shortest slot worth listing
+        #[arg(long, default_value = "30m")]
+        duration: String,
+
+        /// Day to meet on
+        #[arg(long, default_value = "today")]
+        on: String,
+    },
+    /// Show who is on call, or when someone's next shift starts
+    OnCall {
+        /// Who is on call at this time instead of now
+        #[arg(long, value_name = "EXPRESSION", conflicts_with = "next")]
+        at: Option<String>,
+
+        /// Show this participant's next shift instead
+        #[arg(long, value_name = "NAME")]
+        next: Option<String>,
+
+        /// Participants in rotation order, overriding the config
+        #[arg(long, value_delimiter = ',', value_name = "NAME,...")]
+        participants: Vec<String>,
+
+        /// When the first participant's first shift starts
+        #[arg(long, value_name = "EXPRESSION")]
+        start: Option<String>,
+
+        /// How long each shift lasts
+        #[arg(long, value_name = "DURATION")]
+        every: Option<String>,
+    },
+    /// Count down a duration such as 25m, then ring the terminal bell
+    Timer {
+        /// Send a desktop notification when the time is up
+        #[arg(long)]
+        notify: bool,
+
+        #[arg(required = true, allow_hyphen_values = true, value_name = "DURATION")]
+        duration: Vec<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum TzCommand {
+    /// List zone names, optionally only those containing FILTER
+    List { filter: Option<String> },
+    /// Show a zone's current offset, daylight saving status and next change
+    Info { zone: String },
+}
+
 pub fn exec() -> Result<(), String> {
     let cli = Cli::parse();
-    let calendar = load_calendar(&cli)?;
-    let expression = cli.expression.join(" ");
-    let result = run(&expression, Some(&calendar))?;
-    println!("{}", result);
+    let options = cli.settings.options()?;
+    match cli.command {
+        Some(_) if cli.eval.is_given() => Err(
+            "evaluation flags such as --explain or -e go after eval, not before a subcommand"
+                .to_string(),
+        ),
+        Some(command) => command.run(options),
+        None => evaluate(&cli.eval, options),
+    }
+}
+
+impl Eval {
+    fn is_given(&self) -> bool {
+        self.explain
+            || self.lint
+            || !self.exprs.is_empty()
+            || self.output.is_some()
+            || self.timeline
+            || self.diagnostics.is_some()
+            || self.shell_quote.is_some()
+            || !self.expression.is_empty()
+    }
+}
+
+impl Settings {
+    /// The options from the config file, with the flags given on top.
+    fn options(&self) -> Result<Options, String> {
+        let mut options = load_options(self)?;
+        options.calendar = load_calendar(self)?;
+        if let Some(hour_format) = self.hour_format {
+            options.hour_format = hour_format;
+        }
+        if let Some(locale) = self.locale {
+            options.locale = Some(locale);
+        }
+        if let Some(date_style) = self.date_style {
+            options.date_style = date_style;
+        }
+        if self.weekday {
+            options.show_weekday = true;
+        }
+        if self.ascii {
+            options.ascii = true;
+        }
+        if let Some(duration_style) = self.duration_style {
+            options.duration_style = duration_style;
+        }
+        if let Some(precision) = self.precision {
+            options.precision = precision;
+        }
+        if let Some(syntax) = self.syntax {
+            options.syntax = syntax;
+        }
+        if let Some(date_order) = self.date_order {
+            options.date_order = date_order;
+        }
+        if let Some(two_digit_years) = self.two_digit_years {
+            options.two_digit_years = two_digit_years;
+        }
+        if let Some(assume_missing) = self.assume_missing {
+            options.assume_missing = assume_missing;
+        }
+        if let Some(month_arithmetic) = self.month_arithmetic {
+            options.month_arithmetic = month_arithmetic;
+        }
+        if let Some(missing_day) = self.missing_day {
+            options.missing_day = missing_day;
+        }
+        if let Some(overflow) = self.overflow {
+            options.overflow = overflow;
+        }
+        Ok(options)
+    }
+}
+
+impl Command {
+    fn run(self, options: Options) -> Result<(), String> {
+        match self {
+            Command::Eval(eval) => evaluate(&eval, options),
+            Command::Repl => repl(options),
+            Command::Fmt { check, files } => format_files(&files, check, &options),
+            Command::Ts { timestamp, tz } => show_timestamp(timestamp, &tz, &options),
+            Command::Extract { deltas } => extract(deltas, &options),
+            Command::Tz { command } => show_zones(&command, &options),
+            Command::Meet {
+                zones,
+                hours,
+                duration,
+                on,
+            } => meet(&zones, &hours, &duration, &on, &options),
+            Command::OnCall {
+                at,
+                next,
+                participants,
+                start,
+                every,
+            } => {
+                let rotation =
+                    load_rotation(&participants, start.as_deref(), every.as_deref(), &options)?;
+                on_call(&rotation, at.as_deref(), next.as_deref(), &options)
+            }
+            Command::Stopwatch => stopwatch(&options),
+            Command::Timer { notify, duration } => timer(&duration.join(" "), notify, &options),
+        }
+    }
+}
+
+/// Evaluates the expression in `eval`, or the batch it names, or without
+/// either reads expressions interactively.
+fn evaluate(eval: &Eval, options: Options) -> Result<(), String> {
+    if eval.diagnostics == Some(Diagnostics::Json) {
+        let input = match (eval.exprs.is_empty(), eval.expression.is_empty()) {
+            (false, _) => eval.exprs.join("\n"),
+            (true, false) => eval.expression.join(" "),
+            (true, true) => io::read_to_string(io::stdin()).map_err(|err| err.to_string())?,
+        };
+        return print_diagnostics(&input, &options);
+    }
+    if eval.timeline {
+        let input = match eval.exprs.is_empty() {
+            false => eval.exprs.join("\n"),
+            true => io::read_to_string(io::stdin()).map_err(|err| err.to_string())?,
+        };
+        return timeline(&input, &options);
+    }
+    if !eval.exprs.is_empty() {
+        let input = eval.exprs.join("\n");
+        let lines = eval_lines(input.as_bytes(), &options);
+        let output = eval.output.unwrap_or_default();
+        return run_batch(lines, output, eval.shell_quote, &options);
+    }
+    if eval.expression.is_empty() {
+        return match eval.output {
+            Some(output) => {
+                let lines = eval_lines(io::stdin().lock(), &options);
+                run_batch(lines, output, None, &options)
+            }
+            None => repl(options),
+        };
+    }
+
+    let expression = eval.expression.join(" ");
+    let compiled = compile_with_options(&expression, &options)?;
+    if eval.lint {
+        for lint in lint(compiled.expr()) {
+            eprintln!("lint: {}", lint);
+        }
+    }
+    let evaluation = if eval.explain {
+        compiled.explain(&options)?
+    } else {
+        compiled.evaluate(&options)?
+    };
+    for step in &evaluation.trace {
+        println!("{} = {}", step.expr, step.value.display(&options));
+    }
+    for warning in &evaluation.warnings {
+        eprintln!("note: {}", warning);
+    }
+    let result = evaluation.value.display(&options).to_string();
+    match eval.shell_quote {
+        Some(shell) => println!("{}", shell.quote(&result)),
+        None => println!("{}", result),
+    }
+    if matches!(evaluation.value, Value::Bool(false)) {
+        exit_false();
+    }
+    Ok(())
+}
+
+/// Like `test`, a false condition exits with status 1, for `tcalc '…' && …`
+/// in scripts; errors exit with 2.
+fn exit_false() -> ! {
+    let _ = io::stdout().flush();
+    std::process::exit(1)
+}
+
+/// Evaluates a batch of expressions and prints them with their results, or
+/// each error in place of its result. Results are quoted for `shell`, if
+/// given.
+fn run_batch(
+    lines: impl Iterator<Item = io::Result<EvaluatedLine>>,
+    output: Output,
+    shell: Option<Shell>,
+    options: &Options,
+) -> Result<(), String> {
+    let mut rows = Vec::new();
+    let mut failed = 0;
+    let mut any_false = false;
+    for line in lines {
+        let line = line.map_err(|err| err.to_string())?;
+        let result = match line.result {
+            Ok(evaluation) => {
+                any_false |= matches!(evaluation.value, Value::Bool(false));
+                for warning in &evaluation.warnings {
+                    eprintln!("note: line {}: {}", line.line, warning);
+                }
+                let result = evaluation.value.display(options).to_string();
+                match shell {
+                    Some(shell) => shell.quote(&result),
+                    None => result,
+                }
+            }
+            Err(err) => {
+                failed += 1;
+                format!("error: {}", err)
+            }
+        };
+        rows.push([line.source.trim().to_string(), result]);
+    }
+
+    print_rows(&rows, output);
+    match failed {
+        0 if any_false => exit_false(),
+        0 => Ok(()),
+        failed => Err(format!("{} of {} expressions failed", failed, rows.len())),
+    }
+}
+
+/// Evaluates each line of `input` as a moment, or as a stretch of time
+/// written `START .. END`, and draws them against a shared axis whose ticks
+/// are as far apart as the whole span needs.
+fn timeline(input: &str, options: &Options) -> Result<(), String> {
+    let mut session = Session::new(options.clone());
+    let mut rows = Vec::new();
+    let mut failed = 0;
+    for line in input.lines() {
+        let code = split_comment(line).0.trim();
+        if code.is_empty() {
+            continue;
+        }
+
+        let mut evaluate = |input: &str| match session.evaluate(input)?.value {
+            value @ (Value::Date(_) | Value::DateTime(_) | Value::Zoned(..)) => Ok(value),
+            value => Err(format!(
+                "'{}' is not a date or a time",
+                value.display(session.options())
+            )),
+        };
+        let row = match code.split_once("..") {
+            Some((start, end)) => evaluate(start).and_then(|start| Ok((start, evaluate(end)?))),
+            None => evaluate(code).map(|at| (at.clone(), at)),
+        };
+        match row {
+            Ok((start, end)) => rows.push((code, start, end)),
+            Err(err) => {
+                failed += 1;
+                eprintln!("error: {}: {}", code, err);
+            }
+        }
+    }
+
+    // Dates start at midnight in the zone of the first time given, if any.
+    let offset = rows
+        .iter()
+        .flat_map(|(_, start, end)| [start, end])
+        .find_map(|value| match value {
+            Value::DateTime(at) | Value::Zoned(at, _) => Some(at.offset()),
+            _ => None,
+        })
+        .unwrap_or(UtcOffset::UTC);
+    let moment = |value: &Value| match value {
+        Value::Date(date) => date.midnight().assume_offset(offset),
+        Value::DateTime(at) | Value::Zoned(at, _) => *at,
+        _ => unreachable!("only dates and times are kept"),
+    };
+    let rows = rows
+        .iter()
+        .map(|(code, start, end)| (*code, moment(start), moment(end)))
+        .collect::<Vec<_>>();
+
+    let (Some(first), Some(last)) = (
+        rows.iter().map(|(_, start, end)| *start.min(end)).min(),
+        rows.iter().map(|(_, start, end)| *start.max(end)).max(),
+    ) else {
+        return match failed {
+            0 => Ok(()),
+            failed => Err(format!("{} of {} lines failed", failed, failed)),
+        };
+    };
+    let span = (last - first).max(Duration::MINUTE);
+    let step = TIMELINE_STEPS
+        .into_iter()
+        .find(|step| span.whole_seconds() / step.whole_seconds() <= TIMELINE_TICKS)
+        .unwrap_or_else(|| Duration::days(365 * (span.whole_days() / 365 / TIMELINE_TICKS + 1)));
+    let column = |at: OffsetDateTime| {
+        let offset = i128::from((at - first).whole_seconds());
+        (offset * (TIMELINE_WIDTH as i128 - 1) / i128::from(span.whole_seconds())) as usize
+    };
+
+    let width = rows
+        .iter()
+        .map(|(code, ..)| code.chars().count())
+        .max()
+        .unwrap_or_default();
+    for (code, start, end) in &rows {
+        let (from, to) = (column(*start.min(end)), column(*start.max(end)));
+        let mut bar = vec![' '; to + 1];
+        if start == end {
+            bar[from] = '*';
+        } else if from == to {
+            bar[from] = '|';
+        } else {
+            bar[from..=to].fill('=');
+            bar[from] = '[';
+            bar[to] = ']';
+        }
+        println!("{:width$}  {}", code, bar.iter().collect::<String>());
+    }
+
+    // Ticks fall on whole steps in local time, so on midnight for days.
+    let seconds = step.whole_seconds();
+    let local = first.to_offset(offset);
+    let local_seconds = local.unix_timestamp() + i64::from(offset.whole_seconds());
+    let mut next = local.checked_sub(Duration::seconds(local_seconds.rem_euclid(seconds)));
+    if next.is_some_and(|tick| tick < first) {
+        next = next.and_then(|tick| tick.checked_add(step));
+    }
+    // The span is at least a minute, which may run past the last moment.
+    let end = first.checked_add(span).unwrap_or(last);
+    let mut axis = vec!['-'; TIMELINE_WIDTH];
+    let mut labels = String::new();
+    while let Some(tick) = next.filter(|tick| *tick <= end) {
+        let at = column(tick);
+        axis[at] = '+';
+        let label = match tick.time() {
+            time if step < Duration::DAY && time != Time::MIDNIGHT => {
+                Value::Time(time).display(options).to_string()
+            }
+            _ => Value::Date(tick.date()).display(options).to_string(),
+        };
+        let taken = labels.chars().count();
+        if at >= taken + usize::from(taken > 0) {
+            labels.push_str(&" ".repeat(at - taken));
+            labels.push_str(&label);
+        }
+        next = tick.checked_add(step);
+    }
+    println!("{:width$}  {}", "", axis.iter().collect::<String>());
+    println!("{:width$}  {}", "", labels);
+
+    match failed {
+        0 => Ok(()),
+        failed => Err(format!(
+            "{} of {} lines failed",
+            failed,
+            failed + rows.len()
+        )),
+    }
+}
+
+/// Prints the diagnostics for each expression line of `input` as JSON, one
+/// object per line, with the 1-based `line` and `column` the span starts at.
+fn print_diagnostics(input: &str, options: &Options) -> Result<(), String> {
+    let mut failed = 0;
+    for (index, line) in input.lines().enumerate() {
+        let code = split_comment(line).0;
+        if code.trim().is_empty() {
+            continue;
+        }
+
+        for diagnostic in diagnose(code, options) {
+            failed += usize::from(diagnostic.severity == Severity::Error);
+            let column = code[..diagnostic.span.start].chars().count() + 1;
+            let mut json = serde_json::to_value(&diagnostic).map_err(|err| err.to_string())?;
+            json["line"] = (index + 1).into();
+            json["column"] = column.into();
+            println!("{}", json);
+        }
+    }
+
+    match failed {
+        0 => Ok(()),
+        failed => Err(format!("{} expressions failed", failed)),
+    }
+}
+
+fn print_rows(rows: &[[String; 2]], output: Output) {
+    let header = ["expression".to_string(), "result".to_string()];
+    let width = |rows: &[[String; 2]], column: usize| {
+        rows.iter()
+            .chain([&header])
+            .map(|row| row[column].chars().count())
+            .max()
+            .unwrap_or_default()
+    };
+
+    match output {
+        Output::Plain => {
+            for [_, result] in rows {
+                println!("{}", result);
+            }
+        }
+        Output::Table => {
+            let width = width(rows, 0);
+            for [expression, result] in [&header].into_iter().chain(rows) {
+                println!("{:width$}  {}", expression, result);
+            }
+        }
+        Output::Markdown => {
+            let rows = rows
+                .iter()
+                .map(|[expression, result]| [format!("`{}`", expression), result.clone()])
+                .map(|row| row.map(|cell| cell.replace('|', "\\|")))
+                .collect::<Vec<_>>();
+            let [left, right] = [0, 1].map(|column| width(&rows, column).max(3));
+            println!("| {:left$} | {:right$} |", header[0], header[1]);
+            println!("| {} | {} |", "-".repeat(left), "-".repeat(right));
+            for [expression, result] in &rows {
+                println!("| {:left$} | {:right$} |", expression, result);
+            }
+        }
+        Output::Tsv => {
+            for [expression, result] in [&header].into_iter().chain(rows) {
+                println!("{}\t{}", expression, result);
+            }
+        }
+    }
+}
+
+/// Evaluates one expression per line of standard input until it ends.
+fn repl(options: Options) -> Result<(), String> {
+    let mut session = Session::new(options);
+    let mut lines = io::stdin().lock().lines();
+    let interactive = io::stdin().is_terminal();
+
+    loop {
+        let Some(mut line) = prompt(&mut lines, interactive, "> ")? else {
+            return Ok(());
+        };
+        while is_incomplete(&line) {
+            let Some(more) = prompt(&mut lines, interactive, "... ")? else {
+                break;
+            };
+            line.push('\n');
+            line.push_str(&more);
+        }
+        let input = line.trim();
+        if input.is_empty() {
+            continue;
+        }
+        if let Some(command) = input.strip_prefix(':') {
+            if let Err(err) = meta_command(&mut session, command) {
+                eprintln!("error: {}", err);
+            }
+            continue;
+        }
+
+        session.refresh_now();
+        let result = match disambiguate(&mut lines, interactive, input, session.options())? {
+            Some(input) => session.evaluate(&input),
+            None => continue,
+        };
+        match result {
+            Ok(evaluation) => {
+                for warning in &evaluation.warnings {
+                    eprintln!("note: {}", warning);
+                }
+                let number = session.results().len();
+                println!(
+                    "%{} = {}",
+                    number,
+                    evaluation.value.display(session.options())
+                );
+            }
+            Err(err) => eprintln!("error: {}", err),
+        }
+    }
+}
+
+/// Runs a REPL command such as `:vars`, which inspects or changes the
+/// session rather than evaluating an expression.
+fn meta_command(session: &mut Session, command: &str) -> Result<(), String> {
+    let (name, argument) = command
+        .split_once(char::is_whitespace)
+        .map_or((command, ""), |(name, argument)| (name, argument.trim()));
+    let options = session.options();
+    match (name, argument) {
+        ("vars", "") => {
+            for (name, value) in session.variables() {
+                println!("{{{}}} = {}", name, value.display(options));
+            }
+        }
+        ("history", "") => {
+            for (index, (input, value)) in
+                session.history().iter().zip(session.results()).enumerate()
+            {
+                println!("%{}  {}  ->  {}", index + 1, input, value.display(options));
+            }
+        }
+        ("stats", "") => {
+            let results = session.results();
+            println!(
+                "{} evaluated, {} failed, {} variables",
+                results.len(),
+                session.failures(),
+                session.variables().len()
+            );
+            let mut types = Vec::<(&str, usize)>::new();
+            for value in results {
+                match types
+                    .iter_mut()
+                    .find(|(name, _)| *name == value.type_name())
+                {
+                    Some((_, count)) => *count += 1,
+                    None => types.push((value.type_name(), 1)),
+                }
+            }
+            types.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+            for (name, count) in types {
+                println!("{:>6}  {}", count, name);
+            }
+        }
+        ("tz", "") => match session.zone() {
+            Some(zone) => println!("{}", zone),
+            None => println!("results keep their own offset"),
+        },
+        ("tz", "off") => session.set_zone(None),
+        ("tz", name) => {
+            let zone =
+                Zone::from_name(name).ok_or_else(|| format!("unknown time zone '{}'", name))?;
+            session.set_zone(Some(zone));
+        }
+        ("format", format) => {
+            if let Ok(date_style) = format.parse::<DateStyle>() {
+                session.configure(|options| options.date_style = date_style);
+            } else if let Ok(duration_style) = format.parse::<DurationStyle>() {
+                session.configure(|options| options.duration_style = duration_style);
+            } else if let Ok(hour_format) = format.parse::<HourFormat>() {
+                session.configure(|options| options.hour_format = hour_format);
+            } else if let Ok(precision) = format.parse::<Precision>() {
+                session.configure(|options| options.precision = precision);
+            } else {
+                return Err(format!(
+                    "invalid format '{}', expected a date style, a duration style, an hour format or a precision",
+                    format
+                ));
+            }
+        }
+        ("save", path) if !path.is_empty() => {
+            let saved =
+                serde_json::to_string_pretty(&session.save()).map_err(|err| err.to_string())?;
+            std::fs::write(path, saved + "\n")
+                .map_err(|err| format!("failed to write '{}': {}", path, err))?;
+        }
+        ("load", path) if !path.is_empty() => {
+            let saved = std::fs::read_to_string(path)
+                .map_err(|err| format!("failed to read '{}': {}", path, err))?;
+            let saved = serde_json::from_str(&saved)
+                .map_err(|err| format!("failed to load '{}': {}", path, err))?;
+            session.load(saved);
+        }
+        ("save" | "load", _) => return Err(format!(":{} takes a file path", name)),
+        ("undo", "") if !session.undo() => return Err("nothing to undo".to_string()),
+        ("redo", "") if !session.redo() => return Err("nothing to redo".to_string()),
+        ("undo" | "redo", "") => {}
+        ("vars" | "history" | "stats" | "undo" | "redo", _) => {
+            return Err(format!(":{} takes no argument", name));
+        }
+        _ => {
+            return Err(format!(
+                "unknown command ':{}', expected :vars, :history, :stats, :tz, :format, :undo, :redo, :save or :load",
+                name
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Whether `input` clearly continues on the next line: it ends in an
+/// operator or a comma, or leaves a parenthesis open.
+fn is_incomplete(input: &str) -> bool {
+    let mut depth = 0usize;
+    let mut last = Token::Eof;
+    for token in Lexer::new(input) {
+        match token {
+            Token::Eof => break,
+            Token::Error(_) => return false,
+            Token::LParen => depth += 1,
+            Token::RParen => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+        last = token;
+    }
+
+    depth > 0
+        || match last {
+            Token::Ident(word) => matches!(word.as_str(), "and" | "or" | "not" | "between"),
+            Token::Plus
+            | Token::Minus
+            | Token::Star
+            | Token::Percent
+            | Token::Slash
+            | Token::Comma
+            | Token::Less
+            | Token::LessEqual
+            | Token::Greater
+            | Token::GreaterEqual
+            | Token::EqualEqual
+            | Token::BangEqual
+            | Token::Pipe => true,
+            _ => false,
+        }
+}
+
+/// Asks which reading was meant when `input` parses differently under
+/// different date orders, returning the chosen one in canonical form, or
+/// `None` if no valid choice was made. An input that only parses under
+/// another order than the configured one is read that way, with a note.
+fn disambiguate(
+    lines: &mut impl Iterator<Item = io::Result<String>>,
+    interactive: bool,
+    input: &str,
+    options: &Options,
+) -> Result<Option<String>, String> {
+    let readings = readings(input, options);
+    match readings.as_slice() {
+        [] => return Ok(Some(input.to_string())),
+        [reading] if reading.date_order == options.date_order => {
+            return Ok(Some(input.to_string()));
+        }
+        [reading] => {
+            eprintln!(
+                "note: '{}' only parses with the {} date order, as '{}'",
+                input.trim(),
+                reading.date_order,
+                reading.expr
+            );
+            return Ok(Some(reading.expr.to_string()));
+        }
+        _ => {}
+    }
+
+    let choices = readings
+        .iter()
+        .map(|reading| reading.expr.to_string())
+        .collect::<Vec<_>>();
+    eprintln!("did you mean {}?", choices.join(" or "));
+    for (index, choice) in choices.iter().enumerate() {
+        eprintln!("  {}) {}", index + 1, choice);
+    }
+
+    let answer = prompt(lines, interactive, "? ")?.unwrap_or_default();
+    match answer.trim().parse::<usize>() {
+        Ok(index @ 1..) if index <= choices.len() => Ok(Some(choices[index - 1].clone())),
+        _ => {
+            eprintln!("error: expected a number from 1 to {}", choices.len());
+            Ok(None)
+        }
+    }
+}
+
+/// Reads the next line, showing `prompt` first when a person is typing.
+fn prompt(
+    lines: &mut impl Iterator<Item = io::Result<String>>,
+    interactive: bool,
+    prompt: &str,
+) -> Result<Option<String>, String> {
+    if interactive {
+        print!("{}", prompt);
+        io::stdout().flush().map_err(|err| err.to_string())?;
+    }
+    lines.next().transpose().map_err(|err| err.to_string())
+}
+
+fn format_files(files: &[PathBuf], check: bool, options: &Options) -> Result<(), String> {
+    let mut unformatted = Vec::new();
+
+    for path in files {
+        let input = std::fs::read_to_string(path)
+            .map_err(|err| format!("failed to read '{}': {}", path.display(), err))?;
+        let output =
+            format_source(&input, options).map_err(|err| format!("{}: {}", path.display(), err))?;
+
+        if output == input {
+            continue;
+        }
+        if check {
+            unformatted.push(path.display().to_string());
+        } else {
+            std::fs::write(path, output)
+                .map_err(|err| format!("failed to write '{}': {}", path.display(), err))?;
+        }
+    }
+
+    match unformatted.as_slice() {
+        [] => Ok(()),
+        paths => Err(format!("not formatted: {}", paths.join(", "))),
+    }
+}
+
+fn show_timestamp(timestamp: i64, tz: &str, options: &Options) -> Result<(), String> {
+    let zone = Zone::from_name(tz).ok_or_else(|| format!("unknown time zone '{}'", tz))?;
+    let instant = OffsetDateTime::from_unix_timestamp(timestamp)
+        .ok()
+        .and_then(|instant| zone.at(instant))
+        .ok_or_else(|| format!("timestamp {} is out of range", timestamp))?;
+
+    let iso = instant.format(&Rfc3339).map_err(|err| err.to_string())?;
+    // RFC 2822 cannot express years before 1900.
+    let rfc2822 = instant.format(&Rfc2822).unwrap_or_else(|_| "-".to_string());
+    let offset = instant - options.now.unwrap_or_else(OffsetDateTime::now_utc);
+    let seconds = Options {
+        precision: Precision::Seconds,
+        ..Options::default()
+    };
+    let distance = Value::Duration(offset.abs()).display(&seconds).to_string();
+    let relative = if offset.is_negative() {
+        format!("{} ago", distance)
+    } else {
+        format!("in {}", distance)
+    };
+
+    println!("{}", Value::Zoned(instant, zone).display(options));
+    println!("iso      {}", iso);
+    println!("rfc2822  {}", rfc2822);
+    println!("relative {}", relative);
+    Ok(())
+}
+
+/// Copies standard input line by line, annotating the timestamps in each,
+/// read as `auto(…)` reads them, with their age or, with `deltas`, the line
+/// with the time since the previous timestamp. The first timestamp without
+/// an offset gets a note that it, like any later one, is read as UTC.
+fn extract(deltas: bool, options: &Options) -> Result<(), String> {
+    let now = options.now.unwrap_or_else(OffsetDateTime::now_utc);
+    let mut previous: Option<OffsetDateTime> = None;
+    let mut noted = false;
+    for (index, line) in io::stdin().lock().lines().enumerate() {
+        let line = line.map_err(|err| err.to_string())?;
+        let found = find_timestamps(&line);
+        if !noted && let Some(warning) = found.iter().find_map(|found| found.warning.as_ref()) {
+            eprintln!("note: line {}: {}", index + 1, warning);
+            noted = true;
+        }
+        if !deltas {
+            let mut annotated = String::new();
+            let mut copied = 0;
+            for timestamp in &found {
+                annotated.push_str(&line[copied..timestamp.range.end]);
+                let age = Value::Relative(timestamp.at - now);
+                annotated.push_str(&format!(" ({})", age.display(options)));
+                copied = timestamp.range.end;
+            }
+            println!("{}{}", annotated, &line[copied..]);
+            continue;
+        }
+
+        let at = found.first().map(|timestamp| timestamp.at);
+        let delta = match (previous, at) {
+            (Some(previous), Some(at)) => {
+                let delta = at - previous;
+                let sign = if delta.is_negative() { "-" } else { "+" };
+                let length = Value::Duration(delta.abs());
+                format!("{}{}", sign, length.display(options))
+            }
+            _ => String::new(),
+        };
+        println!("{:>width$}  {}", delta, line, width = DELTA_WIDTH);
+        previous = at.or(previous);
+    }
+    Ok(())
+}
+
+fn show_zones(command: &TzCommand, options: &Options) -> Result<(), String> {
+    match command {
+        TzCommand::List { filter } => {
+            let filter = filter.as_deref().unwrap_or_default().to_lowercase();
+            let mut names = Zone::all()
+                .map(Zone::name)
+                .filter(|name| name.to_lowercase().contains(&filter))
+                .collect::<Vec<_>>();
+            names.sort_unstable();
+            for name in names {
+                println!("{}", name);
+            }
+        }
+        TzCommand::Info { zone: name } => {
+            let zone =
+                Zone::from_name(name).ok_or_else(|| format!("unknown time zone '{}'", name))?;
+            let now = options
+                .now
+                .unwrap_or_else(OffsetDateTime::now_utc)
+                .replace_nanosecond(0)
+                .ok()
+                .and_then(|now| zone.at(now))
+                .ok_or("current time is out of range")?;
+
+            println!("now      {}", Value::Zoned(now, zone).display(options));
+            println!("abbr     {}", zone.abbreviation(now));
+            println!("dst      {}", if zone.is_dst(now) { "yes" } else { "no" });
+            match zone.next_transition(now).and_then(|next| zone.at(next)) {
+                Some(next) => println!(
+                    "next     {} ({})",
+                    Value::Zoned(next, zone).display(options),
+                    zone.abbreviation(next)
+                ),
+                None => println!("next     none within two years"),
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Lists the slots on the day `on` names during which every zone is within
+/// `hours`, each in the local time of every zone.
+fn meet(
+    zones: &[String],
+    hours: &str,
+    duration: &str,
+    on: &str,
+    options: &Options,
+) -> Result<(), String> {
+    let zones = zones
+        .iter()
+        .map(|name| Zone::from_name(name).ok_or_else(|| format!("unknown time zone '{}'", name)))
+        .collect::<Result<Vec<_>, _>>()?;
+    let hours = parse_hours(hours)?;
+    let length = match compile_with_options(duration, options)?.eval(options)? {
+        Value::Duration(length) if length.is_positive() => length,
+        value => {
+            return Err(format!(
+                "a meeting needs a positive duration, not '{}'",
+                value.display(options)
+            ));
+        }
+    };
+    let day = match compile_with_options(on, options)?.eval(options)? {
+        Value::Date(date) => date,
+        Value::DateTime(datetime) | Value::Zoned(datetime, _) => datetime.date(),
+        value => {
+            return Err(format!(
+                "a meeting needs a day, not '{}'",
+                value.display(options)
+            ));
+        }
+    };
+
+    let slots = meeting_slots(day, &zones, hours, length);
+    if slots.is_empty() {
+        return Err(format!(
+            "no {} slot within working hours in every zone on {}",
+            Value::Duration(length).display(options),
+            Value::Date(day).display(options)
+        ));
+    }
+    let width = zones
+        .iter()
+        .map(|zone| zone.name().len())
+        .max()
+        .unwrap_or_default();
+    for (index, slot) in slots.iter().enumerate() {
+        if index > 0 {
+            println!();
+        }
+        for zone in &zones {
+            let (Some(start), Some(end)) = (zone.at(slot.start), zone.at(slot.end)) else {
+                continue;
+            };
+            println!(
+                "{:width$}  {} - {}  ({})",
+                zone.name(),
+                Value::DateTime(start).display(options),
+                Value::Time(end.time()).display(options),
+                Value::Duration(slot.length()).display(options)
+            );
+        }
+    }
+    Ok(())
+}
+
+/// The rotation in the config, with any part of it given as a flag in its
+/// place.
+fn load_rotation(
+    participants: &[String],
+    start: Option<&str>,
+    every: Option<&str>,
+    options: &Options,
+) -> Result<Rotation, String> {
+    let configured = options.rotation.clone();
+    let start = match start {
+        Some(start) => match compile_with_options(start, options)?.eval(options)? {
+            Value::Date(date) => Some(date.midnight().assume_utc()),
+            Value::DateTime(start) | Value::Zoned(start, _) => Some(start),
+            value => {
+                return Err(format!(
+                    "a rotation starts at a date or a time, not '{}'",
+                    value.display(options)
+                ));
+            }
+        },
+        None => configured.as_ref().map(|rotation| rotation.start),
+    };
+    let period = match every {
+        Some(every) => match compile_with_options(every, options)?.eval(options)? {
+            Value::Duration(period) if period.is_positive() => Some(period),
+            _ => {
+                return Err(format!(
+                    "shifts need a positive, fixed length such as 1w, not '{}'",
+                    every
+                ));
+            }
+        },
+        None => configured.as_ref().map(|rotation| rotation.period),
+    };
+    let participants = match participants {
+        [] => configured.map(|rotation| rotation.participants),
+        participants => Some(participants.to_vec()),
+    };
+
+    match (participants, start, period) {
+        (Some(participants), Some(start), Some(period)) => Ok(Rotation {
+            participants,
+            start,
+            period,
+        }),
+        _ => Err(
+            "no rotation: set participants, start and every in a [rotation] config \
+             table or with --participants, --start and --every"
+                .to_string(),
+        ),
+    }
+}
+
+/// Prints who is on call at `at`, or now, or with `next` when that
+/// participant's next shift starts.
+fn on_call(
+    rotation: &Rotation,
+    at: Option<&str>,
+    next: Option<&str>,
+    options: &Options,
+) -> Result<(), String> {
+    let now = options.now.unwrap_or_else(OffsetDateTime::now_utc);
+    let at = match at {
+        Some(at) => match compile_with_options(at, options)?.eval(options)? {
+            Value::Date(date) => date.midnight().assume_utc(),
+            Value::DateTime(at) | Value::Zoned(at, _) => at,
+            value => {
+                return Err(format!(
+                    "expected a date or a time, not '{}'",
+                    value.display(options)
+                ));
+            }
+        },
+        None => now,
+    };
+    let show = |at: OffsetDateTime| Value::DateTime(at).display(options).to_string();
+
+    match next {
+        Some(name) => {
+            let shift = rotation
+                .next_shift(name, at)
+                .ok_or_else(|| format!("'{}' is not in the rotation", name))?;
+            println!(
+                "{} is next on call from {} to {} ({})",
+                name,
+                show(shift.start),
+                show(shift.end),
+                Value::Relative(shift.start - now).display(options)
+            );
+        }
+        None => {
+            let (name, shift) = rotation
+                .on_call(at)
+                .ok_or_else(|| "no one is on call then".to_string())?;
+            println!(
+                "{} is on call from {} to {}",
+                name,
+                show(shift.start),
+                show(shift.end)
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Reads working hours written as `9-17` or `08:30-17:30`.
+fn parse_hours(hours: &str) -> Result<(Time, Time), String> {
+    let invalid = || {
+        format!(
+            "invalid working hours '{}', expected START-END such as 9-17",
+            hours
+        )
+    };
+    let time = |part: &str| {
+        let (hour, minute) = part.trim().split_once(':').unwrap_or((part.trim(), "0"));
+        let hour = hour.parse().map_err(|_| invalid())?;
+        let minute = minute.parse().map_err(|_| invalid())?;
+        Time::from_hms(hour, minute, 0).map_err(|_| invalid())
+    };
+    let (open, close) = hours.split_once('-').ok_or_else(invalid)?;
+    let (open, close) = (time(open)?, time(close)?);
+    if open < close {
+        Ok((open, close))
+    } else {
+        Err(invalid())
+    }
+}
+
+/// Counts up from zero, redrawing the elapsed time on a terminal, until `q`
+/// or the end of input. Every other line, empty or not, marks a lap. Only
+/// the total goes to standard output, as a duration further expressions can
+/// use.
+fn stopwatch(options: &Options) -> Result<(), String> {
+    let start = Instant::now();
+    let running = Arc::new(AtomicBool::new(true));
+    let live = io::stderr().is_terminal();
+    let ticker = live.then(|| {
+        let (running, options) = (Arc::clone(&running), options.clone());
+        thread::spawn(move || {
+            while running.load(Ordering::Relaxed) {
+                draw_status(&show_elapsed(start.elapsed(), &options));
+                thread::sleep(REDRAW_INTERVAL);
+            }
+        })
+    });
+
+    let mut last_lap = StdDuration::ZERO;
+    for (lap, line) in io::stdin().lock().lines().enumerate() {
+        let line = line.map_err(|err| err.to_string())?;
+        if line.trim().eq_ignore_ascii_case("q") {
+            break;
+        }
+        let elapsed = start.elapsed();
+        if live {
+            clear_status();
+        }
+        eprintln!(
+            "lap {}  {}  (total {})",
+            lap + 1,
+            show_elapsed(elapsed - last_lap, options),
+            show_elapsed(elapsed, options)
+        );
+        last_lap = elapsed;
+    }
+
+    let total = start.elapsed();
+    running.store(false, Ordering::Relaxed);
+    if let Some(ticker) = ticker {
+        let _ = ticker.join();
+        clear_status();
+    }
+    println!("{}", show_elapsed(total, options));
     Ok(())
 }
 
-fn load_calendar(cli: &Cli) -> Result<Calendar, String> {
-    let mut calendar = match &cli.calendar {
+/// Writes `status` over the current line of standard error.
+fn draw_status(status: &str) {
+    eprint!("\r{:STATUS_WIDTH$}", status);
+}
+
+fn clear_status() {
+    eprint!("\r{:STATUS_WIDTH$}\r", "");
+}
+
+/// Waits for `input`, an expression giving a positive duration, redrawing
+/// the time left and a progress bar on a terminal.
+fn timer(input: &str, notify: bool, options: &Options) -> Result<(), String> {
+    const BAR_WIDTH: u32 = 20;
+    let length = match compile_with_options(input, options)?.eval(options)? {
+        Value::Duration(length) if length.is_positive() => length,
+        value => {
+            return Err(format!(
+                "a timer needs a positive duration, not '{}'",
+                value.display(options)
+            ));
+        }
+    };
+    let length = StdDuration::try_from(length).map_err(|err| err.to_string())?;
+
+    let live = io::stderr().is_terminal();
+    let start = Instant::now();
+    while let Some(left) = length
+        .checked_sub(start.elapsed())
+        .filter(|left| !left.is_zero())
+    {
+        if live {
+            let filled =
+                (BAR_WIDTH as f64 * (1.0 - left.as_secs_f64() / length.as_secs_f64())) as u32;
+            draw_status(&format!(
+                "{} left  [{}{}]",
+                show_elapsed(left, options),
+                "#".repeat(filled as usize),
+                "-".repeat((BAR_WIDTH - filled.min(BAR_WIDTH)) as usize)
+            ));
+            thread::sleep(left.min(REDRAW_INTERVAL));
+        } else {
+            thread::sleep(left);
+        }
+    }
+
+    let message = format!("{} is up", show_elapsed(length, options));
+    if live {
+        clear_status();
+        eprint!("\x07");
+    }
+    eprintln!("{}", message);
+    if notify && let Err(err) = send_notification(&message) {
+        eprintln!("note: could not send a notification: {}", err);
+    }
+    Ok(())
+}
+
+/// Shows `message` as a desktop notification, through `osascript` on macOS
+/// and `notify-send` elsewhere.
+fn send_notification(message: &str) -> io::Result<()> {
+    let mut command = if cfg!(target_os = "macos") {
+        let mut command = ProcessCommand::new("osascript");
+        let script = format!("display notification {:?} with title \"tcalc\"", message);
+        command.args(["-e", &script]);
+        command
+    } else {
+        let mut command = ProcessCommand::new("notify-send");
+        command.args(["tcalc", message]);
+        command
+    };
+    match command.status()? {
+        status if status.success() => Ok(()),
+        status => Err(io::Error::other(format!(
+            "{:?} exited with {}",
+            command.get_program(),
+            status
+        ))),
+    }
+}
+
+/// An elapsed time as a duration, to the millisecond unless the options ask
+/// for less.
+fn show_elapsed(elapsed: StdDuration, options: &Options) -> String {
+    let precision = match options.precision {
+        Precision::Full => Precision::Milliseconds,
+        precision => precision,
+    };
+    let options = Options {
+        precision,
+        ..options.clone()
+    };
+    let elapsed = Duration::try_from(elapsed).unwrap_or(Duration::MAX);
+    Value::Duration(elapsed).display(&options).to_string()
+}
+
+fn load_options(settings: &Settings) -> Result<Options, String> {
+    match &settings.config {
+        Some(path) => {
+            let input = std::fs::read_to_string(path)
+                .map_err(|err| format!("failed to read config '{}': {}", path.display(), err))?;
+            options_from_toml(&input)
+        }
+        None => Ok(Options::default()),
+    }
+}
+
+fn load_calendar(settings: &Settings) -> Result<Calendar, String> {
+    let mut calendar = match &settings.calendar {
         Some(path) => {
             let input = std::fs::read_to_string(path)
                 .map_err(|err| format!("failed to read calendar '{}': {}", path.display(), err))?;
-            calendar_from_toml(&input, cli.calendar_name.as_deref())?
+            calendar_from_toml(&input, settings.calendar_name.as_deref())?
         }
         None => Calendar::new(),
     };
 
-    let holiday_calendar = calendar_from_holidays(&cli.holiday)?;
+    let holiday_calendar = calendar_from_holidays(&settings.holiday)?;
     calendar.extend(&holiday_calendar);
 
     Ok(calendar)
@@ -50,7 +1551,7 @@ fn main() {
         Ok(()) => {}
         Err(err) => {
             eprintln!("error: {}", err);
-            std::process::exit(1);
+            std::process::exit(2);
         }
     }
 }
@@ -0,0 +1,33 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+#[test]
+fn ascii_repl_reads_input_as_typed() {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_tcalc"))
+        .args([
+            "--ascii",
+            "--locale",
+            "de-DE",
+            "--date-style",
+            "long",
+            "repl",
+        ])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all("3 März 2024\n1h\n".as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "%1 = Sonntag, 3. Maerz 2024\n%2 = 1h\n"
+    );
+}
@@ -0,0 +1,41 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// Runs `tcalc` with `args` and `stdin`, returning its exit status.
+fn status(args: &[&str], stdin: &str) -> Option<i32> {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_tcalc"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin.as_bytes())
+        .unwrap();
+    child.wait().unwrap().code()
+}
+
+#[test]
+fn single_expressions_exit_like_test() {
+    assert_eq!(status(&["1h < 2h"], ""), Some(0));
+    assert_eq!(status(&["1h > 2h"], ""), Some(1));
+    assert_eq!(status(&["2024/13/01"], ""), Some(2));
+}
+
+#[test]
+fn batches_exit_like_test() {
+    assert_eq!(status(&["-e", "1h", "-e", "1h < 2h"], ""), Some(0));
+    assert_eq!(status(&["-e", "1h", "-e", "1h > 2h"], ""), Some(1));
+    assert_eq!(status(&["-e", "1h > 2h", "-e", "2024/13/01"], ""), Some(2));
+
+    assert_eq!(status(&["--output", "plain"], "1h\n1h < 2h\n"), Some(0));
+    assert_eq!(status(&["--output", "plain"], "1h > 2h\n1h\n"), Some(1));
+    assert_eq!(
+        status(&["--output", "plain"], "1h > 2h\n2024/13/01\n"),
+        Some(2)
+    );
+}
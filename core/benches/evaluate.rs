@@ -0,0 +1,117 @@
+//! Lexing, parsing and evaluation benchmarks over representative inputs.
+//!
+//! Record a baseline before a performance-motivated change and compare
+//! against it afterwards:
+//!
+//! ```text
+//! cargo bench -p tcalc_core -- --save-baseline main
+//! cargo bench -p tcalc_core -- --baseline main
+//! ```
+
+use std::hint::black_box;
+
+use criterion::{BatchSize, Criterion, Throughput, criterion_group, criterion_main};
+use tcalc_core::{Lexer, Options, Token, compile, compile_with_options};
+use time::OffsetDateTime;
+
+const SHORT: &str = "2024/04/27 + 40wd";
+const BATCH_SIZE: usize = 10_000;
+
+/// A chain of a few hundred steps mixing dates, durations and units.
+fn long_chain() -> String {
+    let mut input = String::from("2024/01/31 10:00");
+    for step in 0..250 {
+        match step % 5 {
+            0 => input.push_str(" + 1d 6h"),
+            1 => input.push_str(" - 90 minutes"),
+            2 => input.push_str(" + 250ms * 4"),
+            3 => input.push_str(" + 3wd"),
+            _ => input.push_str(" - 2h45m"),
+        }
+    }
+    input
+}
+
+/// One expression per line, cycling through the supported syntax.
+fn batch() -> Vec<String> {
+    const TEMPLATES: &[&str] = &[
+        "2023/12/25 - 7d",
+        "2am + 30m",
+        "today - 2025/12/25",
+        "5 January 2024 + 1 month",
+        "next friday + 2h",
+        "days_in(2024/02) + 1d",
+        "(1s - 350ms) * 2",
+        "today > 2024/06/01 and today < 2024/09/01",
+    ];
+    (0..BATCH_SIZE)
+        .map(|index| TEMPLATES[index % TEMPLATES.len()].to_string())
+        .collect()
+}
+
+fn options() -> Options {
+    Options {
+        now: Some(OffsetDateTime::UNIX_EPOCH),
+        ..Options::default()
+    }
+}
+
+fn count_tokens(input: &str) -> usize {
+    let mut lexer = Lexer::new(input);
+    let mut count = 0;
+    while lexer.next_token() != Token::Eof {
+        count += 1;
+    }
+    count
+}
+
+fn lex(c: &mut Criterion) {
+    let long = long_chain();
+    let mut group = c.benchmark_group("lex");
+    group.bench_function("short", |b| b.iter(|| count_tokens(black_box(SHORT))));
+    group.throughput(Throughput::Bytes(long.len() as u64));
+    group.bench_function("long_chain", |b| b.iter(|| count_tokens(black_box(&long))));
+    group.finish();
+}
+
+fn parse(c: &mut Criterion) {
+    let long = long_chain();
+    let mut group = c.benchmark_group("parse");
+    group.bench_function("short", |b| b.iter(|| compile(black_box(SHORT))));
+    group.bench_function("long_chain", |b| b.iter(|| compile(black_box(&long))));
+    group.finish();
+}
+
+fn evaluate(c: &mut Criterion) {
+    let options = options();
+    let short = compile_with_options(SHORT, &options).unwrap();
+    let long = compile_with_options(&long_chain(), &options).unwrap();
+    let mut group = c.benchmark_group("evaluate");
+    group.bench_function("short", |b| b.iter(|| short.eval(black_box(&options))));
+    group.bench_function("long_chain", |b| b.iter(|| long.eval(black_box(&options))));
+    group.finish();
+}
+
+fn run_batch(c: &mut Criterion) {
+    let options = options();
+    let inputs = batch();
+    let mut group = c.benchmark_group("batch");
+    group.throughput(Throughput::Elements(BATCH_SIZE as u64));
+    group.sample_size(20);
+    group.bench_function("compile_and_run", |b| {
+        b.iter_batched(
+            || inputs.clone(),
+            |inputs| {
+                for input in &inputs {
+                    let _ = compile_with_options(input, &options)
+                        .and_then(|compiled| compiled.run(&options));
+                }
+            },
+            BatchSize::LargeInput,
+        )
+    });
+    group.finish();
+}
+
+criterion_group!(benches, lex, parse, evaluate, run_batch);
+criterion_main!(benches);
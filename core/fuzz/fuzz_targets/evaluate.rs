@@ -0,0 +1,17 @@
+//! Evaluating any expression that parses must fail cleanly, never panic.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use tcalc_core::{Options, compile};
+
+fuzz_target!(|input: &str| {
+    // A fixed reference time keeps crashes reproducible.
+    let options = Options {
+        now: Some(time::OffsetDateTime::UNIX_EPOCH),
+        ..Options::default()
+    };
+    if let Ok(compiled) = compile(input) {
+        let _ = compiled.evaluate(&options);
+    }
+});
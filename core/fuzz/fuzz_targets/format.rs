@@ -0,0 +1,15 @@
+//! The canonical form of a parsed expression parses and prints back to itself.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use tcalc_core::compile;
+
+fuzz_target!(|input: &str| {
+    if let Ok(compiled) = compile(input) {
+        let printed = compiled.expr().to_string();
+        let reparsed = compile(&printed)
+            .unwrap_or_else(|err| panic!("'{}' printed as '{}': {}", input, printed, err));
+        assert_eq!(reparsed.expr().to_string(), printed);
+    }
+});
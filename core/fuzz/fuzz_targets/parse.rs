@@ -0,0 +1,9 @@
+//! Lexing and parsing arbitrary text must fail cleanly, never panic.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|input: &str| {
+    let _ = tcalc_core::compile(input);
+});
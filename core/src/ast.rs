@@ -0,0 +1,132 @@
+//! Stores an expression as a flat list of nodes that refer to their operands
+//! by index, so that a whole tree takes a single allocation.
+
+use alloc::{boxed::Box, string::String, vec::Vec};
+use core::ops::Index;
+
+use crate::parser::{Expr, Op, UnaryOp};
+
+/// Refers to a node of an [`Ast`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NodeId(u32);
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Node {
+    /// An expression without operands, such as a date or a duration. Never
+    /// an `Expr::UnaryOp`, `Expr::BinOp` or `Expr::Call`.
+    Leaf(Expr),
+    UnaryOp(UnaryOp, NodeId),
+    BinOp(NodeId, Op, NodeId),
+    Call(String, Vec<NodeId>),
+}
+
+/// An expression tree whose nodes live in one vector. Operands are pushed
+/// before the node that uses them, so the last node is the root.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Ast {
+    nodes: Vec<Node>,
+}
+
+impl Ast {
+    pub fn push(&mut self, node: Node) -> NodeId {
+        debug_assert!(!matches!(
+            node,
+            Node::Leaf(Expr::UnaryOp(..) | Expr::BinOp(..) | Expr::Call(..))
+        ));
+        let id = NodeId(self.nodes.len() as u32);
+        self.nodes.push(node);
+        id
+    }
+
+    /// Pushes `expr`, with its operands as separate nodes.
+    pub fn push_expr(&mut self, expr: &Expr) -> NodeId {
+        let node = match expr {
+            Expr::UnaryOp(op, operand) => Node::UnaryOp(op.clone(), self.push_expr(operand)),
+            Expr::BinOp(left, op, right) => {
+                let left = self.push_expr(left);
+                Node::BinOp(left, op.clone(), self.push_expr(right))
+            }
+            Expr::Call(name, args) => Node::Call(
+                name.clone(),
+                args.iter().map(|arg| self.push_expr(arg)).collect(),
+            ),
+            leaf => Node::Leaf(leaf.clone()),
+        };
+        self.push(node)
+    }
+
+    /// The root node. Panics on an empty tree, which the parser never builds.
+    pub fn root(&self) -> NodeId {
+        NodeId(self.nodes.len() as u32 - 1)
+    }
+
+    /// Rebuilds the boxed [`Expr`] rooted at `id`.
+    pub fn expr(&self, id: NodeId) -> Expr {
+        match &self[id] {
+            Node::Leaf(expr) => expr.clone(),
+            Node::UnaryOp(op, operand) => Expr::UnaryOp(op.clone(), Box::new(self.expr(*operand))),
+            Node::BinOp(left, op, right) => Expr::BinOp(
+                Box::new(self.expr(*left)),
+                op.clone(),
+                Box::new(self.expr(*right)),
+            ),
+            Node::Call(name, args) => Expr::Call(
+                name.clone(),
+                args.iter().map(|arg| self.expr(*arg)).collect(),
+            ),
+        }
+    }
+
+    /// The expressions without operands anywhere in the tree.
+    pub fn leaves(&self) -> impl Iterator<Item = &Expr> {
+        self.nodes.iter().filter_map(|node| match node {
+            Node::Leaf(expr) => Some(expr),
+            _ => None,
+        })
+    }
+
+    pub fn to_expr(&self) -> Expr {
+        self.expr(self.root())
+    }
+}
+
+impl Index<NodeId> for Ast {
+    type Output = Node;
+
+    fn index(&self, id: NodeId) -> &Node {
+        &self.nodes[id.0 as usize]
+    }
+}
+
+impl From<&Expr> for Ast {
+    fn from(expr: &Expr) -> Self {
+        let mut ast = Ast::default();
+        ast.push_expr(expr);
+        ast
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Unit;
+
+    #[test]
+    fn test_round_trips_expr() {
+        let expr = Expr::BinOp(
+            Box::new(Expr::Date(2024, 1, 31)),
+            Op::Add,
+            Box::new(Expr::Call(
+                "days_in".to_string(),
+                vec![Expr::UnaryOp(
+                    UnaryOp::Neg,
+                    Box::new(Expr::Duration(2, Unit::Days)),
+                )],
+            )),
+        );
+        let ast = Ast::from(&expr);
+
+        assert!(matches!(ast[ast.root()], Node::BinOp(_, Op::Add, _)));
+        assert_eq!(ast.to_expr(), expr);
+    }
+}
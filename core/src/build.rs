@@ -0,0 +1,195 @@
+//! Builds expressions out of parts, so a host application can combine a
+//! user's input with its own values without pasting strings together. Each
+//! part becomes a whole operand: a [`fragment`] is parsed on its own, so
+//! whatever it contains cannot reach outside the operand it becomes.
+//!
+//! ```
+//! use tcalc_core::build;
+//! use tcalc_core::{CompiledExpr, Op, Options};
+//!
+//! let options = Options::default();
+//! let deadline = time::Date::from_calendar_date(2024, time::Month::March, 1).unwrap();
+//! let user_input = "3wd";
+//!
+//! let expr = build::binary(
+//!     build::date(deadline),
+//!     Op::Add,
+//!     build::fragment(user_input, &options).unwrap(),
+//! );
+//! assert_eq!(CompiledExpr::from(expr).run(&options).unwrap(), "2024-03-06");
+//! ```
+//!
+//! Values without a literal form, such as a date-time with an offset, go in
+//! through a [`placeholder`] and a [`Template`](crate::Template).
+//!
+//! The helpers return an opaque [`Part`], so this module needs no
+//! `unstable-ast` feature: only turning a part into an
+//! [`Expr`](crate::Expr) does.
+
+use alloc::{boxed::Box, format, string::String, vec::Vec};
+use core::fmt;
+
+use time::Date;
+
+use crate::lexer::Lexer;
+use crate::options::Options;
+use crate::parser::{Expr, Op, Unit, parse_ast};
+
+/// A piece of an expression made by the helpers in this module, ready to be
+/// combined further or turned into a [`CompiledExpr`](crate::CompiledExpr)
+/// or [`Template`](crate::Template). It prints in canonical form.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Part(pub(crate) Expr);
+
+impl Part {
+    /// The parsed tree, whose shape may change in any release.
+    #[cfg(feature = "unstable-ast")]
+    pub fn expr(&self) -> &Expr {
+        &self.0
+    }
+}
+
+#[cfg(feature = "unstable-ast")]
+impl From<Expr> for Part {
+    fn from(expr: Expr) -> Self {
+        Part(expr)
+    }
+}
+
+impl fmt::Display for Part {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// Parses a fragment from an untrusted source, such as `3wd` or `next
+/// friday`, as one complete expression, with the parse-time settings and
+/// limits in `options`. Placeholders, `%n` and `ans` are rejected, so the
+/// fragment cannot read values the host binds or earlier results.
+pub fn fragment(input: &str, options: &Options) -> Result<Part, String> {
+    let ast = parse_ast(Lexer::new(input), options)
+        .map_err(|err| format!("failed to parse fragment: {}", err))?;
+    if let Some(leaf) = ast
+        .leaves()
+        .find(|leaf| matches!(leaf, Expr::Placeholder(_) | Expr::History(_) | Expr::Ans))
+    {
+        return Err(format!("fragment cannot refer to '{}'", leaf));
+    }
+    Ok(Part(ast.to_expr()))
+}
+
+pub fn date(date: Date) -> Part {
+    Part(Expr::Date(date.year(), date.month().into(), date.day()))
+}
+
+/// A duration literal in the largest unit that holds `duration` exactly.
+pub fn duration(duration: time::Duration) -> Part {
+    let nanos = duration.whole_nanoseconds();
+    let expr = [
+        Unit::Days,
+        Unit::Hours,
+        Unit::Minutes,
+        Unit::Seconds,
+        Unit::Milliseconds,
+        Unit::Microseconds,
+    ]
+    .into_iter()
+    .find_map(|unit| {
+        let length = i128::from(unit.nanoseconds()?);
+        let value = i64::try_from(nanos / length).ok()?;
+        (nanos % length == 0).then_some(Expr::Duration(value, unit))
+    })
+    .unwrap_or_else(|| match i64::try_from(nanos) {
+        Ok(nanos) => Expr::Duration(nanos, Unit::Nanoseconds),
+        Err(_) => Expr::Duration(duration.whole_seconds(), Unit::Seconds),
+    });
+    Part(expr)
+}
+
+pub fn number(number: i64) -> Part {
+    Part(Expr::Number(number))
+}
+
+/// A slot filled in with a value when the expression is evaluated as a
+/// [`Template`](crate::Template).
+pub fn placeholder(name: &str) -> Part {
+    Part(Expr::Placeholder(String::from(name)))
+}
+
+pub fn binary(left: Part, op: Op, right: Part) -> Part {
+    Part(Expr::BinOp(Box::new(left.0), op, Box::new(right.0)))
+}
+
+pub fn call(name: &str, args: Vec<Part>) -> Part {
+    Part(Expr::Call(
+        String::from(name),
+        args.into_iter().map(|arg| arg.0).collect(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CompiledExpr, Template, Value};
+    use alloc::{string::ToString, vec};
+
+    #[test]
+    fn test_fragments_stay_one_operand() {
+        let options = Options::default();
+        let expr = binary(fragment("1d + 2d", &options).unwrap(), Op::Mul, number(2));
+
+        assert_eq!(expr.to_string(), "(1d + 2d) * 2");
+        assert_eq!(CompiledExpr::from(expr).run(&options).unwrap(), "6d");
+        assert!(fragment("1d) + (2d", &options).is_err());
+    }
+
+    #[test]
+    fn test_fragments_cannot_read_bindings() {
+        let options = Options::default();
+        for input in ["{start} + 1d", "days_in(%1)", "ans * 2"] {
+            assert!(fragment(input, &options).is_err(), "{}", input);
+        }
+        assert_eq!(
+            fragment("1d + {start}", &options).unwrap_err(),
+            "fragment cannot refer to '{start}'"
+        );
+    }
+
+    #[test]
+    fn test_binds_values_without_a_literal_form() {
+        let options = Options::default();
+        let start = Date::from_calendar_date(2024, time::Month::March, 1).unwrap();
+        let expr = call(
+            "between",
+            vec![
+                placeholder("start"),
+                binary(
+                    placeholder("start"),
+                    Op::Add,
+                    duration(time::Duration::minutes(90)),
+                ),
+            ],
+        );
+
+        let template = Template::from(expr);
+        assert_eq!(template.placeholders(), ["start"]);
+        let start = Value::DateTime(start.midnight().assume_utc());
+        assert_eq!(
+            template.run(&[("start", start)], &options).unwrap(),
+            "1h30m"
+        );
+    }
+
+    #[test]
+    fn test_picks_largest_exact_unit() {
+        assert_eq!(
+            duration(time::Duration::hours(48)).0,
+            Expr::Duration(2, Unit::Days)
+        );
+        assert_eq!(
+            duration(time::Duration::milliseconds(1500)).0,
+            Expr::Duration(1500, Unit::Milliseconds)
+        );
+        assert_eq!(duration(time::Duration::ZERO).to_string(), "0d");
+    }
+}
@@ -1,15 +1,23 @@
-use std::collections::HashSet;
+use alloc::{collections::BTreeSet, format, string::String};
+use core::str::FromStr;
 
 use time::{Date, Duration, Month, OffsetDateTime, Weekday};
 
 use crate::evaluator::EvalError;
+use crate::options::{Paydays, Sprints};
+use crate::parser::{Period, Recurrence, SprintEdge};
 
 #[derive(Debug, Clone, Default)]
 pub struct Calendar {
-    holidays: HashSet<Date>,
+    holidays: BTreeSet<Date>,
 }
 
 impl Calendar {
+    /// A calendar without holidays, on which working days are weekdays.
+    pub const EMPTY: Calendar = Calendar {
+        holidays: BTreeSet::new(),
+    };
+
     pub fn new() -> Self {
         Self::default()
     }
@@ -22,7 +30,7 @@ impl Calendar {
         self.holidays.extend(other.holidays.iter().copied());
     }
 
-    pub fn add_holiday_ymd(&mut self, year: u32, month: u8, day: u8) -> Result<(), EvalError> {
+    pub fn add_holiday_ymd(&mut self, year: i32, month: u8, day: u8) -> Result<(), EvalError> {
         self.add_holiday(date_from_parts(year, month, day)?);
         Ok(())
     }
@@ -33,37 +41,298 @@ impl Calendar {
     }
 }
 
+/// Counts the days from `from` to `to`, both included, that `recurrence`
+/// falls on. None when `to` comes before `from`, and no paydays without a
+/// pay schedule.
+pub fn count_days(
+    from: Date,
+    to: Date,
+    recurrence: Recurrence,
+    calendar: &Calendar,
+    paydays: Option<&Paydays>,
+) -> i64 {
+    let mut count = 0;
+    let mut date = Some(from);
+    while let Some(day) = date.filter(|day| *day <= to) {
+        let falls_on = match recurrence {
+            Recurrence::Day => true,
+            Recurrence::Weekday => !matches!(day.weekday(), Weekday::Saturday | Weekday::Sunday),
+            Recurrence::WorkingDay => calendar.is_working_day(day),
+            Recurrence::On(weekday) => day.weekday() == weekday,
+            Recurrence::Payday => paydays.is_some_and(|paydays| is_payday(paydays, day)),
+        };
+        count += i64::from(falls_on);
+        date = day.next_day();
+    }
+    count
+}
+
+pub fn is_payday(paydays: &Paydays, date: Date) -> bool {
+    match *paydays {
+        Paydays::Every { start, days } => {
+            (date - start).whole_days().rem_euclid(i64::from(days)) == 0
+        }
+        Paydays::SemiMonthly(first, second) => {
+            let last = date.month().length(date.year());
+            date.day() == first.min(last) || date.day() == second.min(last)
+        }
+    }
+}
+
+/// The first `weekday` after `date`, or with `forward` false the last one
+/// before it, or `None` if that is out of the supported date range.
+pub fn next_weekday(date: Date, weekday: Weekday, forward: bool) -> Option<Date> {
+    let ahead = i64::from(weekday.number_days_from_monday())
+        - i64::from(date.weekday().number_days_from_monday());
+    let days = match (forward, ahead.rem_euclid(7)) {
+        (true, 0) => 7,
+        (true, days) => days,
+        (false, 0) => -7,
+        (false, days) => days - 7,
+    };
+    date.checked_add(Duration::days(days))
+}
+
+/// The first payday after `date`, or with `forward` false the last one
+/// before it, or `None` if that is out of the supported date range.
+pub fn next_payday(paydays: &Paydays, date: Date, forward: bool) -> Option<Date> {
+    let step = if forward { 1 } else { -1 };
+    if let Paydays::Every { start, days } = *paydays {
+        let days = i64::from(days);
+        let offset = (date - start).whole_days().rem_euclid(days);
+        let distance = match (forward, offset) {
+            (true, _) => days - offset,
+            (false, 0) => days,
+            (false, _) => offset,
+        };
+        return date.checked_add(Duration::days(step * distance));
+    }
+
+    // Semi-monthly paydays are never more than a month apart.
+    let mut date = date;
+    for _ in 0..31 {
+        date = date.checked_add(Duration::days(step))?;
+        if is_payday(paydays, date) {
+            return Some(date);
+        }
+    }
+    None
+}
+
 pub fn add_datetime_working_days(
     datetime: OffsetDateTime,
     days: i64,
     calendar: &Calendar,
-) -> OffsetDateTime {
-    let date = add_working_days(datetime.date(), days, calendar);
-    OffsetDateTime::new_in_offset(date, datetime.time(), datetime.offset())
+) -> Option<OffsetDateTime> {
+    let date = add_working_days(datetime.date(), days, calendar)?;
+    Some(datetime.replace_date(date))
 }
 
-pub fn add_working_days(mut date: Date, days: i64, calendar: &Calendar) -> Date {
+/// Moves `date` by `days` working days, or returns `None` if that leaves the
+/// supported date range.
+pub fn add_working_days(mut date: Date, days: i64, calendar: &Calendar) -> Option<Date> {
     let step = if days >= 0 { 1 } else { -1 };
-    let mut remaining = days.abs();
+    let mut remaining = days.unsigned_abs();
 
     while remaining > 0 {
-        date += Duration::days(step);
+        date = date.checked_add(Duration::days(step))?;
 
         if calendar.is_working_day(date) {
             remaining -= 1;
         }
     }
 
-    date
+    Some(date)
+}
+
+/// Returns the first day of the week (Monday), month, or year containing
+/// `date`, shifted by `offset` whole periods.
+pub fn start_of_period(date: Date, period: &Period, offset: i32) -> Option<Date> {
+    match period {
+        Period::Week => {
+            let monday = date.checked_sub(Duration::days(
+                date.weekday().number_days_from_monday().into(),
+            ))?;
+            monday.checked_add(Duration::weeks(offset.into()))
+        }
+        Period::Month => {
+            let months = date.year() * 12 + i32::from(u8::from(date.month())) - 1 + offset;
+            let month = Month::try_from((months.rem_euclid(12) + 1) as u8).ok()?;
+            Date::from_calendar_date(months.div_euclid(12), month, 1).ok()
+        }
+        Period::Year => Date::from_calendar_date(date.year() + offset, Month::January, 1).ok(),
+    }
+}
+
+/// What calendar month arithmetic does when the target month is too short
+/// for the day, as in January 31 plus a month or February 29 plus a year.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum MissingDay {
+    /// Use the last day of the month: February 29 plus a year is February 28.
+    #[default]
+    Clamp,
+    /// Carry the extra days into the next month: February 29 plus a year is
+    /// March 1.
+    Roll,
+    /// Fail with [`EvalError::MissingDay`].
+    Error,
 }
 
-pub fn date_from_parts(year: u32, month: u8, day: u8) -> Result<Date, EvalError> {
+impl FromStr for MissingDay {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "clamp" => Ok(MissingDay::Clamp),
+            "roll" => Ok(MissingDay::Roll),
+            "error" => Ok(MissingDay::Error),
+            _ => Err(format!(
+                "invalid missing day policy '{}', expected clamp, roll or error",
+                value
+            )),
+        }
+    }
+}
+
+/// The number of the sprint `date` falls in.
+pub fn sprint_number(sprints: &Sprints, date: Date) -> i64 {
+    (date - sprints.start)
+        .whole_days()
+        .div_euclid(i64::from(sprints.length))
+        + 1
+}
+
+/// The first or last day of sprint `number`, or `None` if that is out of the
+/// supported date range.
+pub fn sprint_day(sprints: &Sprints, number: i64, edge: SprintEdge) -> Option<Date> {
+    let length = i64::from(sprints.length);
+    let days = number.checked_sub(1)?.checked_mul(length)?;
+    let days = match edge {
+        SprintEdge::Start => days,
+        SprintEdge::End => days.checked_add(length - 1)?,
+    };
+    sprints
+        .start
+        .checked_add(Duration::seconds(days.checked_mul(86_400)?))
+}
+
+/// Shifts `date` by whole calendar months, handling a day the target month
+/// lacks as `missing_day` says. `None` means the result is out of range.
+pub fn add_months(
+    date: Date,
+    months: i64,
+    missing_day: MissingDay,
+) -> Result<Option<Date>, EvalError> {
+    let index = i64::from(date.year()) * 12 + i64::from(u8::from(date.month())) - 1;
+    let Some(index) = index.checked_add(months) else {
+        return Ok(None);
+    };
+    let Ok(year) = i32::try_from(index.div_euclid(12)) else {
+        return Ok(None);
+    };
+    let month = Month::try_from((index.rem_euclid(12) + 1) as u8).expect("valid month");
+
+    let length = month.length(year);
+    if date.day() <= length {
+        return Ok(Date::from_calendar_date(year, month, date.day()).ok());
+    }
+    match missing_day {
+        MissingDay::Clamp => Ok(Date::from_calendar_date(year, month, length).ok()),
+        MissingDay::Roll => Ok(Date::from_calendar_date(year, month, length)
+            .ok()
+            .and_then(|end| end.checked_add(Duration::days(i64::from(date.day() - length))))),
+        MissingDay::Error => Err(EvalError::MissingDay(year, month, date.day())),
+    }
+}
+
+pub fn date_from_parts(year: i32, month: u8, day: u8) -> Result<Date, EvalError> {
     let month = Month::try_from(month).map_err(|_| EvalError::Month(month))?;
-    Date::from_calendar_date(
-        year.try_into()
-            .map_err(|_| EvalError::Date(year, month.into(), day))?,
-        month,
-        day,
-    )
-    .map_err(|_| EvalError::Date(year, month.into(), day))
+    Date::from_calendar_date(year, month, day).map_err(|_| EvalError::Date(year, month.into(), day))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: Month, day: u8) -> Date {
+        Date::from_calendar_date(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn start_of_next_week_is_monday() {
+        let thursday = date(2024, Month::July, 4);
+        assert_eq!(
+            start_of_period(thursday, &Period::Week, 1),
+            Some(date(2024, Month::July, 8))
+        );
+    }
+
+    #[test]
+    fn start_of_last_month_crosses_year() {
+        let start = date(2024, Month::January, 15);
+        assert_eq!(
+            start_of_period(start, &Period::Month, -1),
+            Some(date(2023, Month::December, 1))
+        );
+    }
+
+    #[test]
+    fn start_of_next_year() {
+        let start = date(2024, Month::July, 4);
+        assert_eq!(
+            start_of_period(start, &Period::Year, 1),
+            Some(date(2025, Month::January, 1))
+        );
+    }
+
+    #[test]
+    fn add_months_clamps_to_month_end() {
+        let start = date(2024, Month::January, 31);
+        let add = |months| add_months(start, months, MissingDay::Clamp).unwrap();
+        assert_eq!(add(1), Some(date(2024, Month::February, 29)));
+        assert_eq!(add(-2), Some(date(2023, Month::November, 30)));
+        assert_eq!(add(13), Some(date(2025, Month::February, 28)));
+    }
+
+    #[test]
+    fn add_months_rolls_or_refuses_missing_days() {
+        let leap_day = date(2024, Month::February, 29);
+        assert_eq!(
+            add_months(leap_day, 12, MissingDay::Roll).unwrap(),
+            Some(date(2025, Month::March, 1))
+        );
+        assert_eq!(
+            add_months(date(2024, Month::January, 31), 1, MissingDay::Roll).unwrap(),
+            Some(date(2024, Month::March, 2))
+        );
+        assert_eq!(
+            add_months(leap_day, 48, MissingDay::Error).unwrap(),
+            Some(date(2028, Month::February, 29))
+        );
+        assert!(matches!(
+            add_months(leap_day, 12, MissingDay::Error),
+            Err(EvalError::MissingDay(2025, Month::February, 29))
+        ));
+    }
+
+    #[test]
+    fn sprint_day_bounds_each_sprint() {
+        let sprints = Sprints {
+            start: date(2024, Month::January, 1),
+            length: 14,
+        };
+        assert_eq!(
+            sprint_day(&sprints, 2, SprintEdge::Start),
+            Some(date(2024, Month::January, 15))
+        );
+        assert_eq!(
+            sprint_day(&sprints, 2, SprintEdge::End),
+            Some(date(2024, Month::January, 28))
+        );
+        assert_eq!(
+            sprint_day(&sprints, 1_000_000_000_000_000, SprintEdge::Start),
+            None
+        );
+        assert_eq!(sprint_day(&sprints, i64::MIN, SprintEdge::End), None);
+    }
 }
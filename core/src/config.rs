@@ -0,0 +1,656 @@
+//! Reads [`Options`] and holiday [`Calendar`]s from TOML files.
+
+use toml::Value as TomlValue;
+
+use crate::add_holiday_to_calendar;
+use crate::calendar::{Calendar, date_from_parts};
+use crate::evaluator::Value;
+use crate::lexer::Lexer;
+use crate::options::{Options, Paydays, Sprints};
+use crate::parser::{Expr, Unit, parse};
+use crate::rotation::Rotation;
+
+pub fn options_from_toml(input: &str) -> Result<Options, String> {
+    let value = input
+        .parse::<TomlValue>()
+        .map_err(|err| format!("failed to parse config file: {}", err))?;
+
+    let mut options = Options::default();
+
+    if let Some(hour_format) = config_setting(&value, "hour_format")? {
+        options.hour_format = hour_format;
+    }
+    if let Some(locale) = config_setting(&value, "locale")? {
+        options.locale = Some(locale);
+    }
+    if let Some(date_style) = config_setting(&value, "date_style")? {
+        options.date_style = date_style;
+    }
+    if let Some(show_weekday) = value.get("show_weekday") {
+        options.show_weekday = show_weekday
+            .as_bool()
+            .ok_or_else(|| "config show_weekday must be a boolean".to_string())?;
+    }
+    if let Some(ascii) = value.get("ascii") {
+        options.ascii = ascii
+            .as_bool()
+            .ok_or_else(|| "config ascii must be a boolean".to_string())?;
+    }
+    if let Some(duration_style) = config_setting(&value, "duration_style")? {
+        options.duration_style = duration_style;
+    }
+    if let Some(precision) = config_setting(&value, "precision")? {
+        options.precision = precision;
+    }
+    if let Some(syntax) = config_setting(&value, "syntax")? {
+        options.syntax = syntax;
+    }
+    if let Some(date_order) = config_setting(&value, "date_order")? {
+        options.date_order = date_order;
+    }
+    if let Some(two_digit_years) = config_setting(&value, "two_digit_years")? {
+        options.two_digit_years = two_digit_years;
+    }
+    if let Some(assume_missing) = config_setting(&value, "assume_missing")? {
+        options.assume_missing = assume_missing;
+    }
+    if let Some(month_arithmetic) = config_setting(&value, "month_arithmetic")? {
+        options.month_arithmetic = month_arithmetic;
+    }
+    if let Some(missing_day) = config_setting(&value, "missing_day")? {
+        options.missing_day = missing_day;
+    }
+    if let Some(overflow) = config_setting(&value, "overflow")? {
+        options.overflow = overflow;
+    }
+
+    if let Some(day_parts) = value.get("day_parts") {
+        let day_parts = day_parts
+            .as_table()
+            .ok_or_else(|| "config day_parts must be a table".to_string())?;
+
+        for (name, time) in day_parts {
+            let slot = match name.as_str() {
+                "start" => &mut options.day_parts.start,
+                "morning" => &mut options.day_parts.morning,
+                "afternoon" => &mut options.day_parts.afternoon,
+                "evening" => &mut options.day_parts.evening,
+                "night" => &mut options.day_parts.night,
+                _ => return Err(format!("unknown day part '{}'", name)),
+            };
+            let time = time
+                .as_str()
+                .ok_or_else(|| format!("day part '{}' must be a time string", name))?;
+            *slot = parse_config_time(time)?;
+        }
+    }
+
+    if let Some(sprints) = value.get("sprints") {
+        options.sprints = Some(parse_config_sprints(sprints)?);
+    }
+    if let Some(paydays) = value.get("paydays") {
+        options.paydays = Some(parse_config_paydays(paydays)?);
+    }
+    if let Some(rotation) = value.get("rotation") {
+        options.rotation = Some(parse_config_rotation(rotation)?);
+    }
+
+    if let Some(units) = value.get("units") {
+        let units = units
+            .as_table()
+            .ok_or_else(|| "config units must be a table".to_string())?;
+
+        for (name, length) in units {
+            let length = length
+                .as_str()
+                .ok_or_else(|| format!("unit '{}' must be a duration string", name))?;
+            let (amount, unit) = parse_config_unit(name, length)?;
+            options
+                .units
+                .define(name, &[&format!("{}s", name)], amount, unit);
+        }
+    }
+
+    if let Some(limits) = value.get("limits") {
+        let limits = limits
+            .as_table()
+            .ok_or_else(|| "config limits must be a table".to_string())?;
+
+        for (name, limit) in limits {
+            let slot = match name.as_str() {
+                "max_depth" => &mut options.limits.max_depth,
+                "max_operators" => &mut options.limits.max_operators,
+                "max_input_length" => &mut options.limits.max_input_length,
+                "max_tokens" => &mut options.limits.max_tokens,
+                "max_iterations" => &mut options.limits.max_iterations,
+                _ => return Err(format!("unknown limit '{}'", name)),
+            };
+            *slot = limit
+                .as_integer()
+                .and_then(|limit| usize::try_from(limit).ok())
+                .ok_or_else(|| format!("limit '{}' must be a non-negative integer", name))?;
+        }
+    }
+
+    Ok(options)
+}
+
+fn config_setting<T>(config: &TomlValue, key: &str) -> Result<Option<T>, String>
+where
+    T: std::str::FromStr<Err = String>,
+{
+    match config.get(key) {
+        Some(setting) => setting
+            .as_str()
+            .ok_or_else(|| format!("config {} must be a string", key))?
+            .parse()
+            .map(Some),
+        None => Ok(None),
+    }
+}
+
+fn parse_config_time(time: &str) -> Result<time::Time, String> {
+    let tokens = Lexer::new(time);
+    let ast = parse(tokens).map_err(|err| format!("failed to parse time '{}': {}", time, err))?;
+
+    match ast {
+        Expr::Time(hour, minute) => time::Time::from_hms(hour, minute, 0)
+            .map_err(|err| format!("invalid time '{}': {}", time, err)),
+        _ => Err(format!("'{}' must be a time", time)),
+    }
+}
+
+/// Reads a `[sprints]` table with the first day of sprint 1 as `start` and a
+/// whole number of days or weeks as `length`.
+fn parse_config_sprints(sprints: &TomlValue) -> Result<Sprints, String> {
+    let setting = |key: &str| {
+        sprints
+            .get(key)
+            .and_then(TomlValue::as_str)
+            .ok_or_else(|| format!("config sprints must have a string {}", key))
+    };
+
+    Ok(Sprints {
+        start: parse_config_date(setting("start")?, "sprint start")?,
+        length: parse_config_days(setting("length")?, "sprint length")?,
+    })
+}
+
+/// Reads a `[paydays]` table: either a `start` date and an `every` length
+/// for a fixed cycle, or the two `days_of_month` of a semi-monthly one.
+fn parse_config_paydays(paydays: &TomlValue) -> Result<Paydays, String> {
+    if let Some(days) = paydays.get("days_of_month") {
+        let days = days
+            .as_array()
+            .map(|days| {
+                days.iter()
+                    .map(|day| day.as_integer().and_then(|day| u8::try_from(day).ok()))
+                    .collect::<Option<Vec<_>>>()
+            })
+            .unwrap_or_default();
+        return match days.as_deref() {
+            Some(&[first, second]) if (1..=31).contains(&first) && (1..=31).contains(&second) => {
+                Ok(Paydays::SemiMonthly(first.min(second), first.max(second)))
+            }
+            _ => Err("config paydays days_of_month must be two days from 1 to 31".to_string()),
+        };
+    }
+
+    let setting = |key: &str| {
+        paydays
+            .get(key)
+            .and_then(TomlValue::as_str)
+            .ok_or_else(|| format!("config paydays must have a string {}", key))
+    };
+    Ok(Paydays::Every {
+        start: parse_config_date(setting("start")?, "payday start")?,
+        days: parse_config_days(setting("every")?, "payday interval")?,
+    })
+}
+
+/// Reads a `[rotation]` table with the `participants` in order, the `start`
+/// of the first one's first shift and how long each shift lasts as `every`.
+/// A start without an offset or a zone is in UTC.
+fn parse_config_rotation(rotation: &TomlValue) -> Result<Rotation, String> {
+    let participants = rotation
+        .get("participants")
+        .and_then(TomlValue::as_array)
+        .and_then(|participants| {
+            participants
+                .iter()
+                .map(|name| name.as_str().map(String::from))
+                .collect::<Option<Vec<_>>>()
+        })
+        .filter(|participants| !participants.is_empty())
+        .ok_or_else(|| "config rotation participants must be a list of names".to_string())?;
+    let setting = |key: &str| {
+        let input = rotation
+            .get(key)
+            .and_then(TomlValue::as_str)
+            .ok_or_else(|| format!("config rotation must have a string {}", key))?;
+        crate::compile(input)
+            .and_then(|compiled| compiled.eval_pure(&Options::default()))
+            .map_err(|err| format!("invalid rotation {} '{}': {}", key, input, err))
+    };
+
+    let start = match setting("start")? {
+        Value::Date(date) => date.midnight().assume_utc(),
+        Value::DateTime(start) | Value::Zoned(start, _) => start,
+        _ => return Err("rotation start must be a date or a date and time".to_string()),
+    };
+    match setting("every")? {
+        Value::Duration(period) if period.is_positive() => Ok(Rotation {
+            participants,
+            start,
+            period,
+        }),
+        _ => Err("rotation every must be a positive duration".to_string()),
+    }
+}
+
+/// Reads the length of a unit named in the `[units]` table, a positive
+/// duration in built-in units such as `25 minutes`.
+fn parse_config_unit(name: &str, length: &str) -> Result<(i64, Unit), String> {
+    if name.is_empty() || !name.chars().all(char::is_alphabetic) {
+        return Err(format!("unit name '{}' must be a single word", name));
+    }
+    match parse(Lexer::new(length)) {
+        Ok(Expr::Duration(amount, unit)) if amount > 0 => Ok((amount, unit)),
+        _ => Err(format!(
+            "unit '{}' length '{}' must be a positive duration",
+            name, length
+        )),
+    }
+}
+
+fn parse_config_date(date: &str, name: &str) -> Result<time::Date, String> {
+    match parse(Lexer::new(date)) {
+        Ok(Expr::Date(year, month, day)) => date_from_parts(year, month, day)
+            .map_err(|err| format!("invalid {} '{}': {}", name, date, err)),
+        _ => Err(format!("{} '{}' must be a date", name, date)),
+    }
+}
+
+/// Reads a whole, positive number of days from a duration such as `2w`.
+fn parse_config_days(length: &str, name: &str) -> Result<u32, String> {
+    const DAY: i64 = 24 * 3600 * 1_000_000_000;
+    let days = match parse(Lexer::new(length)) {
+        Ok(Expr::Duration(value, unit)) => unit
+            .nanoseconds()
+            .and_then(|nanos| value.checked_mul(nanos))
+            .filter(|nanos| nanos % DAY == 0)
+            .and_then(|nanos| u32::try_from(nanos / DAY).ok())
+            .filter(|days| *days > 0),
+        _ => None,
+    };
+    days.ok_or_else(|| {
+        format!(
+            "{} '{}' must be a positive number of days or weeks",
+            name, length
+        )
+    })
+}
+
+pub fn calendar_from_toml(input: &str, calendar_name: Option<&str>) -> Result<Calendar, String> {
+    let value = input
+        .parse::<TomlValue>()
+        .map_err(|err| format!("failed to parse calendar file: {}", err))?;
+
+    let table = match calendar_name {
+        Some(name) => value
+            .get(name)
+            .ok_or_else(|| format!("calendar '{}' not found", name))?,
+        None => &value,
+    };
+
+    let holidays = table
+        .get("holidays")
+        .ok_or_else(|| missing_holidays_error(calendar_name))?
+        .as_array()
+        .ok_or_else(|| holidays_type_error(calendar_name))?;
+
+    let mut calendar = Calendar::new();
+    for holiday in holidays {
+        let holiday = holiday
+            .as_str()
+            .ok_or_else(|| holidays_type_error(calendar_name))?;
+        add_holiday_to_calendar(&mut calendar, holiday)?;
+    }
+
+    Ok(calendar)
+}
+
+fn missing_holidays_error(calendar_name: Option<&str>) -> String {
+    match calendar_name {
+        Some(name) => format!("calendar '{}' must define holidays", name),
+        None => "calendar file must define top-level holidays or use --calendar-name".to_string(),
+    }
+}
+
+fn holidays_type_error(calendar_name: Option<&str>) -> String {
+    match calendar_name {
+        Some(name) => format!(
+            "calendar '{}' holidays must be an array of date strings",
+            name
+        ),
+        None => "calendar holidays must be an array of date strings".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{run, run_with_options};
+
+    #[test]
+    fn options_from_toml_overrides_day_parts() {
+        let options = options_from_toml(
+            r#"
+            [day_parts]
+            start = "06:00"
+            night = "22:30"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(options.day_parts.start.hour(), 6);
+        assert_eq!(options.day_parts.night.hour(), 22);
+        assert_eq!(options.day_parts.night.minute(), 30);
+    }
+
+    #[test]
+    fn options_from_toml_reads_hour_format() {
+        let options = options_from_toml(r#"hour_format = "12""#).unwrap();
+
+        let result = run_with_options("14:30", &options).unwrap();
+
+        assert_eq!(result, "2:30 PM");
+    }
+
+    #[test]
+    fn options_from_toml_reads_show_weekday() {
+        let options = options_from_toml("show_weekday = true").unwrap();
+
+        let result = run_with_options("2024/07/04", &options).unwrap();
+
+        assert_eq!(result, "2024-07-04 (Thursday)");
+        assert!(options_from_toml(r#"show_weekday = "yes""#).is_err());
+        assert!(options_from_toml("ascii = true").unwrap().ascii);
+    }
+
+    #[test]
+    #[cfg(feature = "locales")]
+    fn options_from_toml_reads_locale() {
+        let options = options_from_toml(
+            r#"
+            locale = "it-IT"
+            date_style = "long"
+            "#,
+        )
+        .unwrap();
+
+        let result = run_with_options("2024/01/31", &options).unwrap();
+
+        assert_eq!(result, "mercoledì 31 gennaio 2024");
+    }
+
+    #[test]
+    fn options_from_toml_reads_date_order() {
+        let options = options_from_toml(r#"date_order = "dmy""#).unwrap();
+
+        let result = run_with_options("03/04/2024", &options).unwrap();
+
+        assert_eq!(result, "2024-04-03");
+    }
+
+    #[test]
+    fn options_from_toml_reads_two_digit_year_pivot() {
+        let options = options_from_toml(r#"two_digit_years = "70""#).unwrap();
+
+        let result = run_with_options("24/3/5", &options).unwrap();
+
+        assert_eq!(result, "2024-03-05");
+    }
+
+    #[test]
+    fn options_from_toml_reads_syntax() {
+        let options = options_from_toml(r#"syntax = "gnu""#).unwrap();
+
+        let result = run_with_options("2024-03-01 2 days ago", &options).unwrap();
+
+        assert_eq!(result, "2024-02-28");
+    }
+
+    #[test]
+    fn options_from_toml_reads_assume_missing() {
+        let options = options_from_toml(r#"assume_missing = "current""#).unwrap();
+
+        let result = run_with_options("jan 5", &options).unwrap();
+
+        assert!(result.ends_with("-01-05"));
+    }
+
+    #[test]
+    fn options_from_toml_reads_units() {
+        let options = options_from_toml(
+            r#"
+            [units]
+            pomodoro = "25 minutes"
+            sprint = "2 weeks"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(run_with_options("3 pomodoros", &options).unwrap(), "1h15m");
+        assert_eq!(
+            run_with_options("2024/03/01 + 1 sprint", &options).unwrap(),
+            "2024-03-15"
+        );
+        assert!(options_from_toml("[units]\nsprint = \"-2w\"").is_err());
+        assert!(options_from_toml("[units]\n\"two words\" = \"2w\"").is_err());
+    }
+
+    #[test]
+    fn options_from_toml_reads_sprints() {
+        let mut options = options_from_toml(
+            r#"
+            [sprints]
+            start = "2025/01/06"
+            length = "2w"
+            "#,
+        )
+        .unwrap();
+        options.now = Some(
+            time::Date::from_calendar_date(2025, time::Month::February, 5)
+                .unwrap()
+                .midnight()
+                .assume_utc(),
+        );
+
+        assert_eq!(
+            run_with_options("sprint 3", &options).unwrap(),
+            "2025-02-03"
+        );
+        assert_eq!(
+            run_with_options("start of sprint", &options).unwrap(),
+            "2025-02-03"
+        );
+        assert_eq!(
+            run_with_options("end of sprint", &options).unwrap(),
+            "2025-02-16"
+        );
+        assert_eq!(
+            run_with_options("end of sprint 0", &options).unwrap(),
+            "2025-01-05"
+        );
+
+        assert!(options_from_toml("[sprints]\nstart = \"2025/01/06\"\nlength = \"36h\"").is_err());
+        assert!(run_with_options("sprint 3", &Options::default()).is_err());
+    }
+
+    #[test]
+    fn options_from_toml_reads_rotation() {
+        let options = options_from_toml(
+            r#"
+            [rotation]
+            participants = ["alice", "bob"]
+            start = "2024/10/14 09:00"
+            every = "1w"
+            "#,
+        )
+        .unwrap();
+        let rotation = options.rotation.unwrap();
+        let at = time::Date::from_calendar_date(2024, time::Month::October, 22)
+            .unwrap()
+            .midnight()
+            .assume_utc();
+
+        assert_eq!(rotation.participants, ["alice", "bob"]);
+        assert_eq!(rotation.on_call(at).unwrap().0, "bob");
+        assert_eq!(rotation.period, time::Duration::WEEK);
+
+        let missing = "[rotation]\nparticipants = []\nstart = \"2024/10/14\"\nevery = \"1w\"";
+        assert!(options_from_toml(missing).is_err());
+        let relative = "[rotation]\nparticipants = [\"a\"]\nstart = \"today\"\nevery = \"1w\"";
+        assert!(options_from_toml(relative).is_err());
+        let calendar =
+            "[rotation]\nparticipants = [\"a\"]\nstart = \"2024/10/14\"\nevery = \"1 month\"";
+        assert!(options_from_toml(calendar).is_err());
+    }
+
+    #[test]
+    fn options_from_toml_reads_paydays() {
+        let today = time::Date::from_calendar_date(2025, time::Month::February, 14).unwrap();
+        let mut biweekly = options_from_toml(
+            r#"
+            [paydays]
+            start = "2025/01/03"
+            every = "2w"
+            "#,
+        )
+        .unwrap();
+        biweekly.now = Some(today.midnight().assume_utc());
+
+        assert_eq!(
+            run_with_options("next payday", &biweekly).unwrap(),
+            "2025-02-28"
+        );
+        assert_eq!(
+            run_with_options("last payday", &biweekly).unwrap(),
+            "2025-01-31"
+        );
+        assert_eq!(
+            run_with_options("paydays in 2025", &biweekly).unwrap(),
+            "26"
+        );
+        assert_eq!(
+            run_with_options(
+                "sum(1d every payday from 2025/01/01 to 2025/01/31)",
+                &biweekly
+            )
+            .unwrap(),
+            "3d"
+        );
+
+        let mut semi_monthly = options_from_toml("[paydays]\ndays_of_month = [31, 15]").unwrap();
+        semi_monthly.now = Some(today.midnight().assume_utc());
+        assert_eq!(
+            run_with_options("next payday", &semi_monthly).unwrap(),
+            "2025-02-15"
+        );
+        assert_eq!(
+            run_with_options("last payday", &semi_monthly).unwrap(),
+            "2025-01-31"
+        );
+        assert_eq!(
+            run_with_options("paydays in 2025/02", &semi_monthly).unwrap(),
+            "2"
+        );
+
+        assert!(options_from_toml("[paydays]\ndays_of_month = [15]").is_err());
+        assert!(run_with_options("next payday", &Options::default()).is_err());
+    }
+
+    #[test]
+    fn options_from_toml_reads_month_arithmetic() {
+        let options = options_from_toml(r#"month_arithmetic = "calendar""#).unwrap();
+
+        let result = run_with_options("2024/03/31 - 1 month", &options).unwrap();
+
+        assert_eq!(result, "2024-02-29");
+    }
+
+    #[test]
+    fn options_from_toml_reads_limits() {
+        let options = options_from_toml(
+            r#"
+            [limits]
+            max_depth = 3
+            max_operators = 4
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(options.limits.max_depth, 3);
+        assert!(run_with_options("((1h))", &options).is_ok());
+        assert!(run_with_options("(((1h)))", &options).is_err());
+        assert!(run_with_options("1h + 1h + 1h + 1h + 1h", &options).is_ok());
+        assert!(run_with_options("1h + 1h + 1h + 1h + 1h + 1h", &options).is_err());
+    }
+
+    #[test]
+    fn options_from_toml_rejects_unknown_day_part() {
+        let result = options_from_toml(
+            r#"
+            [day_parts]
+            noon = "12:00"
+            "#,
+        );
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn calendar_from_toml_reads_top_level_holidays() {
+        let calendar = calendar_from_toml(
+            r#"
+            holidays = ["2024/04/29"]
+            "#,
+            None,
+        )
+        .unwrap();
+
+        let result = run("2024/04/26 + 1wd", Some(&calendar)).unwrap();
+
+        assert_eq!(result, "2024-04-30");
+    }
+
+    #[test]
+    fn calendar_from_toml_reads_named_calendar() {
+        let calendar = calendar_from_toml(
+            r#"
+            [italy]
+            holidays = ["2024/04/29"]
+            "#,
+            Some("italy"),
+        )
+        .unwrap();
+
+        let result = run("2024/04/26 + 1wd", Some(&calendar)).unwrap();
+
+        assert_eq!(result, "2024-04-30");
+    }
+
+    #[test]
+    fn calendar_from_toml_requires_name_for_named_calendar_only_file() {
+        let result = calendar_from_toml(
+            r#"
+            [italy]
+            holidays = ["2024/04/29"]
+            "#,
+            None,
+        );
+
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,299 @@
+//! Conversions between [`Value`](crate::Value) and the date and time types of other crates.
+
+use core::fmt;
+use core::time::Duration as StdDuration;
+#[cfg(feature = "std")]
+use std::time::SystemTime;
+
+use time::Duration;
+#[cfg(feature = "std")]
+use time::OffsetDateTime;
+
+use crate::evaluator::Value;
+
+#[derive(Debug, PartialEq)]
+pub enum ConvertError {
+    /// The value has a different type than the conversion target.
+    Type {
+        expected: &'static str,
+        found: &'static str,
+    },
+    /// The value does not fit in the target type.
+    OutOfRange,
+    /// The duration is negative and the target cannot represent it.
+    Negative,
+}
+
+/// How [`Value::to_std_duration`] treats negative durations, which
+/// `std::time::Duration` cannot represent.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum NegativeDuration {
+    /// Fail with [`ConvertError::Negative`].
+    #[default]
+    Reject,
+    /// Use the magnitude of the duration.
+    Abs,
+    /// Clamp to zero.
+    Zero,
+}
+
+impl fmt::Display for ConvertError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConvertError::Type { expected, found } => {
+                write!(f, "expected '{}', found '{}'", expected, found)
+            }
+            ConvertError::OutOfRange => write!(f, "value out of range"),
+            ConvertError::Negative => write!(f, "negative duration"),
+        }
+    }
+}
+
+impl core::error::Error for ConvertError {}
+
+fn type_error(expected: &'static str, value: &Value) -> ConvertError {
+    ConvertError::Type {
+        expected,
+        found: value.type_name(),
+    }
+}
+
+impl Value {
+    /// Converts a duration to `std::time::Duration`, handling negative
+    /// durations according to `negative`. Month counts use the usual
+    /// 30-day approximation.
+    pub fn to_std_duration(&self, negative: NegativeDuration) -> Result<StdDuration, ConvertError> {
+        let duration = match self.approximate() {
+            Value::Duration(duration) => duration,
+            _ => return Err(type_error("Duration", self)),
+        };
+
+        match (duration.is_negative(), negative) {
+            (true, NegativeDuration::Reject) => Err(ConvertError::Negative),
+            (true, NegativeDuration::Zero) => Ok(StdDuration::ZERO),
+            _ => Ok(duration.unsigned_abs()),
+        }
+    }
+}
+
+impl TryFrom<StdDuration> for Value {
+    type Error = ConvertError;
+
+    fn try_from(duration: StdDuration) -> Result<Self, Self::Error> {
+        Duration::try_from(duration)
+            .map(Value::Duration)
+            .map_err(|_| ConvertError::OutOfRange)
+    }
+}
+
+impl TryFrom<Value> for StdDuration {
+    type Error = ConvertError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        value.to_std_duration(NegativeDuration::Reject)
+    }
+}
+
+#[cfg(feature = "std")]
+impl TryFrom<SystemTime> for Value {
+    type Error = ConvertError;
+
+    fn try_from(time: SystemTime) -> Result<Self, Self::Error> {
+        let offset = match time.duration_since(SystemTime::UNIX_EPOCH) {
+            Ok(after) => Duration::try_from(after),
+            Err(before) => Duration::try_from(before.duration()).map(|before| -before),
+        }
+        .map_err(|_| ConvertError::OutOfRange)?;
+
+        OffsetDateTime::UNIX_EPOCH
+            .checked_add(offset)
+            .map(Value::DateTime)
+            .ok_or(ConvertError::OutOfRange)
+    }
+}
+
+#[cfg(feature = "std")]
+impl TryFrom<Value> for SystemTime {
+    type Error = ConvertError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        let datetime = match value {
+            Value::DateTime(datetime) | Value::Zoned(datetime, _) => datetime,
+            _ => return Err(type_error("DateTime", &value)),
+        };
+
+        let offset = datetime - OffsetDateTime::UNIX_EPOCH;
+        let magnitude = offset.unsigned_abs();
+        let time = if offset.is_negative() {
+            SystemTime::UNIX_EPOCH.checked_sub(magnitude)
+        } else {
+            SystemTime::UNIX_EPOCH.checked_add(magnitude)
+        };
+        time.ok_or(ConvertError::OutOfRange)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_std_duration_round_trip() {
+        let duration = StdDuration::from_millis(90_500);
+        let value = Value::try_from(duration).unwrap();
+        assert_eq!(StdDuration::try_from(value), Ok(duration));
+    }
+
+    #[test]
+    fn test_negative_std_duration_policy() {
+        let value = Value::Duration(Duration::seconds(-5));
+        assert_eq!(
+            StdDuration::try_from(value.clone()),
+            Err(ConvertError::Negative)
+        );
+        assert_eq!(
+            value.to_std_duration(NegativeDuration::Abs),
+            Ok(StdDuration::from_secs(5))
+        );
+        assert_eq!(
+            value.to_std_duration(NegativeDuration::Zero),
+            Ok(StdDuration::ZERO)
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_system_time_round_trip() {
+        let time = SystemTime::UNIX_EPOCH - StdDuration::from_secs(86_400);
+        let value = Value::try_from(time).unwrap();
+        assert_eq!(value.to_string(), "1969-12-31 00:00 +00:00");
+        assert_eq!(SystemTime::try_from(value), Ok(time));
+    }
+}
+
+#[cfg(feature = "chrono")]
+mod chrono_interop {
+    use ::chrono::{DateTime, Datelike, NaiveDate, TimeDelta, Utc};
+    use time::{Date, Duration, Month, OffsetDateTime};
+
+    use super::{ConvertError, type_error};
+    use crate::evaluator::Value;
+
+    impl TryFrom<NaiveDate> for Value {
+        type Error = ConvertError;
+
+        fn try_from(date: NaiveDate) -> Result<Self, Self::Error> {
+            let month =
+                Month::try_from(date.month() as u8).map_err(|_| ConvertError::OutOfRange)?;
+            Date::from_calendar_date(date.year(), month, date.day() as u8)
+                .map(Value::Date)
+                .map_err(|_| ConvertError::OutOfRange)
+        }
+    }
+
+    impl TryFrom<Value> for NaiveDate {
+        type Error = ConvertError;
+
+        fn try_from(value: Value) -> Result<Self, Self::Error> {
+            match value {
+                Value::Date(date) => NaiveDate::from_ymd_opt(
+                    date.year(),
+                    u8::from(date.month()).into(),
+                    date.day().into(),
+                )
+                .ok_or(ConvertError::OutOfRange),
+                _ => Err(type_error("Date", &value)),
+            }
+        }
+    }
+
+    impl TryFrom<DateTime<Utc>> for Value {
+        type Error = ConvertError;
+
+        fn try_from(datetime: DateTime<Utc>) -> Result<Self, Self::Error> {
+            let seconds = OffsetDateTime::from_unix_timestamp(datetime.timestamp())
+                .map_err(|_| ConvertError::OutOfRange)?;
+            let nanos = Duration::nanoseconds(datetime.timestamp_subsec_nanos().into());
+            Ok(Value::DateTime(seconds + nanos))
+        }
+    }
+
+    impl TryFrom<Value> for DateTime<Utc> {
+        type Error = ConvertError;
+
+        fn try_from(value: Value) -> Result<Self, Self::Error> {
+            match value {
+                Value::DateTime(datetime) | Value::Zoned(datetime, _) => {
+                    DateTime::from_timestamp(datetime.unix_timestamp(), datetime.nanosecond())
+                        .ok_or(ConvertError::OutOfRange)
+                }
+                _ => Err(type_error("DateTime", &value)),
+            }
+        }
+    }
+
+    impl From<TimeDelta> for Value {
+        fn from(delta: TimeDelta) -> Self {
+            Value::Duration(
+                Duration::seconds(delta.num_seconds())
+                    + Duration::nanoseconds(delta.subsec_nanos().into()),
+            )
+        }
+    }
+
+    impl TryFrom<Value> for TimeDelta {
+        type Error = ConvertError;
+
+        fn try_from(value: Value) -> Result<Self, Self::Error> {
+            match value.approximate() {
+                Value::Duration(duration) => TimeDelta::try_seconds(duration.whole_seconds())
+                    .and_then(|seconds| {
+                        seconds.checked_add(&TimeDelta::nanoseconds(
+                            duration.subsec_nanoseconds().into(),
+                        ))
+                    })
+                    .ok_or(ConvertError::OutOfRange),
+                _ => Err(type_error("Duration", &value)),
+            }
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_naive_date_round_trip() {
+            let date = NaiveDate::from_ymd_opt(2024, 2, 29).unwrap();
+            let value = Value::try_from(date).unwrap();
+            assert_eq!(value.to_string(), "2024-02-29");
+            assert_eq!(NaiveDate::try_from(value), Ok(date));
+        }
+
+        #[test]
+        fn test_datetime_round_trip() {
+            let datetime = DateTime::from_timestamp(1_700_000_000, 250).unwrap();
+            let value = Value::try_from(datetime).unwrap();
+            assert_eq!(DateTime::<Utc>::try_from(value), Ok(datetime));
+        }
+
+        #[test]
+        fn test_negative_time_delta_round_trip() {
+            let delta = TimeDelta::milliseconds(-1_500);
+            let value = Value::from(delta);
+            assert_eq!(value.to_string(), "-1s500ms");
+            assert_eq!(TimeDelta::try_from(value), Ok(delta));
+        }
+
+        #[test]
+        fn test_rejects_mismatched_value() {
+            assert_eq!(
+                NaiveDate::try_from(Value::Bool(true)),
+                Err(ConvertError::Type {
+                    expected: "Date",
+                    found: "Bool",
+                })
+            );
+        }
+    }
+}
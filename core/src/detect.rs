@@ -0,0 +1,291 @@
+//! Recognizes timestamps in the common formats found in logs and APIs, for
+//! reading them without knowing their pattern beforehand.
+
+use alloc::{format, string::String, vec::Vec};
+use core::cmp::Reverse;
+use core::fmt;
+use core::ops::Range;
+
+use time::OffsetDateTime;
+
+use crate::evaluator::{Value, Warning};
+use crate::functions::{read_pattern, read_prefix};
+use crate::locale::Locale;
+use crate::parser::TwoDigitYears;
+
+/// Nanoseconds in a millisecond.
+const NANOS_PER_MILLI: i128 = 1_000_000;
+
+/// The format a timestamp was recognized as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TimestampFormat {
+    /// `2024-03-01T14:30:00Z` and its variants: a space instead of the `T`,
+    /// fractional seconds, an offset or none, or the basic form without
+    /// separators.
+    Iso8601,
+    /// `Fri, 01 Mar 2024 14:30:00 +0000`, as in email and HTTP headers.
+    Rfc2822,
+    /// Seconds since the Unix epoch, 9 or 10 digits.
+    UnixSeconds,
+    /// Milliseconds since the Unix epoch, 12 or 13 digits.
+    UnixMilliseconds,
+    /// An ordinary tcalc expression for a date or date and time.
+    Native,
+}
+
+impl fmt::Display for TimestampFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            TimestampFormat::Iso8601 => "ISO 8601",
+            TimestampFormat::Rfc2822 => "RFC 2822",
+            TimestampFormat::UnixSeconds => "Unix seconds",
+            TimestampFormat::UnixMilliseconds => "Unix milliseconds",
+            TimestampFormat::Native => "tcalc syntax",
+        };
+        f.write_str(name)
+    }
+}
+
+/// A timestamp found by [`find_timestamps`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct FoundTimestamp {
+    /// Where in the text it was written, in bytes.
+    pub range: Range<usize>,
+    /// The instant it stands for, in UTC if it was written without an
+    /// offset.
+    pub at: OffsetDateTime,
+    pub format: TimestampFormat,
+    /// [`Warning::AssumedUtc`] when it was written without an offset.
+    pub warning: Option<Warning>,
+}
+
+/// Reads `text` as a timestamp in the first format that fits it, trying ISO
+/// 8601, then RFC 2822, then Unix seconds and milliseconds. The warning is
+/// [`Warning::AssumedUtc`] when the timestamp has no offset. Tcalc's own
+/// syntax is left to `auto(…)`, which has the options to read it with.
+pub fn detect(text: &str) -> Option<(Value, TimestampFormat, Option<Warning>)> {
+    let text = text.trim();
+    let read = |patterns: Vec<String>, format| {
+        patterns.iter().find_map(|pattern| {
+            let (value, warning) =
+                read_pattern(text, pattern, Locale::EnGb, TwoDigitYears::Strict).ok()?;
+            Some((value, format, warning))
+        })
+    };
+    read(iso_patterns(), TimestampFormat::Iso8601)
+        .or_else(|| read(rfc_patterns(), TimestampFormat::Rfc2822))
+        .or_else(|| epoch(text))
+}
+
+/// The timestamps in `text`, such as a line from a log, in the order they
+/// appear. Each starts and ends at a word boundary, and has a time of day:
+/// a date on its own is too easily something else.
+pub fn find_timestamps(text: &str) -> Vec<FoundTimestamp> {
+    let patterns = [
+        (iso_patterns(), TimestampFormat::Iso8601),
+        (rfc_patterns(), TimestampFormat::Rfc2822),
+    ];
+    let mut found = Vec::new();
+    let mut previous = None;
+    let mut end = 0;
+    for (index, c) in text.char_indices() {
+        let starts_word = c.is_alphanumeric() && !previous.is_some_and(char::is_alphanumeric);
+        previous = Some(c);
+        if index < end || !starts_word {
+            continue;
+        }
+        if let Some((at, format, warning, length)) = timestamp_at(&text[index..], &patterns) {
+            end = index + length;
+            found.push(FoundTimestamp {
+                range: index..end,
+                at,
+                format,
+                warning,
+            });
+        }
+    }
+    found
+}
+
+/// The longest timestamp at the start of `text`, the one earliest in
+/// `patterns` among those as long, with its warning and length.
+fn timestamp_at(
+    text: &str,
+    patterns: &[(Vec<String>, TimestampFormat)],
+) -> Option<(OffsetDateTime, TimestampFormat, Option<Warning>, usize)> {
+    let digits = text.bytes().take_while(u8::is_ascii_digit).count();
+    let epoch =
+        epoch(&text[..digits]).map(|(value, format, warning)| (value, format, warning, digits));
+    patterns
+        .iter()
+        .flat_map(|(patterns, format)| patterns.iter().map(move |pattern| (pattern, *format)))
+        .filter_map(|(pattern, format)| {
+            let (value, warning, length) =
+                read_prefix(text, pattern, Locale::EnGb, TwoDigitYears::Strict).ok()??;
+            Some((value, format, warning, length))
+        })
+        .chain(epoch)
+        .filter_map(|(value, format, warning, length)| match value {
+            Value::DateTime(at) => Some((at, format, warning, length)),
+            _ => None,
+        })
+        .filter(|(.., length)| {
+            !text[*length..]
+                .chars()
+                .next()
+                .is_some_and(char::is_alphanumeric)
+        })
+        .min_by_key(|(.., length)| Reverse(*length))
+}
+
+/// The ISO 8601 patterns, each with an offset before the same one without.
+fn iso_patterns() -> Vec<String> {
+    let mut patterns = Vec::new();
+    for separator in ["T", " "] {
+        for time in ["%H:%M:%S.%f", "%H:%M:%S,%f", "%H:%M:%S", "%H:%M"] {
+            for zone in [" %z", ""] {
+                patterns.push(format!("%Y-%m-%d{}{}{}", separator, time, zone));
+            }
+        }
+    }
+    patterns.extend(["%Y%m%dT%H%M%S %z", "%Y%m%dT%H%M%S", "%Y-%m-%d"].map(String::from));
+    patterns
+}
+
+fn rfc_patterns() -> Vec<String> {
+    let mut patterns = Vec::new();
+    for weekday in ["%a, ", ""] {
+        for time in ["%H:%M:%S", "%H:%M"] {
+            patterns.push(format!("{}%d %b %Y {} %z", weekday, time));
+        }
+    }
+    patterns
+}
+
+/// Reads a run of 9 or 10 digits as seconds since the epoch, and 12 or 13 as
+/// milliseconds. Other lengths are more likely counts or identifiers.
+fn epoch(text: &str) -> Option<(Value, TimestampFormat, Option<Warning>)> {
+    if !text.bytes().all(|byte| byte.is_ascii_digit()) {
+        return None;
+    }
+    let number: i128 = text.parse().ok()?;
+    let (at, format) = match text.len() {
+        9 | 10 => (
+            OffsetDateTime::from_unix_timestamp(i64::try_from(number).ok()?).ok()?,
+            TimestampFormat::UnixSeconds,
+        ),
+        12 | 13 => (
+            OffsetDateTime::from_unix_timestamp_nanos(number * NANOS_PER_MILLI).ok()?,
+            TimestampFormat::UnixMilliseconds,
+        ),
+        _ => return None,
+    };
+    Some((Value::DateTime(at), format, None))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::{Date, Month, Time, UtcOffset};
+
+    fn datetime(offset: i8, nanosecond: u32) -> OffsetDateTime {
+        let date = Date::from_calendar_date(2024, Month::March, 1).unwrap();
+        let time = Time::from_hms_nano(14, 30, 0, nanosecond).unwrap();
+        let offset = UtcOffset::from_hms(offset, 0, 0).unwrap();
+        date.with_time(time).assume_offset(offset)
+    }
+
+    /// What `detect` reads from `text`, which must be a date and time.
+    fn detected(text: &str) -> Option<(OffsetDateTime, TimestampFormat, Option<Warning>)> {
+        detect(text).map(|(value, format, warning)| match value {
+            Value::DateTime(at) => (at, format, warning),
+            value => panic!("Expected Value::DateTime, got {:?}", value),
+        })
+    }
+
+    fn format(text: &str) -> Option<TimestampFormat> {
+        detect(text).map(|(_, format, _)| format)
+    }
+
+    #[test]
+    fn test_detects_iso_8601() {
+        assert_eq!(
+            detected("2024-03-01T14:30:00Z"),
+            Some((datetime(0, 0), TimestampFormat::Iso8601, None))
+        );
+        assert_eq!(
+            detected(" 2024-03-01 14:30:00.250+02:00 "),
+            Some((datetime(2, 250_000_000), TimestampFormat::Iso8601, None))
+        );
+        assert_eq!(
+            detected("20240301T143000"),
+            Some((
+                datetime(0, 0),
+                TimestampFormat::Iso8601,
+                Some(Warning::AssumedUtc)
+            ))
+        );
+        assert_eq!(format("2024-03-01"), Some(TimestampFormat::Iso8601));
+    }
+
+    #[test]
+    fn test_detects_rfc_2822() {
+        assert_eq!(
+            detected("Fri, 01 Mar 2024 14:30:00 +0000"),
+            Some((datetime(0, 0), TimestampFormat::Rfc2822, None))
+        );
+        assert_eq!(
+            format("1 Mar 2024 14:30 GMT"),
+            Some(TimestampFormat::Rfc2822)
+        );
+        assert_eq!(format("Mon, 01 Mar 2024 14:30:00 +0000"), None);
+    }
+
+    #[test]
+    fn test_finds_timestamps_in_log_lines() {
+        let line = "2024-03-01T14:30:00.250Z GET /jobs/1709303400 done at 14:30 \
+                    Fri, 01 Mar 2024 14:30:00 +0000 (2024-03-01)";
+        let found = find_timestamps(line);
+        let written: Vec<_> = found
+            .iter()
+            .map(|found| &line[found.range.clone()])
+            .collect();
+        assert_eq!(
+            written,
+            [
+                "2024-03-01T14:30:00.250Z",
+                "1709303400",
+                "Fri, 01 Mar 2024 14:30:00 +0000"
+            ]
+        );
+        assert_eq!(found[0].at, datetime(0, 250_000_000));
+        assert_eq!(found[0].warning, None);
+        assert_eq!(found[1].format, TimestampFormat::UnixSeconds);
+        assert_eq!(found[2].format, TimestampFormat::Rfc2822);
+        assert_eq!(
+            find_timestamps("started 2024-03-01 14:30:00")[0].warning,
+            Some(Warning::AssumedUtc)
+        );
+
+        assert_eq!(find_timestamps("id 17093034001 and x2024-03-01T14:30"), []);
+    }
+
+    #[test]
+    fn test_detects_epoch_timestamps() {
+        assert_eq!(
+            detected("1709303400"),
+            Some((datetime(0, 0), TimestampFormat::UnixSeconds, None))
+        );
+        assert_eq!(
+            detected("1709303400250"),
+            Some((
+                datetime(0, 250_000_000),
+                TimestampFormat::UnixMilliseconds,
+                None
+            ))
+        );
+        assert_eq!(format("12345"), None);
+        assert_eq!(format("17093034002"), None);
+    }
+}
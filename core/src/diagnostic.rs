@@ -0,0 +1,146 @@
+//! Errors, warnings and lints as structured records located in the input,
+//! for editor plugins and CI checks.
+
+use alloc::{
+    boxed::Box,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+use core::ops::Range;
+
+use crate::evaluator::evaluate;
+use crate::lexer::Lexer;
+use crate::lint::{Lint, lint};
+use crate::options::Options;
+use crate::parser::{Expr, Op, ParsingError, parse_ast};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum Severity {
+    /// The input has no result.
+    Error,
+    /// The input has a result that may not be what was meant.
+    Warning,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// The byte range of the input the diagnostic is about. Only lexing
+    /// errors point inside the expression; everything else covers it all.
+    pub span: Range<usize>,
+    pub message: String,
+    /// The whole input rewritten to fix the problem, when a fix is known.
+    pub suggestion: Option<String>,
+}
+
+/// Parses, lints and evaluates `input`, reporting what stops it from
+/// producing a result along with every warning and lint on the way. No
+/// diagnostics means the input evaluated cleanly.
+pub fn diagnose(input: &str, options: &Options) -> Vec<Diagnostic> {
+    let start = input.len() - input.trim_start().len();
+    let whole = start..input.trim_end().len().max(start);
+    let diagnostic = |severity, span: Range<usize>, message: String| Diagnostic {
+        severity,
+        span,
+        message,
+        suggestion: None,
+    };
+
+    let ast = match parse_ast(Lexer::new(input), options) {
+        Ok(ast) => ast,
+        Err(ParsingError::Lex(err)) => {
+            return vec![diagnostic(
+                Severity::Error,
+                err.span.clone(),
+                err.to_string(),
+            )];
+        }
+        Err(err) => return vec![diagnostic(Severity::Error, whole, err.to_string())],
+    };
+
+    let expr = ast.to_expr();
+    let mut diagnostics = lint(&expr)
+        .into_iter()
+        .map(|lint| Diagnostic {
+            suggestion: fix(&lint).map(|fixed| replaced(&expr, lint.expr(), &fixed).to_string()),
+            ..diagnostic(Severity::Warning, whole.clone(), lint.to_string())
+        })
+        .collect::<Vec<_>>();
+
+    match evaluate(&ast, options, false) {
+        Ok(evaluation) => diagnostics.extend(
+            evaluation
+                .warnings
+                .iter()
+                .map(|warning| diagnostic(Severity::Warning, whole.clone(), warning.to_string())),
+        ),
+        Err(err) => diagnostics.push(diagnostic(Severity::Error, whole, err.to_string())),
+    }
+    diagnostics
+}
+
+/// What the sub-expression flagged by `lint` was likely meant to be.
+fn fix(lint: &Lint) -> Option<Expr> {
+    match lint {
+        Lint::AddedDates(Expr::BinOp(left, _, right)) => {
+            Some(Expr::BinOp(left.clone(), Op::Sub, right.clone()))
+        }
+        _ => None,
+    }
+}
+
+/// `expr` with its first sub-expression equal to `target` swapped for `with`.
+fn replaced(expr: &Expr, target: &Expr, with: &Expr) -> Expr {
+    if expr == target {
+        return with.clone();
+    }
+    match expr {
+        Expr::UnaryOp(op, operand) => {
+            Expr::UnaryOp(op.clone(), Box::new(replaced(operand, target, with)))
+        }
+        Expr::BinOp(left, op, right) => Expr::BinOp(
+            Box::new(replaced(left, target, with)),
+            op.clone(),
+            Box::new(replaced(right, target, with)),
+        ),
+        Expr::Call(name, args) => Expr::Call(
+            name.clone(),
+            args.iter().map(|arg| replaced(arg, target, with)).collect(),
+        ),
+        leaf => leaf.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_locates_lexing_errors() {
+        let diagnostics = diagnose("2024/01/01 + 3d $", &Options::default());
+
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, Severity::Error);
+        assert_eq!(diagnostics[0].span, 16..17);
+    }
+
+    #[test]
+    fn test_reports_lints_with_suggestions_and_errors() {
+        let diagnostics = diagnose("  (2024/01/01 + 2024/02/01) > 3d ", &Options::default());
+
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(diagnostics[0].severity, Severity::Warning);
+        assert_eq!(diagnostics[0].span, 2..32);
+        assert_eq!(
+            diagnostics[0].suggestion.as_deref(),
+            Some("2024/01/01 - 2024/02/01 > 3d")
+        );
+        assert_eq!(diagnostics[1].severity, Severity::Error);
+
+        assert_eq!(diagnose("2h + 30m", &Options::default()), []);
+    }
+}
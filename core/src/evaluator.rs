@@ -1,19 +1,76 @@
-use crate::calendar::{Calendar, add_datetime_working_days, add_working_days, date_from_parts};
-use crate::parser::{Expr, Op};
-use crate::parser::{Keyword, Unit};
+use crate::ast::{Ast, Node, NodeId};
+use crate::calendar::{
+    Calendar, MissingDay, add_datetime_working_days, add_months, add_working_days, date_from_parts,
+    next_payday, next_weekday, sprint_day, sprint_number, start_of_period,
+};
+use crate::detect::TimestampFormat;
+use crate::functions;
+use crate::options::{DayParts, Options};
+use crate::parser::{AssumeMissing, Expr, Op, Syntax, UnaryOp};
+use crate::parser::{Keyword, Period, Recurrence, Unit};
+use crate::zone::Zone;
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
 
-use std::fmt;
-use time::{Date, Duration, Month, OffsetDateTime, Time, UtcOffset};
+use core::cmp::Ordering;
+use core::fmt;
+use core::str::FromStr;
+use time::{Date, Duration, Month, OffsetDateTime, PrimitiveDateTime, Time, UtcOffset};
 
 const DAYS_PER_MONTH_APPROX: i64 = 30;
 const DAYS_PER_YEAR_APPROX: i64 = 365;
+const MONTHS_PER_YEAR: i64 = 12;
+/// Largest month count whose approximation in days still fits a `Duration`.
+const MAX_MONTHS: i64 = i64::MAX / (31 * 86_400);
 
+/// Why an expression could not be evaluated. New reasons may be added, so a
+/// `match` needs a wildcard arm.
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum EvalError {
-    Date(u32, u8, u8),
+    Date(i32, u8, u8),
     Month(u8),
     Time(u8, u8, u8),
     Operation(Op, Value, Value),
+    UnaryOperation(UnaryOp, Value),
+    Anchor(Date),
+    UnknownFunction(String),
+    Arguments(String, Vec<Value>),
+    Approximation(i64),
+    OutOfRange(String),
+    DivisionByZero(Value),
+    RemainderByZero(Value),
+    TooDeep(usize),
+    TooManySteps(i64, usize),
+    /// A relative keyword was used without a clock to resolve it against.
+    NoReferenceTime,
+    /// Calendar month arithmetic landed on a day the month does not have.
+    MissingDay(i32, Month, u8),
+    UnknownZone(String),
+    /// `%n` or, without a number, `ans` referred to an earlier result that
+    /// does not exist.
+    NoResult(Option<u32>),
+    /// A sprint was asked for without [`Options::sprints`].
+    NoSprints,
+    /// A payday was asked for without [`Options::paydays`].
+    NoPaydays,
+    /// A template placeholder without a value bound to it.
+    Unbound(String),
+    /// A recurrence used anywhere but inside `sum(x every … from … to …)`.
+    Recurrence(Recurrence),
+    /// A `%` directive in a `format` pattern that is unknown, or that needs
+    /// a part the value does not have, such as the hour of a date.
+    Directive(char, Value),
+    /// Text that `parse` could not read with a pattern, or that does not
+    /// make a whole date, date-time, month, year or time.
+    Unparsable(String, String),
+    /// A `%y` year read by `parse` while two-digit years are rejected.
+    TwoDigitYear(i64),
+    /// Text `auto` could not read as a timestamp in any format it knows.
+    Undetected(String),
 }
 
 impl fmt::Display for EvalError {
@@ -35,23 +92,117 @@ impl fmt::Display for EvalError {
                     right.type_name(),
                 )
             }
+            EvalError::Anchor(date) => write!(f, "cannot resolve period relative to '{}'", date),
+            EvalError::UnaryOperation(op, value) => {
+                write!(f, "invalid operation '{}' for '{}'", op, value.type_name())
+            }
+            EvalError::Approximation(months) => write!(
+                f,
+                "refusing to approximate {} month(s) as days (month_arithmetic = \"error\")",
+                months
+            ),
+            EvalError::OutOfRange(computation) => {
+                write!(f, "result of '{}' is out of range", computation)
+            }
+            EvalError::DivisionByZero(value) => write!(f, "cannot divide '{}' by zero", value),
+            EvalError::RemainderByZero(value) => {
+                write!(f, "cannot take the remainder of '{}' by zero", value)
+            }
+            EvalError::NoSprints => write!(
+                f,
+                "sprints are not configured; set a start and length under [sprints]"
+            ),
+            EvalError::NoPaydays => write!(
+                f,
+                "paydays are not configured; set a schedule under [paydays]"
+            ),
+            EvalError::Unbound(name) => {
+                write!(f, "no value bound to placeholder '{{{}}}'", name)
+            }
+            EvalError::Recurrence(recurrence) => write!(
+                f,
+                "'every {}' can only be used as 'sum(x every {} from a to b)'",
+                recurrence, recurrence
+            ),
+            EvalError::TooDeep(max) => {
+                write!(f, "expression nests deeper than {} levels", max)
+            }
+            EvalError::TooManySteps(days, max) => write!(
+                f,
+                "cannot count {} working days; the limit is {}",
+                days, max
+            ),
+            EvalError::NoReferenceTime => {
+                write!(f, "relative keywords need a reference time (Options::now)")
+            }
+            EvalError::MissingDay(year, month, day) => write!(
+                f,
+                "{} {} has no day {} (missing_day = \"error\")",
+                month, year, day
+            ),
+            EvalError::UnknownZone(name) => write!(f, "unknown time zone '{}'", name),
+            EvalError::NoResult(Some(number)) => write!(f, "no result %{} yet", number),
+            EvalError::NoResult(None) => write!(f, "no earlier result for 'ans'"),
+            EvalError::UnknownFunction(name) => write!(f, "unknown function '{}'", name),
+            EvalError::Directive(directive, value) => write!(
+                f,
+                "format directive '%{}' is unknown or not valid for '{}'",
+                directive,
+                value.type_name()
+            ),
+            EvalError::Unparsable(text, pattern) => {
+                write!(f, "cannot read '{}' with the pattern '{}'", text, pattern)
+            }
+            EvalError::TwoDigitYear(year) => write!(
+                f,
+                "ambiguous two-digit year '{}', set a pivot to accept it",
+                year
+            ),
+            EvalError::Undetected(text) => {
+                write!(f, "cannot tell what timestamp '{}' is", text)
+            }
+            EvalError::Arguments(name, args) => {
+                let types: Vec<_> = args.iter().map(Value::type_name).collect();
+                write!(f, "invalid arguments ({}) for '{}'", types.join(", "), name)
+            }
         }
     }
 }
 
-impl std::error::Error for EvalError {}
+impl core::error::Error for EvalError {}
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Value {
     Date(Date),
     DateTime(OffsetDateTime),
+    /// A date and time in a named zone, always holding the zone's offset at
+    /// that instant.
+    Zoned(OffsetDateTime, Zone),
     Duration(Duration),
     WorkingDays(i64),
+    /// A count of days from Monday to Friday, holidays or not.
+    Weekdays(i64),
     Time(Time),
+    Bool(bool),
+    Number(i64),
+    Year(i32),
+    YearMonth(i32, Month),
+    /// A calendar-month count. Exact against years and year-months, and
+    /// approximated as a plain duration everywhere else.
+    Months(i64),
+    /// A ratio held as a numerator and a positive denominator, such as 25%,
+    /// shown as a percentage.
+    Ratio(i64, i64),
+    /// How far a moment is from now, shown in words as `in 3 weeks` or
+    /// `2 months ago`. Made by `relative(x)` and only meant for display.
+    Relative(Duration),
+    /// Text, such as a pattern for `format` or the date it wrote out.
+    Str(String),
 }
 
 impl Value {
-    fn from_date(year: u32, month: u8, day: u8) -> Result<Self, EvalError> {
+    fn from_date(year: i32, month: u8, day: u8) -> Result<Self, EvalError> {
         Ok(Value::Date(date_from_parts(year, month, day)?))
     }
 
@@ -62,203 +213,1351 @@ impl Value {
     }
 
     fn from_duration(value: i64, unit: &Unit) -> Result<Self, EvalError> {
-        let duration = match unit {
-            Unit::Years => Duration::days(value * DAYS_PER_YEAR_APPROX),
-            Unit::Months => Duration::days(value * DAYS_PER_MONTH_APPROX),
-            Unit::Days => Duration::days(value),
-            Unit::WorkingDays => return Ok(Value::WorkingDays(value)),
-            Unit::Hours => Duration::hours(value),
-            Unit::Minutes => Duration::minutes(value),
-            Unit::Seconds => Duration::seconds(value),
+        let out_of_range =
+            || EvalError::OutOfRange(Expr::Duration(value, unit.clone()).to_string());
+        let result = match (unit, unit.months()) {
+            (Unit::WorkingDays, _) => Some(Value::WorkingDays(value)),
+            (Unit::Weekdays, _) => Some(Value::Weekdays(value)),
+            (_, Some(months)) => value.checked_mul(months).and_then(Value::months),
+            _ => unit
+                .nanoseconds()
+                .and_then(|nanos| duration_from_nanos(i128::from(value) * i128::from(nanos)))
+                .map(Value::Duration),
         };
-        Ok(Value::Duration(duration))
+        result.ok_or_else(out_of_range)
+    }
+
+    /// A month count, if small enough to be approximated in days.
+    fn months(months: i64) -> Option<Self> {
+        (-MAX_MONTHS..=MAX_MONTHS)
+            .contains(&months)
+            .then_some(Value::Months(months))
+    }
+
+    /// A keyword's day at the given time of day, in the offset of `now`.
+    fn from_keyword_time(
+        keyword: &Keyword,
+        hour: u8,
+        minute: u8,
+        day_parts: &DayParts,
+        now: OffsetDateTime,
+    ) -> Result<Self, EvalError> {
+        let time = Time::from_hms(hour, minute, 0).map_err(|_| EvalError::Time(hour, minute, 0))?;
+        match Value::from_keyword(keyword, day_parts, now)? {
+            Value::Date(date) => time_on_day(date, time, day_parts)
+                .map(|datetime| Value::DateTime(datetime.assume_offset(now.offset())))
+                .ok_or_else(|| EvalError::OutOfRange(Expr::Keyword(keyword.clone()).to_string())),
+            Value::DateTime(datetime) => Ok(Value::DateTime(datetime.replace_time(time))),
+            value => Err(EvalError::Operation(Op::Add, value, Value::Time(time))),
+        }
     }
 
-    fn from_keyword(keyword: &Keyword) -> Result<Self, EvalError> {
+    fn from_keyword(
+        keyword: &Keyword,
+        day_parts: &DayParts,
+        now: OffsetDateTime,
+    ) -> Result<Self, EvalError> {
+        let today = current_day(now, day_parts);
+        let out_of_range = || EvalError::OutOfRange(Expr::Keyword(keyword.clone()).to_string());
+        let at = |datetime: Option<PrimitiveDateTime>| {
+            datetime
+                .map(|datetime| Value::DateTime(datetime.assume_offset(now.offset())))
+                .ok_or_else(out_of_range)
+        };
         match keyword {
-            Keyword::Now => {
-                let now = OffsetDateTime::now_utc();
-                Ok(Value::DateTime(now))
-            }
-            Keyword::Today => {
-                let now = OffsetDateTime::now_utc();
-                Ok(Value::Date(now.date()))
-            }
-            Keyword::Tomorrow => {
-                let now = OffsetDateTime::now_utc();
-                Ok(Value::Date(now.date() + Duration::days(1)))
-            }
-            Keyword::Yesterday => {
-                let now = OffsetDateTime::now_utc();
-                Ok(Value::Date(now.date() - Duration::days(1)))
-            }
+            Keyword::Now => Ok(Value::DateTime(now)),
+            Keyword::Today => Ok(Value::Date(today)),
+            Keyword::Tomorrow => today.next_day().map(Value::Date).ok_or_else(out_of_range),
+            Keyword::Yesterday => today
+                .previous_day()
+                .map(Value::Date)
+                .ok_or_else(out_of_range),
+            Keyword::Morning => at(time_on_day(today, day_parts.morning, day_parts)),
+            Keyword::Afternoon => at(time_on_day(today, day_parts.afternoon, day_parts)),
+            Keyword::Evening => at(time_on_day(today, day_parts.evening, day_parts)),
+            Keyword::Tonight => at(time_on_day(today, day_parts.night, day_parts)),
+            Keyword::EndOfDay => at(today.next_day().map(|date| date.with_time(day_parts.start))),
+            Keyword::This(period) => Value::from_anchor(today, period, 0),
+            Keyword::Next(period) => Value::from_anchor(today, period, 1),
+            Keyword::Last(period) => Value::from_anchor(today, period, -1),
+            Keyword::NextWeekday(weekday) => next_weekday(today, *weekday, true)
+                .map(Value::Date)
+                .ok_or_else(out_of_range),
+            Keyword::LastWeekday(weekday) => next_weekday(today, *weekday, false)
+                .map(Value::Date)
+                .ok_or_else(out_of_range),
         }
     }
 
+    fn from_anchor(today: Date, period: &Period, offset: i32) -> Result<Self, EvalError> {
+        let date = start_of_period(today, period, offset).ok_or(EvalError::Anchor(today))?;
+        Ok(Value::Date(date))
+    }
+
     fn from_datetime(
-        year: u32,
+        year: i32,
+        month: u8,
+        day: u8,
+        hour: u8,
+        minute: u8,
+    ) -> Result<Self, EvalError> {
+        let datetime = local_from_parts(year, month, day, hour, minute)?;
+        Ok(Value::DateTime(datetime.assume_offset(UtcOffset::UTC)))
+    }
+
+    fn from_zoned_datetime(
+        year: i32,
         month: u8,
         day: u8,
         hour: u8,
         minute: u8,
+        zone: &str,
     ) -> Result<Self, EvalError> {
+        let zone = Zone::from_name(zone).ok_or_else(|| EvalError::UnknownZone(zone.to_string()))?;
+        let datetime = local_from_parts(year, month, day, hour, minute)?;
+        zoned(datetime, zone).ok_or_else(|| EvalError::OutOfRange(format!("{} {}", datetime, zone)))
+    }
+
+    fn from_year_month(year: i32, month: u8) -> Result<Self, EvalError> {
         let month = Month::try_from(month).map_err(|_| EvalError::Month(month))?;
-        let date = Date::from_calendar_date(year as i32, month, day)
-            .map_err(|_| EvalError::Date(year, month.into(), day))?;
-        let time = Time::from_hms(hour, minute, 0).map_err(|_| EvalError::Time(hour, minute, 0))?;
-        let offset = UtcOffset::UTC;
-        Ok(Value::DateTime(OffsetDateTime::new_in_offset(
-            date, time, offset,
-        )))
+        Ok(Value::YearMonth(year, month))
     }
 
-    fn add(self, other: Value, calendar: &Calendar) -> Result<Value, EvalError> {
-        match (self, other) {
-            (Value::Date(left), Value::Duration(right)) => Ok(Value::Date(left + right)),
-            (Value::Date(left), Value::WorkingDays(right)) => {
-                Ok(Value::Date(add_working_days(left, right, calendar)))
-            }
-            (Value::DateTime(left), Value::Duration(right)) => Ok(Value::DateTime(left + right)),
-            (Value::DateTime(left), Value::WorkingDays(right)) => Ok(Value::DateTime(
-                add_datetime_working_days(left, right, calendar),
+    /// Converts a month count to the approximate duration used for day-based
+    /// arithmetic: whole years count as 365 days and remaining months as 30.
+    pub(crate) fn approximate(&self) -> Value {
+        match *self {
+            Value::Months(months) => Value::Duration(Duration::days(
+                months / MONTHS_PER_YEAR * DAYS_PER_YEAR_APPROX
+                    + months % MONTHS_PER_YEAR * DAYS_PER_MONTH_APPROX,
             )),
-            (Value::Time(left), Value::Duration(right)) => Ok(Value::Time(left + right)),
-            (Value::Duration(left), Value::Duration(right)) => Ok(Value::Duration(left + right)),
-            (Value::WorkingDays(left), Value::WorkingDays(right)) => {
-                Ok(Value::WorkingDays(left + right))
+            _ => self.clone(),
+        }
+    }
+
+    pub(crate) fn add(self, other: Value, calendar: &Calendar) -> Result<Value, EvalError> {
+        match (&self, &other) {
+            (&Value::YearMonth(year, month), &Value::Months(months))
+            | (&Value::Months(months), &Value::YearMonth(year, month)) => {
+                checked(Op::Add, self, other, shift_year_month(year, month, months))
+            }
+            (&Value::Year(year), &Value::Months(months))
+            | (&Value::Months(months), &Value::Year(year))
+                if months % MONTHS_PER_YEAR == 0 =>
+            {
+                checked(
+                    Op::Add,
+                    self,
+                    other,
+                    shift_year(year, months / MONTHS_PER_YEAR),
+                )
+            }
+            (&Value::Months(left), &Value::Months(right)) => checked(
+                Op::Add,
+                self,
+                other,
+                left.checked_add(right).and_then(Value::months),
+            ),
+            (&Value::Number(left), &Value::Number(right)) => checked(
+                Op::Add,
+                self,
+                other,
+                left.checked_add(right).map(Value::Number),
+            ),
+            (&Value::Months(_), _) | (_, &Value::Months(_)) => {
+                self.approximate().add(other.approximate(), calendar)
+            }
+            (&Value::Date(left), &Value::Duration(right)) => checked(
+                Op::Add,
+                self,
+                other,
+                left.checked_add(right).map(Value::Date),
+            ),
+            (&Value::Date(_) | &Value::DateTime(_) | &Value::Zoned(..), &Value::Weekdays(days)) => {
+                weekdays(
+                    Op::Add,
+                    self.clone(),
+                    other,
+                    self.add(Value::WorkingDays(days), &Calendar::EMPTY),
+                )
             }
+            (&Value::Date(left), &Value::WorkingDays(right)) => checked(
+                Op::Add,
+                self,
+                other,
+                add_working_days(left, right, calendar).map(Value::Date),
+            ),
+            (&Value::DateTime(left), &Value::Duration(right)) => checked(
+                Op::Add,
+                self,
+                other,
+                left.checked_add(right).map(Value::DateTime),
+            ),
+            (&Value::DateTime(left), &Value::WorkingDays(right)) => checked(
+                Op::Add,
+                self,
+                other,
+                add_datetime_working_days(left, right, calendar).map(Value::DateTime),
+            ),
+            (&Value::Zoned(left, zone), &Value::Duration(right)) => {
+                checked(Op::Add, self, other, add_zoned(left, zone, right))
+            }
+            (&Value::Zoned(left, zone), &Value::WorkingDays(right)) => checked(
+                Op::Add,
+                self,
+                other,
+                add_datetime_working_days(left, right, calendar)
+                    .and_then(|moved| zoned(local_time(moved), zone)),
+            ),
+            (&Value::Time(left), &Value::Duration(right)) => Ok(Value::Time(left + right)),
+            (&Value::Duration(left), &Value::Duration(right)) => checked(
+                Op::Add,
+                self,
+                other,
+                left.checked_add(right).map(Value::Duration),
+            ),
+            (&Value::WorkingDays(left), &Value::WorkingDays(right)) => checked(
+                Op::Add,
+                self,
+                other,
+                left.checked_add(right).map(Value::WorkingDays),
+            ),
+            (&Value::Weekdays(left), &Value::Weekdays(right)) => checked(
+                Op::Add,
+                self,
+                other,
+                left.checked_add(right).map(Value::Weekdays),
+            ),
+            (Value::Str(left), Value::Str(right)) => Ok(Value::Str(format!("{}{}", left, right))),
             _ => Err(EvalError::Operation(Op::Add, self, other)),
         }
     }
 
     fn sub(self, other: Value, calendar: &Calendar) -> Result<Value, EvalError> {
-        match (self, other) {
-            (Value::Date(left), Value::Date(right)) => Ok(Value::Duration(left - right)),
-            (Value::Date(left), Value::Duration(right)) => Ok(Value::Date(left - right)),
-            (Value::Date(left), Value::WorkingDays(right)) => {
-                Ok(Value::Date(add_working_days(left, -right, calendar)))
-            }
-            (Value::Duration(left), Value::Duration(right)) => Ok(Value::Duration(left - right)),
-            (Value::WorkingDays(left), Value::WorkingDays(right)) => {
-                Ok(Value::WorkingDays(left - right))
-            }
-            (Value::DateTime(left), Value::Duration(right)) => Ok(Value::DateTime(left - right)),
-            (Value::DateTime(left), Value::WorkingDays(right)) => Ok(Value::DateTime(
-                add_datetime_working_days(left, -right, calendar),
+        match (&self, &other) {
+            (&Value::YearMonth(year, month), &Value::Months(months)) => {
+                checked(Op::Sub, self, other, shift_year_month(year, month, -months))
+            }
+            (
+                &Value::YearMonth(left_year, left_month),
+                &Value::YearMonth(right_year, right_month),
+            ) => Ok(Value::Months(
+                month_index(left_year, left_month) - month_index(right_year, right_month),
             )),
-            (Value::Time(left), Value::Duration(right)) => Ok(Value::Time(left - right)),
-            (Value::Time(left), Value::Time(right)) => Ok(Value::Duration(left - right)),
+            (&Value::Year(year), &Value::Months(months)) if months % MONTHS_PER_YEAR == 0 => {
+                checked(
+                    Op::Sub,
+                    self,
+                    other,
+                    shift_year(year, -months / MONTHS_PER_YEAR),
+                )
+            }
+            (&Value::Year(left), &Value::Year(right)) => Ok(Value::Months(
+                (i64::from(left) - i64::from(right)) * MONTHS_PER_YEAR,
+            )),
+            (&Value::Months(left), &Value::Months(right)) => checked(
+                Op::Sub,
+                self,
+                other,
+                left.checked_sub(right).and_then(Value::months),
+            ),
+            (&Value::Number(left), &Value::Number(right)) => checked(
+                Op::Sub,
+                self,
+                other,
+                left.checked_sub(right).map(Value::Number),
+            ),
+            (&Value::Months(_), _) | (_, &Value::Months(_)) => {
+                self.approximate().sub(other.approximate(), calendar)
+            }
+            (&Value::Date(left), &Value::Date(right)) => Ok(Value::Duration(left - right)),
+            (&Value::Date(left), &Value::Duration(right)) => checked(
+                Op::Sub,
+                self,
+                other,
+                left.checked_sub(right).map(Value::Date),
+            ),
+            (&Value::Date(_) | &Value::DateTime(_) | &Value::Zoned(..), &Value::Weekdays(days)) => {
+                weekdays(
+                    Op::Sub,
+                    self.clone(),
+                    other,
+                    self.sub(Value::WorkingDays(days), &Calendar::EMPTY),
+                )
+            }
+            (&Value::Date(left), &Value::WorkingDays(right)) => checked(
+                Op::Sub,
+                self,
+                other,
+                right
+                    .checked_neg()
+                    .and_then(|right| add_working_days(left, right, calendar))
+                    .map(Value::Date),
+            ),
+            (&Value::Duration(left), &Value::Duration(right)) => checked(
+                Op::Sub,
+                self,
+                other,
+                left.checked_sub(right).map(Value::Duration),
+            ),
+            (&Value::WorkingDays(left), &Value::WorkingDays(right)) => checked(
+                Op::Sub,
+                self,
+                other,
+                left.checked_sub(right).map(Value::WorkingDays),
+            ),
+            (&Value::Weekdays(left), &Value::Weekdays(right)) => checked(
+                Op::Sub,
+                self,
+                other,
+                left.checked_sub(right).map(Value::Weekdays),
+            ),
+            (
+                &Value::DateTime(left) | &Value::Zoned(left, _),
+                &Value::DateTime(right) | &Value::Zoned(right, _),
+            ) => Ok(Value::Duration(left - right)),
+            (&Value::DateTime(left), &Value::Duration(right)) => checked(
+                Op::Sub,
+                self,
+                other,
+                left.checked_sub(right).map(Value::DateTime),
+            ),
+            (&Value::DateTime(left), &Value::WorkingDays(right)) => checked(
+                Op::Sub,
+                self,
+                other,
+                right
+                    .checked_neg()
+                    .and_then(|right| add_datetime_working_days(left, right, calendar))
+                    .map(Value::DateTime),
+            ),
+            (&Value::Zoned(left, zone), &Value::Duration(right)) => checked(
+                Op::Sub,
+                self,
+                other,
+                right
+                    .checked_neg()
+                    .and_then(|right| add_zoned(left, zone, right)),
+            ),
+            (&Value::Zoned(left, zone), &Value::WorkingDays(right)) => checked(
+                Op::Sub,
+                self,
+                other,
+                right
+                    .checked_neg()
+                    .and_then(|right| add_datetime_working_days(left, right, calendar))
+                    .and_then(|moved| zoned(local_time(moved), zone)),
+            ),
+            (&Value::Time(left), &Value::Duration(right)) => Ok(Value::Time(left - right)),
+            (&Value::Time(left), &Value::Time(right)) => Ok(Value::Duration(left - right)),
             _ => Err(EvalError::Operation(Op::Sub, self, other)),
         }
     }
 
+    /// Scales an amount by a whole number.
+    pub(crate) fn mul(self, other: Value) -> Result<Value, EvalError> {
+        let result = match (&self, &other) {
+            (&Value::Number(left), &Value::Number(right)) => {
+                left.checked_mul(right).map(Value::Number)
+            }
+            (&Value::Duration(duration), &Value::Number(factor))
+            | (&Value::Number(factor), &Value::Duration(duration)) => duration
+                .whole_nanoseconds()
+                .checked_mul(i128::from(factor))
+                .and_then(duration_from_nanos)
+                .map(Value::Duration),
+            (&Value::WorkingDays(days), &Value::Number(factor))
+            | (&Value::Number(factor), &Value::WorkingDays(days)) => {
+                days.checked_mul(factor).map(Value::WorkingDays)
+            }
+            (&Value::Weekdays(days), &Value::Number(factor))
+            | (&Value::Number(factor), &Value::Weekdays(days)) => {
+                days.checked_mul(factor).map(Value::Weekdays)
+            }
+            (&Value::Months(months), &Value::Number(factor))
+            | (&Value::Number(factor), &Value::Months(months)) => {
+                months.checked_mul(factor).and_then(Value::months)
+            }
+            (&Value::Duration(duration), &Value::Ratio(numerator, denominator))
+            | (&Value::Ratio(numerator, denominator), &Value::Duration(duration)) => duration
+                .whole_nanoseconds()
+                .checked_mul(i128::from(numerator))
+                .map(|nanos| div_round(nanos, i128::from(denominator)))
+                .and_then(duration_from_nanos)
+                .map(Value::Duration),
+            (&Value::Ratio(numerator, denominator), &Value::Number(factor))
+            | (&Value::Number(factor), &Value::Ratio(numerator, denominator)) => numerator
+                .checked_mul(factor)
+                .map(|numerator| Value::Ratio(numerator, denominator)),
+            (&Value::Ratio(left, left_denominator), &Value::Ratio(right, right_denominator)) => {
+                left.checked_mul(right)
+                    .zip(left_denominator.checked_mul(right_denominator))
+                    .map(|(numerator, denominator)| Value::Ratio(numerator, denominator))
+            }
+            _ => return Err(EvalError::Operation(Op::Mul, self, other)),
+        };
+        checked(Op::Mul, self, other, result)
+    }
+
+    /// Splits an amount into equal parts, or divides it by an amount of the
+    /// same type for their ratio, so `90m / 8h` is `18.75%`.
+    fn div(self, other: Value) -> Result<Value, EvalError> {
+        let zero = match other {
+            Value::Duration(divisor) => divisor.is_zero(),
+            Value::Number(divisor) | Value::Months(divisor) => divisor == 0,
+            _ => false,
+        };
+        if zero {
+            return Err(EvalError::DivisionByZero(self));
+        }
+
+        let result = match (&self, &other) {
+            (&Value::Duration(left), &Value::Duration(right)) => {
+                ratio(left.whole_nanoseconds(), right.whole_nanoseconds())
+            }
+            (&Value::Months(left), &Value::Months(right))
+            | (&Value::Number(left), &Value::Number(right)) => {
+                ratio(i128::from(left), i128::from(right))
+            }
+            (&Value::Duration(duration), &Value::Number(divisor)) => {
+                let nanos = duration.whole_nanoseconds() * i128::from(divisor.signum());
+                duration_from_nanos(div_round(nanos, i128::from(divisor).abs()))
+                    .map(Value::Duration)
+            }
+            _ => return Err(EvalError::Operation(Op::Div, self, other)),
+        };
+        checked(Op::Div, self, other, result)
+    }
+
+    /// The remainder of dividing by an amount of the same type. It is never
+    /// negative, so `-95m % 1h` is `25m`.
+    fn rem(self, other: Value) -> Result<Value, EvalError> {
+        let zero = match other {
+            Value::Duration(divisor) => divisor.is_zero(),
+            Value::Number(divisor) | Value::Months(divisor) => divisor == 0,
+            _ => false,
+        };
+        if zero {
+            return Err(EvalError::RemainderByZero(self));
+        }
+
+        match (&self, &other) {
+            (&Value::Number(left), &Value::Number(right)) => {
+                Ok(Value::Number(left.wrapping_rem_euclid(right)))
+            }
+            (&Value::Months(left), &Value::Months(right)) => {
+                Ok(Value::Months(left.wrapping_rem_euclid(right)))
+            }
+            (&Value::Months(_), _) | (_, &Value::Months(_)) => {
+                self.approximate().rem(other.approximate())
+            }
+            (&Value::Duration(left), &Value::Duration(right)) => {
+                let remainder = left
+                    .whole_nanoseconds()
+                    .rem_euclid(right.whole_nanoseconds());
+                checked(
+                    Op::Rem,
+                    self,
+                    other,
+                    duration_from_nanos(remainder).map(Value::Duration),
+                )
+            }
+            _ => Err(EvalError::Operation(Op::Rem, self, other)),
+        }
+    }
+
+    fn compare(self, op: Op, other: Value) -> Result<Value, EvalError> {
+        let ordering = match (&self, &other) {
+            (&Value::Date(left), &Value::Date(right)) => left.cmp(&right),
+            (
+                &Value::DateTime(left) | &Value::Zoned(left, _),
+                &Value::DateTime(right) | &Value::Zoned(right, _),
+            ) => left.cmp(&right),
+            (&Value::Duration(left), &Value::Duration(right)) => left.cmp(&right),
+            (&Value::WorkingDays(left), &Value::WorkingDays(right))
+            | (&Value::Weekdays(left), &Value::Weekdays(right)) => left.cmp(&right),
+            (&Value::Time(left), &Value::Time(right)) => left.cmp(&right),
+            (&Value::Number(left), &Value::Number(right)) => left.cmp(&right),
+            (&Value::Year(left), &Value::Year(right)) => left.cmp(&right),
+            (
+                &Value::YearMonth(left_year, left_month),
+                &Value::YearMonth(right_year, right_month),
+            ) => month_index(left_year, left_month).cmp(&month_index(right_year, right_month)),
+            (&Value::Months(left), &Value::Months(right)) => left.cmp(&right),
+            (&Value::Months(_), _) | (_, &Value::Months(_)) => {
+                return self.approximate().compare(op, other.approximate());
+            }
+            (&Value::Bool(left), &Value::Bool(right)) if matches!(op, Op::Equal | Op::NotEqual) => {
+                left.cmp(&right)
+            }
+            _ => return Err(EvalError::Operation(op, self, other)),
+        };
+
+        let result = match op {
+            Op::Less => ordering == Ordering::Less,
+            Op::LessEqual => ordering != Ordering::Greater,
+            Op::Greater => ordering == Ordering::Greater,
+            Op::GreaterEqual => ordering != Ordering::Less,
+            Op::Equal => ordering == Ordering::Equal,
+            Op::NotEqual => ordering != Ordering::Equal,
+            _ => return Err(EvalError::Operation(op, self, other)),
+        };
+        Ok(Value::Bool(result))
+    }
+
+    fn logical(self, op: Op, other: Value) -> Result<Value, EvalError> {
+        match (&self, &op, &other) {
+            (&Value::Bool(left), Op::And, &Value::Bool(right)) => Ok(Value::Bool(left && right)),
+            (&Value::Bool(left), Op::Or, &Value::Bool(right)) => Ok(Value::Bool(left || right)),
+            _ => Err(EvalError::Operation(op, self, other)),
+        }
+    }
+
+    pub(crate) fn neg(self) -> Result<Value, EvalError> {
+        let result = match self {
+            Value::Duration(duration) => duration.checked_neg().map(Value::Duration),
+            Value::WorkingDays(days) => days.checked_neg().map(Value::WorkingDays),
+            Value::Weekdays(days) => days.checked_neg().map(Value::Weekdays),
+            Value::Months(months) => Some(Value::Months(-months)),
+            Value::Number(number) => number.checked_neg().map(Value::Number),
+            _ => return Err(EvalError::UnaryOperation(UnaryOp::Neg, self)),
+        };
+        result.ok_or_else(|| EvalError::OutOfRange(format!("-{}", self)))
+    }
+
+    /// The value an out-of-range `left op right` clamps to in
+    /// [`Overflow::Saturate`] mode.
+    fn saturate(op: &Op, left: Value, right: Value) -> Option<Value> {
+        if *op == Op::Mul {
+            // A scaled amount overflows towards the sign of the product.
+            let (amount, factor) = match (left, right) {
+                (Value::Number(factor), amount) | (amount, Value::Number(factor)) => {
+                    (amount, factor)
+                }
+                _ => return None,
+            };
+            let positive = match amount {
+                Value::Duration(duration) => duration.is_positive(),
+                Value::WorkingDays(value)
+                | Value::Weekdays(value)
+                | Value::Number(value)
+                | Value::Months(value) => value > 0,
+                _ => return None,
+            };
+            return Some(amount.bound(positive == (factor > 0)));
+        }
+
+        let forward = |negative: bool| negative == (*op == Op::Sub);
+        let (Value::Duration(_)
+        | Value::WorkingDays(_)
+        | Value::Weekdays(_)
+        | Value::Number(_)
+        | Value::Months(_)) = left
+        else {
+            // A point in time moved by a step: the step's sign decides.
+            let up = match right {
+                Value::Duration(duration) => forward(duration.is_negative()),
+                Value::WorkingDays(days) | Value::Weekdays(days) | Value::Months(days) => {
+                    forward(days < 0)
+                }
+                _ => return None,
+            };
+            return match left {
+                Value::Date(_) => Some(Value::Date(if up { Date::MAX } else { Date::MIN })),
+                Value::DateTime(datetime) => {
+                    let bound = if up {
+                        PrimitiveDateTime::MAX
+                    } else {
+                        PrimitiveDateTime::MIN
+                    };
+                    Some(Value::DateTime(bound.assume_offset(datetime.offset())))
+                }
+                Value::YearMonth(..) => {
+                    let year = if up {
+                        Date::MAX.year()
+                    } else {
+                        Date::MIN.year()
+                    };
+                    let month = if up { Month::December } else { Month::January };
+                    Some(Value::YearMonth(year, month))
+                }
+                _ => None,
+            };
+        };
+
+        // Two amounts only overflow away from zero, in the direction of `left`
+        // (or of `-right` when subtracting from zero).
+        let up = match left {
+            Value::Duration(duration) if duration.is_zero() => *op == Op::Sub,
+            Value::Duration(duration) => duration.is_positive(),
+            Value::WorkingDays(value)
+            | Value::Weekdays(value)
+            | Value::Number(value)
+            | Value::Months(value)
+                if value == 0 =>
+            {
+                *op == Op::Sub
+            }
+            Value::WorkingDays(value)
+            | Value::Weekdays(value)
+            | Value::Number(value)
+            | Value::Months(value) => value > 0,
+            _ => return None,
+        };
+        Some(left.bound(up))
+    }
+
+    /// The largest (`up`) or smallest value of an amount's type.
+    fn bound(self, up: bool) -> Value {
+        let pick = |max: i64| if up { max } else { -max };
+        match self {
+            Value::Duration(_) => Value::Duration(if up { Duration::MAX } else { Duration::MIN }),
+            Value::WorkingDays(_) => Value::WorkingDays(if up { i64::MAX } else { i64::MIN }),
+            Value::Weekdays(_) => Value::Weekdays(if up { i64::MAX } else { i64::MIN }),
+            Value::Number(_) => Value::Number(if up { i64::MAX } else { i64::MIN }),
+            Value::Months(_) => Value::Months(pick(MAX_MONTHS)),
+            value => value,
+        }
+    }
+
+    fn not(self) -> Result<Value, EvalError> {
+        match self {
+            Value::Bool(value) => Ok(Value::Bool(!value)),
+            _ => Err(EvalError::UnaryOperation(UnaryOp::Not, self)),
+        }
+    }
+
     pub fn type_name(&self) -> &'static str {
         match self {
             Value::Date(_) => "Date",
-            Value::DateTime(_) => "DateTime",
-            Value::Duration(_) => "Duration",
+            Value::DateTime(_) | Value::Zoned(..) => "DateTime",
+            Value::Duration(_) | Value::Months(_) => "Duration",
             Value::WorkingDays(_) => "WorkingDays",
+            Value::Weekdays(_) => "Weekdays",
             Value::Time(_) => "Time",
+            Value::Bool(_) => "Bool",
+            Value::Number(_) => "Number",
+            Value::Year(_) => "Year",
+            Value::YearMonth(..) => "YearMonth",
+            Value::Ratio(..) => "Ratio",
+            Value::Relative(_) => "Relative",
+            Value::Str(_) => "String",
         }
     }
 }
 
+fn checked(op: Op, left: Value, right: Value, result: Option<Value>) -> Result<Value, EvalError> {
+    result.ok_or_else(|| EvalError::OutOfRange(format!("{} {} {}", left, op, right)))
+}
+
+/// Reports a weekday step, taken as working days on [`Calendar::EMPTY`], with
+/// the weekdays it was written with.
+fn weekdays(
+    op: Op,
+    left: Value,
+    right: Value,
+    result: Result<Value, EvalError>,
+) -> Result<Value, EvalError> {
+    checked(op, left, right, result.ok())
+}
+
+/// The ratio of two amounts in lowest terms, if it fits.
+fn ratio(numerator: i128, denominator: i128) -> Option<Value> {
+    let (mut a, mut b) = (numerator.unsigned_abs(), denominator.unsigned_abs());
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    let divisor = i128::try_from(a).ok()? * denominator.signum();
+    Some(Value::Ratio(
+        i64::try_from(numerator / divisor).ok()?,
+        i64::try_from(denominator / divisor).ok()?,
+    ))
+}
+
+/// `numerator / denominator` for a positive denominator, rounding halves
+/// away from zero.
+pub(crate) fn div_round(numerator: i128, denominator: i128) -> i128 {
+    let half = denominator / 2;
+    if numerator < 0 {
+        (numerator - half) / denominator
+    } else {
+        (numerator + half) / denominator
+    }
+}
+
+/// A duration of `nanos` nanoseconds, if it fits.
+fn duration_from_nanos(nanos: i128) -> Option<Duration> {
+    const NANOS_PER_SECOND: i128 = 1_000_000_000;
+    let seconds = i64::try_from(nanos / NANOS_PER_SECOND).ok()?;
+    Some(Duration::new(seconds, (nanos % NANOS_PER_SECOND) as i32))
+}
+
+/// The date of the day `now` falls in, when days begin at
+/// [`DayParts::start`].
+pub(crate) fn current_day(now: OffsetDateTime, day_parts: &DayParts) -> Date {
+    let since_start = day_parts.start - Time::MIDNIGHT;
+    now.checked_sub(since_start).unwrap_or(now).date()
+}
+
+/// `time` on the day that begins on `date`: times before
+/// [`DayParts::start`] are the early hours of the next date.
+fn time_on_day(date: Date, time: Time, day_parts: &DayParts) -> Option<PrimitiveDateTime> {
+    let date = if time < day_parts.start {
+        date.next_day()?
+    } else {
+        date
+    };
+    Some(date.with_time(time))
+}
+
+fn local_from_parts(
+    year: i32,
+    month: u8,
+    day: u8,
+    hour: u8,
+    minute: u8,
+) -> Result<PrimitiveDateTime, EvalError> {
+    let month = Month::try_from(month).map_err(|_| EvalError::Month(month))?;
+    let date = Date::from_calendar_date(year, month, day)
+        .map_err(|_| EvalError::Date(year, month.into(), day))?;
+    let time = Time::from_hms(hour, minute, 0).map_err(|_| EvalError::Time(hour, minute, 0))?;
+    Ok(date.with_time(time))
+}
+
+/// Moves a zoned date and time by `duration`. Whole days move the date on
+/// the wall clock, so `+ 1d` keeps the time of day across a daylight saving
+/// change; the rest is elapsed time.
+fn add_zoned(datetime: OffsetDateTime, zone: Zone, duration: Duration) -> Option<Value> {
+    let days = Duration::days(duration.whole_days());
+    let Value::Zoned(moved, _) = zoned(local_time(datetime).checked_add(days)?, zone)? else {
+        return None;
+    };
+    let instant = moved.checked_add(duration - days)?;
+    Some(Value::Zoned(zone.at(instant)?, zone))
+}
+
+/// A wall-clock time in `zone`, resolved as [`Zone::local`] describes.
+fn zoned(local: PrimitiveDateTime, zone: Zone) -> Option<Value> {
+    Some(Value::Zoned(zone.local(local)?, zone))
+}
+
+/// The date and time a clock showed at `datetime`, without its offset.
+fn local_time(datetime: OffsetDateTime) -> PrimitiveDateTime {
+    PrimitiveDateTime::new(datetime.date(), datetime.time())
+}
+
+fn month_index(year: i32, month: Month) -> i64 {
+    i64::from(year) * MONTHS_PER_YEAR + i64::from(u8::from(month)) - 1
+}
+
+fn shift_year_month(year: i32, month: Month, months: i64) -> Option<Value> {
+    let index = month_index(year, month).checked_add(months)?;
+    let year = i32::try_from(index.div_euclid(MONTHS_PER_YEAR)).ok()?;
+    let month = Month::try_from(index.rem_euclid(MONTHS_PER_YEAR) as u8 + 1).ok()?;
+    Some(Value::YearMonth(year, month))
+}
+
+fn shift_year(year: i32, years: i64) -> Option<Value> {
+    let year = i64::from(year).checked_add(years)?;
+    Some(Value::Year(i32::try_from(year).ok()?))
+}
+
 impl fmt::Display for Value {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match self {
-            Value::Date(d) => write_date(f, *d),
-            Value::DateTime(dt) => write_datetime(f, *dt),
-            Value::Duration(dur) => dur.fmt(f),
-            Value::WorkingDays(days) => write!(f, "{days}wd"),
-            Value::Time(t) => write_time(f, *t),
-        }
+        self.display(&Options::default()).fmt(f)
     }
 }
 
-fn write_date(f: &mut fmt::Formatter, date: Date) -> fmt::Result {
-    write!(
-        f,
-        "{:04}-{:02}-{:02}",
-        date.year(),
-        date.month() as u8,
-        date.day()
-    )
+#[cfg(test)]
+fn eval(expr: &Expr) -> Result<Value, EvalError> {
+    eval_with_options(&Ast::from(expr), &Options::default())
 }
 
-fn write_time(f: &mut fmt::Formatter, time: Time) -> fmt::Result {
-    write!(f, "{:02}:{:02}", time.hour(), time.minute())?;
+pub fn eval_with_options(ast: &Ast, options: &Options) -> Result<Value, EvalError> {
+    Evaluator::new(ast, options, reference_time(options), &[], &[], false).eval(ast.root())
+}
 
-    let second = time.second();
-    let nanosecond = time.nanosecond();
+/// A sub-expression together with the value it evaluated to.
+#[derive(Debug, Clone)]
+pub struct TraceStep {
+    pub expr: Expr,
+    pub value: Value,
+}
 
-    if second != 0 || nanosecond != 0 {
-        write!(f, ":{:02}", second)?;
+/// How month and year durations combine with values that have no notion of
+/// months.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum MonthArithmetic {
+    /// Dates move by calendar months, clamping to the end of shorter months;
+    /// anything else is approximated.
+    Calendar,
+    /// Months count as 30 days and years as 365 days. Decades and centuries
+    /// still move dates by calendar years.
+    #[default]
+    Approximate,
+    /// Refuse any operation that would need the approximation.
+    Error,
+}
 
-        if nanosecond != 0 {
-            let mut subseconds = format!("{:09}", nanosecond);
-            while subseconds.ends_with('0') {
-                subseconds.pop();
-            }
-            write!(f, ".{}", subseconds)?;
+impl FromStr for MonthArithmetic {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "calendar" => Ok(MonthArithmetic::Calendar),
+            "approximate" => Ok(MonthArithmetic::Approximate),
+            "error" => Ok(MonthArithmetic::Error),
+            _ => Err(format!(
+                "invalid month arithmetic '{}', expected calendar, approximate or error",
+                value
+            )),
         }
     }
+}
 
-    Ok(())
+/// What happens when a result does not fit its type, such as a date past the
+/// year 9999.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Overflow {
+    /// Fail with [`EvalError::OutOfRange`].
+    #[default]
+    Error,
+    /// Clamp to the largest or smallest representable value.
+    Saturate,
 }
 
-fn write_datetime(f: &mut fmt::Formatter, datetime: OffsetDateTime) -> fmt::Result {
-    write_date(f, datetime.date())?;
-    write!(f, " ")?;
-    write_time(f, datetime.time())?;
+impl FromStr for Overflow {
+    type Err = String;
 
-    let offset = datetime.offset();
-    if offset.whole_seconds() != 0 {
-        write!(f, " {}", format_offset(offset))?;
-    } else {
-        write!(f, " +00:00")?;
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "error" => Ok(Overflow::Error),
+            "saturate" => Ok(Overflow::Saturate),
+            _ => Err(format!(
+                "invalid overflow mode '{}', expected error or saturate",
+                value
+            )),
+        }
     }
+}
 
-    Ok(())
+/// Something the evaluator had to assume or approximate to produce a result.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Warning {
+    /// A month count was used as a fixed number of days.
+    ApproximateMonths { months: i64, days: i64 },
+    /// A date and time written without an offset was taken to be UTC.
+    AssumedUtc,
+    /// A month and day without a year were taken to be in this year.
+    AssumedYear { year: i32 },
+    /// A time of day on its own was taken to be today.
+    AssumedToday,
+    /// Time-of-day arithmetic crossed midnight; the result is that many days
+    /// later (or earlier, when negative) than it looks.
+    WrappedMidnight { days: i64 },
+    /// `auto` read a timestamp as being in this format.
+    Detected { format: TimestampFormat },
 }
 
-fn format_offset(offset: UtcOffset) -> String {
-    let total_seconds = offset.whole_seconds();
-    let sign = if total_seconds >= 0 { '+' } else { '-' };
-    let total_seconds = total_seconds.abs();
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Warning::ApproximateMonths { months, days } => {
+                write!(f, "approximated {} month(s) as {} days", months, days)
+            }
+            Warning::AssumedUtc => write!(f, "date and time without offset assumed to be UTC"),
+            Warning::AssumedYear { year } => {
+                write!(f, "date without a year assumed to be in {}", year)
+            }
+            Warning::AssumedToday => write!(f, "time of day assumed to be today"),
+            Warning::WrappedMidnight { days } if *days > 0 => {
+                write!(
+                    f,
+                    "time wrapped past midnight into the next {} day(s)",
+                    days
+                )
+            }
+            Warning::WrappedMidnight { days } => write!(
+                f,
+                "time wrapped past midnight into the previous {} day(s)",
+                -days
+            ),
+            Warning::Detected { format } => write!(f, "timestamp read as {}", format),
+        }
+    }
+}
 
-    let hours = total_seconds / 3600;
-    let minutes = (total_seconds % 3600) / 60;
-    let seconds = total_seconds % 60;
+/// The result of an evaluation together with its warnings and, when
+/// requested, the trace of evaluated sub-expressions.
+#[derive(Debug, Clone)]
+pub struct Evaluation {
+    pub value: Value,
+    pub warnings: Vec<Warning>,
+    pub trace: Vec<TraceStep>,
+}
 
-    if seconds == 0 {
-        format!("{}{:02}:{:02}", sign, hours, minutes)
-    } else {
-        format!("{}{:02}:{:02}:{:02}", sign, hours, minutes, seconds)
+/// Evaluates `expr`, collecting warnings and, if `trace` is set, every
+/// evaluated sub-expression in the order the evaluator computed them
+/// (operands before their operation).
+pub fn evaluate(ast: &Ast, options: &Options, trace: bool) -> Result<Evaluation, EvalError> {
+    evaluate_with(ast, options, &[], &[], trace)
+}
+
+/// Like [`evaluate`], with `{name}` placeholders taking the value bound to
+/// their name in `bindings`, and `%1`, `%2`, … and `ans` referring to the
+/// earlier `results`, oldest first.
+pub fn evaluate_with(
+    ast: &Ast,
+    options: &Options,
+    bindings: &[(&str, Value)],
+    results: &[Value],
+    trace: bool,
+) -> Result<Evaluation, EvalError> {
+    evaluate_at(
+        ast,
+        options,
+        reference_time(options),
+        bindings,
+        results,
+        trace,
+    )
+}
+
+/// Like [`evaluate`], without ever reading the system clock: relative
+/// keywords resolve against [`Options::now`] alone and fail without it, so
+/// the result depends on nothing but the arguments.
+pub fn evaluate_pure(ast: &Ast, options: &Options) -> Result<Evaluation, EvalError> {
+    evaluate_at(ast, options, options.now, &[], &[], false)
+}
+
+pub(crate) fn evaluate_at(
+    ast: &Ast,
+    options: &Options,
+    now: Option<OffsetDateTime>,
+    bindings: &[(&str, Value)],
+    results: &[Value],
+    trace: bool,
+) -> Result<Evaluation, EvalError> {
+    let mut evaluator = Evaluator::new(ast, options, now, bindings, results, trace);
+    let value = evaluator.eval(ast.root())?;
+    if let Value::Months(months) = value {
+        evaluator.approximate_months(months)?;
     }
+
+    Ok(Evaluation {
+        value,
+        warnings: evaluator.warnings,
+        trace: evaluator.trace.unwrap_or_default(),
+    })
 }
 
-#[cfg(test)]
-fn eval(expr: &Expr) -> Result<Value, EvalError> {
-    eval_with_calendar(expr, &Calendar::default())
+/// The instant relative keywords resolve against: [`Options::now`], or the
+/// system clock when `std` is available.
+fn reference_time(options: &Options) -> Option<OffsetDateTime> {
+    #[cfg(feature = "std")]
+    return Some(options.now.unwrap_or_else(OffsetDateTime::now_utc));
+    #[cfg(not(feature = "std"))]
+    return options.now;
 }
 
-pub fn eval_with_calendar(expr: &Expr, calendar: &Calendar) -> Result<Value, EvalError> {
-    match expr {
-        Expr::BinOp(left, op, right) => {
-            let left = eval_with_calendar(left, calendar)?;
-            let right = eval_with_calendar(right, calendar)?;
+struct Evaluator<'a> {
+    ast: &'a Ast,
+    options: &'a Options,
+    bindings: &'a [(&'a str, Value)],
+    /// Earlier results for `%n` and `ans` to refer to, oldest first.
+    results: &'a [Value],
+    /// Captured once, so every keyword in the expression resolves against
+    /// the same instant. `None` when there is no reference time to read.
+    now: Option<OffsetDateTime>,
+    trace: Option<Vec<TraceStep>>,
+    warnings: Vec<Warning>,
+    depth: usize,
+}
+
+impl<'a> Evaluator<'a> {
+    fn new(
+        ast: &'a Ast,
+        options: &'a Options,
+        now: Option<OffsetDateTime>,
+        bindings: &'a [(&'a str, Value)],
+        results: &'a [Value],
+        trace: bool,
+    ) -> Self {
+        Evaluator {
+            ast,
+            options,
+            bindings,
+            results,
+            now,
+            trace: trace.then(Vec::new),
+            warnings: Vec::new(),
+            depth: 0,
+        }
+    }
+
+    fn now(&self) -> Result<OffsetDateTime, EvalError> {
+        self.now.ok_or(EvalError::NoReferenceTime)
+    }
+
+    /// Records that `months` is about to be treated as a fixed number of days,
+    /// or refuses to when the options forbid it.
+    fn approximate_months(&mut self, months: i64) -> Result<(), EvalError> {
+        if self.options.month_arithmetic == MonthArithmetic::Error {
+            return Err(EvalError::Approximation(months));
+        }
+        if let Value::Duration(duration) = Value::Months(months).approximate() {
+            self.warnings.push(Warning::ApproximateMonths {
+                months,
+                days: duration.whole_days(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Applies `op` to a month count and a value that has no months of its
+    /// own, following [`Options::month_arithmetic`] unless `calendar` asks for
+    /// calendar months regardless, with its own [`MissingDay`] policy. Returns
+    /// `None` when the operands need no special handling.
+    fn month_operation(
+        &mut self,
+        left: &Value,
+        op: &Op,
+        right: &Value,
+        calendar: Option<MissingDay>,
+    ) -> Option<Result<Value, EvalError>> {
+        let months = match (left, right) {
+            (Value::Months(_), Value::Months(_) | Value::YearMonth(..) | Value::Year(_))
+            | (Value::YearMonth(..) | Value::Year(_), Value::Months(_))
+            | (Value::Months(_), Value::Number(_))
+            | (Value::Number(_), Value::Months(_)) => return None,
+            (&Value::Months(months), _) | (_, &Value::Months(months)) => months,
+            _ => return None,
+        };
+
+        let calendar = calendar.or((self.options.month_arithmetic == MonthArithmetic::Calendar)
+            .then_some(self.options.missing_day));
+        if let Some(missing_day) = calendar {
+            let shift = match op {
+                Op::Add => Some(months),
+                Op::Sub => months.checked_neg(),
+                _ => None,
+            };
+            let shifted = match (left, shift) {
+                (&Value::Date(date), Some(shift)) => {
+                    Some(add_months(date, shift, missing_day).map(|date| date.map(Value::Date)))
+                }
+                (&Value::DateTime(datetime), Some(shift)) => Some(
+                    add_months(datetime.date(), shift, missing_day)
+                        .map(|date| date.map(|date| Value::DateTime(datetime.replace_date(date)))),
+                ),
+                (&Value::Zoned(datetime, zone), Some(shift)) => {
+                    Some(add_months(datetime.date(), shift, missing_day).map(|date| {
+                        date.and_then(|date| zoned(date.with_time(datetime.time()), zone))
+                    }))
+                }
+                _ => None,
+            };
+            if let Some(shifted) = shifted {
+                return Some(shifted.and_then(|shifted| {
+                    checked(op.clone(), left.clone(), right.clone(), shifted)
+                }));
+            }
+        }
+
+        self.approximate_months(months).err().map(Err)
+    }
+
+    /// Whether `id` is a count of decades or centuries, which move dates by
+    /// calendar years whatever the month arithmetic: a 365-day year would put
+    /// `1924/01/01 + 1 century` weeks short of 2024.
+    fn in_calendar_years(&self, id: NodeId) -> bool {
+        matches!(
+            &self.ast[id],
+            Node::Leaf(Expr::Duration(_, Unit::Decades | Unit::Centuries))
+        )
+    }
+
+    fn eval(&mut self, id: NodeId) -> Result<Value, EvalError> {
+        let max_depth = self.options.limits.max_depth;
+        if self.depth >= max_depth {
+            return Err(EvalError::TooDeep(max_depth));
+        }
+        self.depth += 1;
+        let value = self.eval_node(id);
+        self.depth -= 1;
+
+        let value = value?;
+        self.record(id, &value);
+        Ok(value)
+    }
+
+    /// Adds the value of `id` to the trace, if one is being kept.
+    fn record(&mut self, id: NodeId, value: &Value) {
+        if let Some(trace) = &mut self.trace {
+            trace.push(TraceStep {
+                expr: self.ast.expr(id),
+                value: value.clone(),
+            });
+        }
+    }
+
+    /// Evaluates the binary operators down the left side of `id`, as in a
+    /// chain such as `1h + 2h + 3h`, from the innermost outwards. A long
+    /// chain then takes no more stack, or depth, than one operator.
+    fn eval_chain(&mut self, id: NodeId) -> Result<Value, EvalError> {
+        let mut chain = Vec::new();
+        let mut first = id;
+        while let Node::BinOp(left, ..) = &self.ast[first] {
+            chain.push(first);
+            first = *left;
+        }
+
+        let mut value = self.eval(first)?;
+        for &link in chain.iter().rev() {
+            let Node::BinOp(left, op, right) = &self.ast[link] else {
+                unreachable!("collected a binary operator");
+            };
+            let calendar = self.calendar_months(*left, *right);
+            let right = self.eval(*right)?;
+            value = self.binary(value, op, right, calendar)?;
+            // The outermost is recorded by `eval`, like any other node.
+            if link != id {
+                self.record(link, &value);
+            }
+        }
+        Ok(value)
+    }
+
+    /// The [`MissingDay`] policy to move by calendar months with, whatever
+    /// the month arithmetic, for an operator on `left` and `right`.
+    fn calendar_months(&self, left: NodeId, right: NodeId) -> Option<MissingDay> {
+        if self.options.syntax == Syntax::Gnu {
+            // As `date -d` does, carrying a day the month lacks over.
+            Some(MissingDay::Roll)
+        } else if self.in_calendar_years(left) || self.in_calendar_years(right) {
+            Some(self.options.missing_day)
+        } else {
+            None
+        }
+    }
+
+    /// Applies `op` to the values of its operands.
+    fn binary(
+        &mut self,
+        left: Value,
+        op: &Op,
+        right: Value,
+        calendar: Option<MissingDay>,
+    ) -> Result<Value, EvalError> {
+        let options = self.options;
+        self.check_midnight(&left, op, &right);
+        self.check_steps(&left, &right)?;
+        let result = match self.month_operation(&left, op, &right, calendar) {
+            Some(result) => result,
+            None => {
+                let (left, right) = (left.clone(), right.clone());
+                match op {
+                    Op::Add => left.add(right, &options.calendar),
+                    Op::Sub => left.sub(right, &options.calendar),
+                    Op::Mul => left.mul(right),
+                    Op::Div => left.div(right),
+                    Op::Rem => left.rem(right),
+                    Op::And | Op::Or => left.logical(op.clone(), right),
+                    _ => left.compare(op.clone(), right),
+                }
+            }
+        };
+        self.saturate_if_enabled(result, || Value::saturate(op, left, right))
+    }
+
+    /// Records a warning when adding a duration to a time of day carries into
+    /// another day, which the `Time` result cannot show.
+    fn check_midnight(&mut self, left: &Value, op: &Op, right: &Value) {
+        let (&Value::Time(time), &Value::Duration(duration)) = (left, right) else {
+            return;
+        };
+        let since_midnight = time - Time::MIDNIGHT;
+        let total = match op {
+            Op::Add => since_midnight.checked_add(duration),
+            Op::Sub => since_midnight.checked_sub(duration),
+            _ => None,
+        };
+        let Some(total) = total else { return };
+
+        let days = total
+            .whole_nanoseconds()
+            .div_euclid(Duration::DAY.whole_nanoseconds()) as i64;
+        if days != 0 {
+            self.warnings.push(Warning::WrappedMidnight { days });
+        }
+    }
+
+    /// Refuses to step through more working days than the limits allow,
+    /// since each one is counted day by day.
+    fn check_steps(&self, left: &Value, right: &Value) -> Result<(), EvalError> {
+        let max = self.options.limits.max_iterations;
+        match (left, right) {
+            (
+                Value::Date(_) | Value::DateTime(_) | Value::Zoned(..),
+                &Value::WorkingDays(days) | &Value::Weekdays(days),
+            ) if days.unsigned_abs() > max as u64 => Err(EvalError::TooManySteps(days, max)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Replaces an out-of-range result with `saturated` in
+    /// [`Overflow::Saturate`] mode.
+    fn saturate_if_enabled(
+        &self,
+        result: Result<Value, EvalError>,
+        saturated: impl FnOnce() -> Option<Value>,
+    ) -> Result<Value, EvalError> {
+        match result {
+            Err(EvalError::OutOfRange(computation))
+                if self.options.overflow == Overflow::Saturate =>
+            {
+                saturated().ok_or(EvalError::OutOfRange(computation))
+            }
+            result => result,
+        }
+    }
+
+    fn eval_node(&mut self, id: NodeId) -> Result<Value, EvalError> {
+        match &self.ast[id] {
+            Node::BinOp(..) => self.eval_chain(id),
+            Node::UnaryOp(op, operand) => {
+                let operand = self.eval(*operand)?;
 
-            match op {
-                Op::Add => left.add(right, calendar),
-                Op::Sub => left.sub(right, calendar),
+                match op {
+                    UnaryOp::Not => operand.not(),
+                    UnaryOp::Neg => self
+                        .saturate_if_enabled(operand.clone().neg(), || Some(operand.bound(true))),
+                }
+            }
+            Node::Call(name, args) if name == "sum" => self.eval_sum(name, args),
+            Node::Call(name, args) => {
+                let args = args
+                    .iter()
+                    .map(|arg| self.eval(*arg))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let mut context = functions::Context::new(self.options, self.now);
+                let result = functions::call(name, &args, &mut context);
+                self.warnings.append(&mut context.warnings);
+                result
             }
+            Node::Leaf(expr) => self.eval_leaf(expr),
         }
-        Expr::Time(hour, minute) => Ok(Value::from_time(*hour, *minute, 0)?),
-        Expr::Date(year, month, day) => Ok(Value::from_date(*year, *month, *day)?),
-        Expr::Duration(value, unit) => Ok(Value::from_duration(*value, unit)?),
-        Expr::Keyword(keyword) => Ok(Value::from_keyword(keyword)?),
-        Expr::DateTime(year, month, day, hour, minute) => {
-            Ok(Value::from_datetime(*year, *month, *day, *hour, *minute)?)
+    }
+
+    /// `sum(x, y, …)` adds its arguments together, and `sum(x every
+    /// <recurrence> from a to b)` adds up `x` once for each day from `a` to
+    /// `b` that the recurrence falls on.
+    fn eval_sum(&mut self, name: &str, args: &[NodeId]) -> Result<Value, EvalError> {
+        if let [amount, recurrence, from, to] = args
+            && let Node::Leaf(Expr::Recurrence(recurrence)) = &self.ast[*recurrence]
+        {
+            let amount = self.eval(*amount)?;
+            let range = [self.eval(*from)?, self.eval(*to)?];
+            let options = self.options;
+            let days = functions::recurrence_days(
+                name,
+                *recurrence,
+                range,
+                &options.calendar,
+                options.paydays.as_ref(),
+                options.limits.max_iterations,
+            )?;
+            return amount.mul(Value::Number(days));
+        }
+
+        let args = args
+            .iter()
+            .map(|arg| self.eval(*arg))
+            .collect::<Result<Vec<_>, _>>()?;
+        match args.split_first() {
+            Some((first, rest)) => rest.iter().try_fold(first.clone(), |total, arg| {
+                total.add(arg.clone(), &self.options.calendar)
+            }),
+            None => Err(EvalError::Arguments(name.to_string(), args)),
+        }
+    }
+
+    fn eval_leaf(&mut self, expr: &Expr) -> Result<Value, EvalError> {
+        let options = self.options;
+        match expr {
+            Expr::Bool(value) => Ok(Value::Bool(*value)),
+            Expr::Percent(percent) => Ok(Value::Ratio(*percent, 100)),
+            Expr::History(number) => number
+                .checked_sub(1)
+                .and_then(|index| self.results.get(index as usize))
+                .cloned()
+                .ok_or(EvalError::NoResult(Some(*number))),
+            Expr::Str(text) => Ok(Value::Str(text.clone())),
+            Expr::Ans => self
+                .results
+                .last()
+                .cloned()
+                .ok_or(EvalError::NoResult(None)),
+            Expr::Number(value) => Ok(Value::Number(*value)),
+            Expr::YearMonth(year, month) => Value::from_year_month(*year, *month),
+            Expr::MonthDay(month, day) => {
+                let year = current_day(self.now()?, &options.day_parts).year();
+                self.warnings.push(Warning::AssumedYear { year });
+                Ok(Value::from_date(year, *month, *day)?)
+            }
+            Expr::Time(hour, minute) if options.assume_missing == AssumeMissing::Current => {
+                self.warnings.push(Warning::AssumedToday);
+                let now = self.now()?;
+                Value::from_keyword_time(&Keyword::Today, *hour, *minute, &options.day_parts, now)
+            }
+            Expr::Time(hour, minute) => Ok(Value::from_time(*hour, *minute, 0)?),
+            Expr::Date(year, month, day) => Ok(Value::from_date(*year, *month, *day)?),
+            Expr::Duration(value, unit) => {
+                self.saturate_if_enabled(Value::from_duration(*value, unit), || {
+                    Value::from_duration(0, unit)
+                        .ok()
+                        .map(|zero| zero.bound(true))
+                })
+            }
+            Expr::Keyword(keyword) => Value::from_keyword(keyword, &options.day_parts, self.now()?),
+            Expr::KeywordTime(keyword, hour, minute) => {
+                Value::from_keyword_time(keyword, *hour, *minute, &options.day_parts, self.now()?)
+            }
+            Expr::DateTime(year, month, day, hour, minute) => {
+                self.warnings.push(Warning::AssumedUtc);
+                Ok(Value::from_datetime(*year, *month, *day, *hour, *minute)?)
+            }
+            Expr::ZonedDateTime(year, month, day, hour, minute, zone) => {
+                Value::from_zoned_datetime(*year, *month, *day, *hour, *minute, zone)
+            }
+            Expr::Recurrence(recurrence) => Err(EvalError::Recurrence(*recurrence)),
+            Expr::Placeholder(name) => self
+                .bindings
+                .iter()
+                .find(|(bound, _)| bound == name)
+                .map(|(_, value)| value.clone())
+                .ok_or_else(|| EvalError::Unbound(name.clone())),
+            Expr::Payday(forward) => {
+                let paydays = options.paydays.as_ref().ok_or(EvalError::NoPaydays)?;
+                let today = current_day(self.now()?, &options.day_parts);
+                next_payday(paydays, today, *forward)
+                    .map(Value::Date)
+                    .ok_or_else(|| EvalError::OutOfRange(expr.to_string()))
+            }
+            Expr::Sprint(edge, number) => {
+                let sprints = options.sprints.as_ref().ok_or(EvalError::NoSprints)?;
+                let number = match number {
+                    Some(number) => *number,
+                    None => sprint_number(sprints, current_day(self.now()?, &options.day_parts)),
+                };
+                sprint_day(sprints, number, *edge)
+                    .map(Value::Date)
+                    .ok_or_else(|| EvalError::OutOfRange(expr.to_string()))
+            }
+            Expr::UnaryOp(..) | Expr::BinOp(..) | Expr::Call(..) => {
+                unreachable!("operators are stored as separate nodes")
+            }
         }
     }
 }
@@ -266,6 +1565,7 @@ pub fn eval_with_calendar(expr: &Expr, calendar: &Calendar) -> Result<Value, Eva
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::options::Limits;
     use crate::parser::{Expr, Op};
 
     #[test]
@@ -369,12 +1669,13 @@ mod tests {
             Op::Add,
             Box::new(Expr::Duration(1, Unit::WorkingDays)),
         );
-        let mut calendar = Calendar::new();
-        calendar
+        let mut options = Options::default();
+        options
+            .calendar
             .add_holiday_ymd(2024, 4, 29)
             .expect("valid holiday");
 
-        let val = eval_with_calendar(&expr, &calendar).unwrap();
+        let val = eval_with_options(&Ast::from(&expr), &options).unwrap();
         match val {
             Value::Date(date) => assert_eq!(
                 date,
@@ -454,6 +1755,14 @@ mod tests {
     }
 
     #[test]
+    #[cfg(not(feature = "std"))]
+    fn test_keywords_need_reference_time_without_std() {
+        let expr = Expr::Keyword(Keyword::Today);
+        assert!(matches!(eval(&expr), Err(EvalError::NoReferenceTime)));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
     fn test_keyword_today() {
         let expr = Expr::Keyword(Keyword::Today);
         let val = eval(&expr).unwrap();
@@ -474,6 +1783,260 @@ mod tests {
         assert!(val.is_err());
     }
 
+    #[test]
+    fn test_concatenate_strings() {
+        let text = |text: &str| Box::new(Expr::Str(text.to_string()));
+        let expr = Expr::BinOp(text("due: "), Op::Add, text("friday"));
+        match eval(&expr).unwrap() {
+            Value::Str(text) => assert_eq!(text, "due: friday"),
+            value => panic!("Expected Value::Str, got {:?}", value),
+        }
+
+        let expr = Expr::BinOp(text("day "), Op::Add, Box::new(Expr::Number(1)));
+        assert_eq!(
+            eval(&expr).unwrap_err().to_string(),
+            "invalid operation '+' for 'String' and 'Number'"
+        );
+    }
+
+    #[test]
+    fn test_add_months_to_year_month() {
+        let expr = Expr::BinOp(
+            Box::new(Expr::YearMonth(2024, 11)),
+            Op::Add,
+            Box::new(Expr::Duration(3, Unit::Months)),
+        );
+        let val = eval(&expr).unwrap();
+        assert!(matches!(val, Value::YearMonth(2025, Month::February)));
+    }
+
+    #[test]
+    fn test_sub_year_month_year_month() {
+        let expr = Expr::BinOp(
+            Box::new(Expr::YearMonth(2025, 2)),
+            Op::Sub,
+            Box::new(Expr::YearMonth(2024, 11)),
+        );
+        let val = eval(&expr).unwrap();
+        assert!(matches!(val, Value::Months(3)));
+    }
+
+    #[test]
+    fn test_add_date_months_stays_approximate() {
+        let expr = Expr::BinOp(
+            Box::new(Expr::Date(2024, 1, 1)),
+            Op::Add,
+            Box::new(Expr::Duration(1, Unit::Years)),
+        );
+        let val = eval(&expr).unwrap();
+        match val {
+            Value::Date(date) => assert_eq!(
+                date,
+                Date::from_calendar_date(2024, Month::December, 31).unwrap()
+            ),
+            _ => panic!("Expected Value::Date"),
+        }
+    }
+
+    #[test]
+    fn test_eval_traced_records_operands_before_operation() {
+        let expr = Expr::BinOp(
+            Box::new(Expr::Date(2024, 1, 31)),
+            Op::Add,
+            Box::new(Expr::Duration(1, Unit::Days)),
+        );
+        let evaluation = evaluate(&Ast::from(&expr), &Options::default(), true).unwrap();
+
+        let steps: Vec<_> = evaluation
+            .trace
+            .iter()
+            .map(|step| format!("{} = {}", step.expr, step.value))
+            .collect();
+        assert_eq!(
+            steps,
+            [
+                "2024/01/31 = 2024-01-31",
+                "1d = 1d",
+                "2024/01/31 + 1d = 2024-02-01"
+            ]
+        );
+        assert_eq!(evaluation.value.to_string(), "2024-02-01");
+    }
+
+    #[test]
+    fn test_evaluate_warns_about_approximated_months() {
+        let expr = Expr::BinOp(
+            Box::new(Expr::Date(2024, 1, 31)),
+            Op::Add,
+            Box::new(Expr::Duration(1, Unit::Years)),
+        );
+        let evaluation = evaluate(&Ast::from(&expr), &Options::default(), false).unwrap();
+        assert_eq!(
+            evaluation.warnings,
+            [Warning::ApproximateMonths {
+                months: 12,
+                days: 365
+            }]
+        );
+
+        let expr = Expr::BinOp(
+            Box::new(Expr::YearMonth(2024, 1)),
+            Op::Add,
+            Box::new(Expr::Duration(1, Unit::Years)),
+        );
+        let evaluation = evaluate(&Ast::from(&expr), &Options::default(), false).unwrap();
+        assert!(evaluation.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_auto_reports_the_format_it_detected() {
+        let auto = |text: &str| {
+            let expr = Expr::Call("auto".to_string(), vec![Expr::Str(text.to_string())]);
+            evaluate(&Ast::from(&expr), &Options::default(), false)
+        };
+
+        let evaluation = auto("2024-03-01 14:30").unwrap();
+        assert_eq!(
+            evaluation.warnings,
+            [
+                Warning::Detected {
+                    format: TimestampFormat::Iso8601
+                },
+                Warning::AssumedUtc
+            ]
+        );
+
+        let evaluation = auto("2024/03/01 + 2d").unwrap();
+        assert!(matches!(evaluation.value, Value::Date(date) if date.day() == 3));
+        assert_eq!(
+            evaluation.warnings,
+            [Warning::Detected {
+                format: TimestampFormat::Native
+            }]
+        );
+
+        assert_eq!(
+            auto("3h").unwrap_err().to_string(),
+            "cannot tell what timestamp '3h' is"
+        );
+    }
+
+    #[test]
+    fn test_eval_rejects_expressions_beyond_depth_limit() {
+        let mut expr = Expr::Duration(1, Unit::Hours);
+        for _ in 0..20 {
+            expr = Expr::UnaryOp(UnaryOp::Neg, Box::new(expr));
+        }
+        let options = Options {
+            limits: Limits {
+                max_depth: 10,
+                ..Limits::default()
+            },
+            ..Options::default()
+        };
+
+        assert!(matches!(
+            eval_with_options(&Ast::from(&expr), &options),
+            Err(EvalError::TooDeep(10))
+        ));
+        assert!(eval(&expr).is_ok());
+    }
+
+    #[test]
+    fn test_evaluate_warns_when_time_wraps_past_midnight() {
+        let wrapped = |hour, op, hours| {
+            let expr = Expr::BinOp(
+                Box::new(Expr::Time(hour, 0)),
+                op,
+                Box::new(Expr::Duration(hours, Unit::Hours)),
+            );
+            let evaluation = evaluate(&Ast::from(&expr), &Options::default(), false).unwrap();
+            (evaluation.value.to_string(), evaluation.warnings)
+        };
+
+        assert_eq!(
+            wrapped(23, Op::Add, 2),
+            (
+                "01:00".to_string(),
+                vec![Warning::WrappedMidnight { days: 1 }]
+            )
+        );
+        assert_eq!(
+            wrapped(1, Op::Sub, 50),
+            (
+                "23:00".to_string(),
+                vec![Warning::WrappedMidnight { days: -3 }]
+            )
+        );
+        assert_eq!(wrapped(10, Op::Add, 13), ("23:00".to_string(), vec![]));
+        assert_eq!(
+            Warning::WrappedMidnight { days: -3 }.to_string(),
+            "time wrapped past midnight into the previous 3 day(s)"
+        );
+    }
+
+    #[test]
+    fn test_month_arithmetic_policies() {
+        let expr = Expr::BinOp(
+            Box::new(Expr::Date(2024, 1, 31)),
+            Op::Add,
+            Box::new(Expr::Duration(1, Unit::Months)),
+        );
+        let with_policy = |month_arithmetic| {
+            let options = Options {
+                month_arithmetic,
+                ..Options::default()
+            };
+            eval_with_options(&Ast::from(&expr), &options).map(|value| value.to_string())
+        };
+
+        assert_eq!(
+            with_policy(MonthArithmetic::Calendar).unwrap(),
+            "2024-02-29"
+        );
+        assert_eq!(
+            with_policy(MonthArithmetic::Approximate).unwrap(),
+            "2024-03-01"
+        );
+        assert!(matches!(
+            with_policy(MonthArithmetic::Error),
+            Err(EvalError::Approximation(1))
+        ));
+    }
+
+    #[test]
+    fn test_out_of_range_addition() {
+        let expr = Expr::BinOp(
+            Box::new(Expr::Date(2024, 1, 1)),
+            Op::Add,
+            Box::new(Expr::Duration(1_000_000_000, Unit::Days)),
+        );
+        assert!(matches!(eval(&expr), Err(EvalError::OutOfRange(_))));
+
+        let options = Options {
+            overflow: Overflow::Saturate,
+            ..Options::default()
+        };
+        let val = eval_with_options(&Ast::from(&expr), &options).unwrap();
+        assert!(matches!(val, Value::Date(date) if date == Date::MAX));
+    }
+
+    #[test]
+    fn test_out_of_range_duration_literal() {
+        let expr = Expr::UnaryOp(
+            UnaryOp::Neg,
+            Box::new(Expr::Duration(i64::MAX, Unit::Weeks)),
+        );
+        assert!(matches!(eval(&expr), Err(EvalError::OutOfRange(_))));
+
+        let options = Options {
+            overflow: Overflow::Saturate,
+            ..Options::default()
+        };
+        let val = eval_with_options(&Ast::from(&expr), &options).unwrap();
+        assert!(matches!(val, Value::Duration(duration) if duration == -Duration::MAX));
+    }
+
     #[test]
     fn test_display_date_formats_consistently() {
         let date = Date::from_calendar_date(2024, Month::January, 5).unwrap();
@@ -514,4 +2077,114 @@ mod tests {
         let dt = OffsetDateTime::new_in_offset(date, time, offset);
         assert_eq!(Value::DateTime(dt).to_string(), "2024-01-05 08:15 +05:30");
     }
+
+    #[test]
+    fn test_compare_dates() {
+        let expr = Expr::BinOp(
+            Box::new(Expr::Date(2024, 7, 1)),
+            Op::Greater,
+            Box::new(Expr::Date(2024, 6, 1)),
+        );
+        let val = eval(&expr).unwrap();
+        match val {
+            Value::Bool(result) => assert!(result),
+            _ => panic!("Expected Value::Bool"),
+        }
+    }
+
+    #[test]
+    fn test_compare_mismatched_types() {
+        let expr = Expr::BinOp(
+            Box::new(Expr::Date(2024, 7, 1)),
+            Op::Less,
+            Box::new(Expr::Duration(1, Unit::Days)),
+        );
+        assert!(eval(&expr).is_err());
+    }
+
+    #[test]
+    fn test_and_or_not() {
+        let expr = Expr::BinOp(
+            Box::new(Expr::UnaryOp(UnaryOp::Not, Box::new(Expr::Bool(false)))),
+            Op::And,
+            Box::new(Expr::BinOp(
+                Box::new(Expr::Bool(false)),
+                Op::Or,
+                Box::new(Expr::Bool(true)),
+            )),
+        );
+        let val = eval(&expr).unwrap();
+        match val {
+            Value::Bool(result) => assert!(result),
+            _ => panic!("Expected Value::Bool"),
+        }
+    }
+
+    #[test]
+    fn test_not_requires_bool() {
+        let expr = Expr::UnaryOp(UnaryOp::Not, Box::new(Expr::Duration(1, Unit::Days)));
+        assert!(eval(&expr).is_err());
+    }
+
+    #[test]
+    fn test_neg_duration() {
+        let expr = Expr::BinOp(
+            Box::new(Expr::Date(2025, 9, 27)),
+            Op::Add,
+            Box::new(Expr::UnaryOp(
+                UnaryOp::Neg,
+                Box::new(Expr::Duration(3, Unit::Days)),
+            )),
+        );
+        let val = eval(&expr).unwrap();
+        match val {
+            Value::Date(date) => assert_eq!(
+                date,
+                Date::from_calendar_date(2025, Month::September, 24).unwrap()
+            ),
+            _ => panic!("Expected Value::Date"),
+        }
+    }
+
+    #[test]
+    fn test_neg_requires_duration() {
+        let expr = Expr::UnaryOp(UnaryOp::Neg, Box::new(Expr::Date(2025, 9, 27)));
+        assert!(eval(&expr).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_keyword_tonight_uses_day_parts() {
+        let mut options = Options::default();
+        options.day_parts.night = Time::from_hms(22, 30, 0).unwrap();
+
+        let val =
+            eval_with_options(&Ast::from(&Expr::Keyword(Keyword::Tonight)), &options).unwrap();
+        match val {
+            Value::DateTime(datetime) => {
+                assert_eq!(datetime.time(), Time::from_hms(22, 30, 0).unwrap())
+            }
+            _ => panic!("Expected Value::DateTime"),
+        }
+    }
+
+    #[test]
+    fn test_history_refers_to_earlier_results() {
+        let results = [Value::Number(1), Value::Number(2)];
+        let result = |expr: &Expr| {
+            evaluate_with(&Ast::from(expr), &Options::default(), &[], &results, false)
+                .map(|evaluation| evaluation.value)
+        };
+
+        assert!(matches!(result(&Expr::History(1)), Ok(Value::Number(1))));
+        assert!(matches!(result(&Expr::Ans), Ok(Value::Number(2))));
+        assert!(matches!(
+            result(&Expr::History(0)),
+            Err(EvalError::NoResult(Some(0)))
+        ));
+        assert!(matches!(
+            result(&Expr::History(3)),
+            Err(EvalError::NoResult(Some(3)))
+        ));
+    }
 }
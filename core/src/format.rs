@@ -0,0 +1,689 @@
+use alloc::{format, string::String};
+use core::fmt::{self, Display, Write as _};
+use core::str::FromStr;
+
+use time::{Date, Duration, Month, OffsetDateTime, Time, UtcOffset};
+
+use crate::evaluator::{Value, div_round};
+use crate::locale::{DateStyle, Locale};
+use crate::options::Options;
+use crate::parser::Syntax;
+
+const HOURS_IN_HALF_DAY: u8 = 12;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum HourFormat {
+    #[default]
+    TwentyFourHour,
+    TwelveHour,
+}
+
+impl FromStr for HourFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "24" | "24h" => Ok(HourFormat::TwentyFourHour),
+            "12" | "12h" => Ok(HourFormat::TwelveHour),
+            _ => Err(format!(
+                "invalid hour format '{}', expected 12 or 24",
+                value
+            )),
+        }
+    }
+}
+
+/// How durations are printed, which matters most for negative ones.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DurationStyle {
+    /// With a leading minus when negative: `-3d4h`.
+    #[default]
+    Signed,
+    /// As a distance in time: `3d4h earlier` or `3d4h later`.
+    Relative,
+    /// Spelled out in the output locale, as in `1 hour, 2 minutes`.
+    Words,
+}
+
+impl FromStr for DurationStyle {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "signed" => Ok(DurationStyle::Signed),
+            "relative" => Ok(DurationStyle::Relative),
+            "words" => Ok(DurationStyle::Words),
+            _ => Err(format!(
+                "invalid duration style '{}', expected signed, relative or words",
+                value
+            )),
+        }
+    }
+}
+
+/// The smallest unit printed in times and durations.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Precision {
+    /// Whatever the value holds, down to nanoseconds; zero seconds are left
+    /// out of times.
+    #[default]
+    Full,
+    Minutes,
+    Seconds,
+    Milliseconds,
+}
+
+impl Precision {
+    /// The unit durations are rounded to, if any.
+    fn unit(self) -> Option<Duration> {
+        match self {
+            Precision::Full => None,
+            Precision::Minutes => Some(Duration::MINUTE),
+            Precision::Seconds => Some(Duration::SECOND),
+            Precision::Milliseconds => Some(Duration::MILLISECOND),
+        }
+    }
+}
+
+impl FromStr for Precision {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "full" => Ok(Precision::Full),
+            "minutes" | "m" => Ok(Precision::Minutes),
+            "seconds" | "s" => Ok(Precision::Seconds),
+            "milliseconds" | "ms" => Ok(Precision::Milliseconds),
+            _ => Err(format!(
+                "invalid precision '{}', expected full, minutes, seconds or milliseconds",
+                value
+            )),
+        }
+    }
+}
+
+/// Renders a [`Value`] according to the output settings in [`Options`].
+pub struct ValueDisplay<'a> {
+    value: &'a Value,
+    options: &'a Options,
+}
+
+impl Value {
+    pub fn display<'a>(&'a self, options: &'a Options) -> ValueDisplay<'a> {
+        ValueDisplay {
+            value: self,
+            options,
+        }
+    }
+}
+
+impl fmt::Display for ValueDisplay<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.options.ascii {
+            write!(Ascii(f), "{}", Unicode(self))
+        } else {
+            self.write(f)
+        }
+    }
+}
+
+/// A [`ValueDisplay`] as written before [`Options::ascii`] applies.
+struct Unicode<'a>(&'a ValueDisplay<'a>);
+
+impl fmt::Display for Unicode<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.write(f)
+    }
+}
+
+/// Passes text on with every character outside ASCII spelled in it.
+struct Ascii<'a, 'b>(&'a mut fmt::Formatter<'b>);
+
+impl fmt::Write for Ascii<'_, '_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for c in s.chars() {
+            match c {
+                'á' | 'à' | 'â' => self.0.write_char('a')?,
+                'Á' | 'À' | 'Â' => self.0.write_char('A')?,
+                'ä' => self.0.write_str("ae")?,
+                'Ä' => self.0.write_str("Ae")?,
+                'ç' => self.0.write_char('c')?,
+                'Ç' => self.0.write_char('C')?,
+                'é' | 'è' | 'ê' | 'ë' => self.0.write_char('e')?,
+                'É' | 'È' | 'Ê' | 'Ë' => self.0.write_char('E')?,
+                'í' | 'ì' | 'î' | 'ï' => self.0.write_char('i')?,
+                'Í' | 'Ì' | 'Î' | 'Ï' => self.0.write_char('I')?,
+                'ñ' => self.0.write_char('n')?,
+                'Ñ' => self.0.write_char('N')?,
+                'ó' | 'ò' | 'ô' => self.0.write_char('o')?,
+                'Ó' | 'Ò' | 'Ô' => self.0.write_char('O')?,
+                'ö' => self.0.write_str("oe")?,
+                'Ö' => self.0.write_str("Oe")?,
+                'ú' | 'ù' | 'û' => self.0.write_char('u')?,
+                'Ú' | 'Ù' | 'Û' => self.0.write_char('U')?,
+                'ü' => self.0.write_str("ue")?,
+                'Ü' => self.0.write_str("Ue")?,
+                'ß' => self.0.write_str("ss")?,
+                'µ' => self.0.write_char('u')?,
+                c if c.is_ascii() => self.0.write_char(c)?,
+                _ => self.0.write_char('?')?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl ValueDisplay<'_> {
+    fn write(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.value {
+            Value::Date(d) => {
+                write_date(f, *d, self.options)?;
+                write_weekday(f, *d, self.options)
+            }
+            Value::DateTime(dt) => {
+                write_datetime(f, *dt, self.options)?;
+                write_weekday(f, dt.date(), self.options)
+            }
+            Value::Zoned(dt, zone) => {
+                write_datetime(f, *dt, self.options)?;
+                write!(f, " {}", zone)?;
+                write_weekday(f, dt.date(), self.options)
+            }
+            Value::Duration(dur) => write_duration(f, *dur, self.options),
+            Value::WorkingDays(days) => write!(f, "{days}wd"),
+            Value::Weekdays(days) => write!(f, "{days} weekdays"),
+            Value::Time(t) => write_time(f, *t, self.options),
+            Value::Bool(b) => b.fmt(f),
+            Value::Number(n) => n.fmt(f),
+            Value::Year(year) => year.fmt(f),
+            Value::YearMonth(year, month) => write_year_month(f, *year, *month, self.options),
+            Value::Months(_) => self.value.approximate().display(self.options).fmt(f),
+            Value::Ratio(numerator, denominator) => write_ratio(f, *numerator, *denominator),
+            Value::Relative(offset) => write_relative(f, *offset),
+            Value::Str(text) => f.write_str(text),
+        }
+    }
+}
+
+fn write_date(f: &mut fmt::Formatter, date: Date, options: &Options) -> fmt::Result {
+    match (options.locale, options.date_style) {
+        (None, DateStyle::Numeric) => {
+            write_year(f, date.year())?;
+            write!(f, "-{:02}-{:02}", date.month() as u8, date.day())
+        }
+        (locale, style) => {
+            let locale = locale.unwrap_or(Locale::EnGb);
+            write!(f, "{}", locale.format_date(date, style))
+        }
+    }
+}
+
+fn write_duration(f: &mut fmt::Formatter, duration: Duration, options: &Options) -> fmt::Result {
+    let duration = match options.precision.unit() {
+        Some(unit) => round_duration(duration, unit),
+        None => duration,
+    };
+    match options.duration_style {
+        DurationStyle::Relative if !duration.is_zero() => {
+            let text = format!("{}", duration);
+            match text.strip_prefix('-') {
+                Some(magnitude) => write!(f, "{} earlier", magnitude),
+                None => write!(f, "{} later", text),
+            }
+        }
+        DurationStyle::Words => write_duration_words(f, duration, options.locale),
+        _ => write!(f, "{}", duration),
+    }
+}
+
+/// Writes a duration as a list of whole units, each named in the singular or
+/// plural its count takes, as in `1 day, 2 hours`.
+fn write_duration_words(
+    f: &mut fmt::Formatter,
+    duration: Duration,
+    locale: Option<Locale>,
+) -> fmt::Result {
+    const LENGTHS: [u128; 7] = [
+        86_400_000_000_000,
+        3_600_000_000_000,
+        60_000_000_000,
+        1_000_000_000,
+        1_000_000,
+        1_000,
+        1,
+    ];
+    let locale = locale.unwrap_or(Locale::EnGb);
+    if duration.is_negative() {
+        f.write_str("-")?;
+    }
+
+    let mut remaining = duration.whole_nanoseconds().unsigned_abs();
+    if remaining == 0 {
+        return write!(f, "0 {}", locale.duration_unit_name(3, 0));
+    }
+    let mut separator = "";
+    for (index, length) in LENGTHS.into_iter().enumerate() {
+        let count = (remaining / length) as u64;
+        if count > 0 {
+            write!(
+                f,
+                "{}{} {}",
+                separator,
+                count,
+                locale.duration_unit_name(index, count)
+            )?;
+            separator = ", ";
+        }
+        remaining %= length;
+    }
+    Ok(())
+}
+
+/// Writes a ratio as a percentage with at most two decimals, as `18.75%`.
+fn write_ratio(f: &mut fmt::Formatter, numerator: i64, denominator: i64) -> fmt::Result {
+    let hundredths = div_round(i128::from(numerator) * 10_000, i128::from(denominator));
+    let sign = if hundredths < 0 { "-" } else { "" };
+    let (whole, fraction) = (hundredths.abs() / 100, hundredths.abs() % 100);
+    match fraction {
+        0 => write!(f, "{}{}%", sign, whole),
+        _ if fraction % 10 == 0 => write!(f, "{}{}.{}%", sign, whole, fraction / 10),
+        _ => write!(f, "{}{}.{:02}%", sign, whole, fraction),
+    }
+}
+
+/// Writes an offset from now in the largest unit it spans, rounded to the
+/// nearest whole one, as `in 3 weeks` or `2 months ago`. Months count as 30
+/// days and years as 365.
+fn write_relative(f: &mut fmt::Formatter, offset: Duration) -> fmt::Result {
+    const UNITS: [(&str, i64); 7] = [
+        ("year", 365 * 86_400),
+        ("month", 30 * 86_400),
+        ("week", 7 * 86_400),
+        ("day", 86_400),
+        ("hour", 3_600),
+        ("minute", 60),
+        ("second", 1),
+    ];
+
+    let seconds = offset.whole_seconds().unsigned_abs();
+    let Some((unit, length)) = UNITS
+        .iter()
+        .map(|(unit, length)| (unit, length.unsigned_abs()))
+        .find(|(_, length)| seconds >= *length)
+    else {
+        return f.write_str("now");
+    };
+
+    let count = (seconds + length / 2) / length;
+    let plural = if count == 1 { "" } else { "s" };
+    if offset.is_negative() {
+        write!(f, "{} {}{} ago", count, unit, plural)
+    } else {
+        write!(f, "in {} {}{}", count, unit, plural)
+    }
+}
+
+/// Rounds `duration` to the nearest multiple of `unit`, halves away from zero.
+fn round_duration(duration: Duration, unit: Duration) -> Duration {
+    const NANOS_PER_SECOND: i128 = 1_000_000_000;
+    let (nanos, unit) = (duration.whole_nanoseconds(), unit.whole_nanoseconds());
+    let remainder = nanos % unit;
+    let mut rounded = nanos - remainder;
+    if remainder.abs() * 2 >= unit {
+        rounded += unit * remainder.signum();
+    }
+    i64::try_from(rounded / NANOS_PER_SECOND)
+        .map(|seconds| Duration::new(seconds, (rounded % NANOS_PER_SECOND) as i32))
+        .unwrap_or(duration)
+}
+
+/// Writes the weekday of `date` in parentheses when
+/// [`Options::show_weekday`] asks for it and the date style leaves it out.
+fn write_weekday(f: &mut fmt::Formatter, date: Date, options: &Options) -> fmt::Result {
+    if !options.show_weekday || options.date_style == DateStyle::Long {
+        return Ok(());
+    }
+    let locale = options.locale.unwrap_or(Locale::EnGb);
+    write!(f, " ({})", locale.weekday_name(date.weekday()))
+}
+
+/// Writes an ISO 8601 year, keeping four digits after the sign of years
+/// before year 0 (`-0043`).
+fn write_year(f: &mut fmt::Formatter, year: i32) -> fmt::Result {
+    if year < 0 {
+        write!(f, "-{:04}", year.unsigned_abs())
+    } else {
+        write!(f, "{:04}", year)
+    }
+}
+
+fn write_year_month(
+    f: &mut fmt::Formatter,
+    year: i32,
+    month: Month,
+    options: &Options,
+) -> fmt::Result {
+    match (options.locale, options.date_style) {
+        (None, DateStyle::Numeric) => {
+            write_year(f, year)?;
+            write!(f, "-{:02}", u8::from(month))
+        }
+        (locale, style) => {
+            let locale = locale.unwrap_or(Locale::EnGb);
+            write!(f, "{}", locale.format_year_month(year, month, style))
+        }
+    }
+}
+
+fn write_time(f: &mut fmt::Formatter, time: Time, options: &Options) -> fmt::Result {
+    match options.hour_format {
+        HourFormat::TwentyFourHour => write!(f, "{:02}:{:02}", time.hour(), time.minute())?,
+        HourFormat::TwelveHour => {
+            let hour = match time.hour() % HOURS_IN_HALF_DAY {
+                0 => HOURS_IN_HALF_DAY,
+                hour => hour,
+            };
+            write!(f, "{}:{:02}", hour, time.minute())?;
+        }
+    }
+
+    let second = time.second();
+    let nanosecond = time.nanosecond();
+
+    // Times are cut off rather than rounded, as a clock would show them.
+    // GNU syntax shows whole seconds unless asked otherwise, as `date` does.
+    let precision = match (options.precision, options.syntax) {
+        (Precision::Full, Syntax::Gnu) => Precision::Seconds,
+        (precision, _) => precision,
+    };
+    match precision {
+        Precision::Full if second != 0 || nanosecond != 0 => {
+            write!(f, ":{:02}", second)?;
+
+            if nanosecond != 0 {
+                let mut subseconds = format!("{:09}", nanosecond);
+                while subseconds.ends_with('0') {
+                    subseconds.pop();
+                }
+                write!(f, ".{}", subseconds)?;
+            }
+        }
+        Precision::Full | Precision::Minutes => {}
+        Precision::Seconds => write!(f, ":{:02}", second)?,
+        Precision::Milliseconds => {
+            write!(f, ":{:02}.{:03}", second, nanosecond / 1_000_000)?;
+        }
+    }
+
+    if options.hour_format == HourFormat::TwelveHour {
+        let meridiem = if time.hour() < HOURS_IN_HALF_DAY {
+            "AM"
+        } else {
+            "PM"
+        };
+        write!(f, " {}", meridiem)?;
+    }
+
+    Ok(())
+}
+
+fn write_datetime(
+    f: &mut fmt::Formatter,
+    datetime: OffsetDateTime,
+    options: &Options,
+) -> fmt::Result {
+    write_date(f, datetime.date(), options)?;
+    write!(f, " ")?;
+    write_time(f, datetime.time(), options)?;
+
+    let offset = datetime.offset();
+    if offset.whole_seconds() != 0 {
+        write!(f, " {}", format_offset(offset))?;
+    } else {
+        write!(f, " +00:00")?;
+    }
+
+    Ok(())
+}
+
+fn format_offset(offset: UtcOffset) -> String {
+    let total_seconds = offset.whole_seconds();
+    let sign = if total_seconds >= 0 { '+' } else { '-' };
+    let total_seconds = total_seconds.abs();
+
+    let hours = total_seconds / 3600;
+    let minutes = (total_seconds % 3600) / 60;
+    let seconds = total_seconds % 60;
+
+    if seconds == 0 {
+        format!("{}{:02}:{:02}", sign, hours, minutes)
+    } else {
+        format!("{}{:02}:{:02}:{:02}", sign, hours, minutes, seconds)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn twelve_hour() -> Options {
+        Options {
+            hour_format: HourFormat::TwelveHour,
+            ..Options::default()
+        }
+    }
+
+    #[test]
+    fn test_display_time_twelve_hour() {
+        let time = Time::from_hms(14, 30, 0).unwrap();
+        assert_eq!(
+            Value::Time(time).display(&twelve_hour()).to_string(),
+            "2:30 PM"
+        );
+    }
+
+    #[test]
+    fn test_display_time_twelve_hour_midnight_and_noon() {
+        let midnight = Time::from_hms(0, 5, 0).unwrap();
+        let noon = Time::from_hms(12, 0, 30).unwrap();
+        assert_eq!(
+            Value::Time(midnight).display(&twelve_hour()).to_string(),
+            "12:05 AM"
+        );
+        assert_eq!(
+            Value::Time(noon).display(&twelve_hour()).to_string(),
+            "12:00:30 PM"
+        );
+    }
+
+    #[test]
+    fn test_display_weekday() {
+        let date = Date::from_calendar_date(2024, time::Month::July, 4).unwrap();
+        let options = Options {
+            show_weekday: true,
+            ..Options::default()
+        };
+        assert_eq!(
+            Value::Date(date).display(&options).to_string(),
+            "2024-07-04 (Thursday)"
+        );
+
+        let datetime = date.midnight().assume_utc();
+        assert_eq!(
+            Value::DateTime(datetime).display(&options).to_string(),
+            "2024-07-04 00:00 +00:00 (Thursday)"
+        );
+
+        let long = Options {
+            date_style: DateStyle::Long,
+            ..options
+        };
+        assert_eq!(
+            Value::Date(date).display(&long).to_string(),
+            "Thursday 4 July 2024"
+        );
+    }
+
+    #[test]
+    fn test_display_duration_relative() {
+        let options = Options {
+            duration_style: DurationStyle::Relative,
+            ..Options::default()
+        };
+        let display = |duration| Value::Duration(duration).display(&options).to_string();
+
+        assert_eq!(display(-Duration::hours(76)), "3d4h earlier");
+        assert_eq!(display(Duration::minutes(90)), "1h30m later");
+        assert_eq!(display(Duration::ZERO), "0s");
+        assert_eq!(Value::Duration(-Duration::hours(76)).to_string(), "-3d4h");
+    }
+
+    #[test]
+    fn test_display_duration_in_words() {
+        let options = |locale| Options {
+            duration_style: DurationStyle::Words,
+            locale,
+            ..Options::default()
+        };
+        let display = |duration, locale| {
+            Value::Duration(duration)
+                .display(&options(locale))
+                .to_string()
+        };
+
+        assert_eq!(display(Duration::minutes(62), None), "1 hour, 2 minutes");
+        assert_eq!(
+            display(-Duration::new(86_401, 500_000_000), None),
+            "-1 day, 1 second, 500 milliseconds"
+        );
+        assert_eq!(display(Duration::ZERO, None), "0 seconds");
+        #[cfg(feature = "locales")]
+        {
+            assert_eq!(
+                display(Duration::hours(49), Some(Locale::DeDe)),
+                "2 Tage, 1 Stunde"
+            );
+            assert_eq!(display(Duration::ZERO, Some(Locale::FrFr)), "0 seconde");
+        }
+    }
+
+    #[test]
+    fn test_display_in_ascii() {
+        let options = Options {
+            ascii: true,
+            ..Options::default()
+        };
+
+        assert_eq!(
+            Value::Duration(Duration::microseconds(1500))
+                .display(&options)
+                .to_string(),
+            "1ms500us"
+        );
+        assert_eq!(
+            Value::Str("Élan, Ärger, Ñu".to_string())
+                .display(&options)
+                .to_string(),
+            "Elan, Aerger, Nu"
+        );
+
+        #[cfg(feature = "locales")]
+        {
+            let date = Date::from_calendar_date(2024, Month::March, 6).unwrap();
+            let options = Options {
+                locale: Some(Locale::DeDe),
+                date_style: DateStyle::Long,
+                show_weekday: true,
+                ..options
+            };
+            assert_eq!(
+                Value::Date(date).display(&options).to_string(),
+                "Mittwoch, 6. Maerz 2024"
+            );
+        }
+    }
+
+    #[test]
+    fn test_display_relative_in_largest_unit() {
+        let display = |offset| Value::Relative(offset).to_string();
+
+        assert_eq!(display(Duration::days(21)), "in 3 weeks");
+        assert_eq!(display(-Duration::days(61)), "2 months ago");
+        assert_eq!(display(Duration::minutes(89)), "in 1 hour");
+        assert_eq!(display(-Duration::seconds(1)), "1 second ago");
+        assert_eq!(display(Duration::milliseconds(400)), "now");
+    }
+
+    #[test]
+    fn test_display_with_precision() {
+        let options = |precision| Options {
+            precision,
+            ..Options::default()
+        };
+        let time = Value::Time(Time::from_hms_nano(14, 30, 15, 678_900_000).unwrap());
+        let duration = Value::Duration(Duration::new(-5_430, -600_000_000));
+
+        assert_eq!(
+            time.display(&options(Precision::Full)).to_string(),
+            "14:30:15.6789"
+        );
+        assert_eq!(
+            time.display(&options(Precision::Minutes)).to_string(),
+            "14:30"
+        );
+        assert_eq!(
+            time.display(&options(Precision::Seconds)).to_string(),
+            "14:30:15"
+        );
+        assert_eq!(
+            time.display(&options(Precision::Milliseconds)).to_string(),
+            "14:30:15.678"
+        );
+        assert_eq!(
+            duration.display(&options(Precision::Minutes)).to_string(),
+            "-1h31m"
+        );
+        assert_eq!(
+            duration.display(&options(Precision::Seconds)).to_string(),
+            "-1h30m31s"
+        );
+    }
+
+    #[test]
+    fn test_parse_hour_format() {
+        assert_eq!("12".parse::<HourFormat>(), Ok(HourFormat::TwelveHour));
+        assert_eq!("24h".parse::<HourFormat>(), Ok(HourFormat::TwentyFourHour));
+        assert!("13".parse::<HourFormat>().is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "locales")]
+    fn test_display_date_localized() {
+        let date = Date::from_calendar_date(2024, time::Month::January, 31).unwrap();
+        let options = Options {
+            locale: Some(Locale::ItIt),
+            ..Options::default()
+        };
+        assert_eq!(
+            Value::Date(date).display(&options).to_string(),
+            "31/01/2024"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "locales")]
+    fn test_display_year_month() {
+        let value = Value::YearMonth(2024, Month::March);
+        assert_eq!(value.display(&Options::default()).to_string(), "2024-03");
+
+        let options = Options {
+            locale: Some(Locale::ItIt),
+            date_style: DateStyle::Long,
+            ..Options::default()
+        };
+        assert_eq!(value.display(&options).to_string(), "marzo 2024");
+    }
+}
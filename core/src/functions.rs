@@ -0,0 +1,1052 @@
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt::Write;
+use core::ops::RangeInclusive;
+use time::{Date, Duration, Month, OffsetDateTime, Time, UtcOffset, Weekday};
+
+use crate::calendar::{Calendar, count_days, date_from_parts};
+use crate::detect::{TimestampFormat, detect};
+use crate::evaluator::{EvalError, Value, Warning, current_day, evaluate_at};
+use crate::lexer::Lexer;
+use crate::locale::Locale;
+use crate::options::{Options, Paydays};
+use crate::parser::{Recurrence, TwoDigitYears, parse_ast};
+use crate::zone::Zone;
+
+/// What a built-in function may need beyond its arguments.
+pub struct Context<'a> {
+    pub options: &'a Options,
+    /// The reference time, or `None` when there is none to read.
+    pub now: Option<OffsetDateTime>,
+    /// The language month and weekday names are read and written in.
+    pub locale: Locale,
+    /// Warnings the call raised, for the evaluator to pass on.
+    pub warnings: Vec<Warning>,
+}
+
+impl<'a> Context<'a> {
+    pub fn new(options: &'a Options, now: Option<OffsetDateTime>) -> Self {
+        Context {
+            options,
+            now,
+            locale: options.locale.unwrap_or(Locale::EnGb),
+            warnings: Vec::new(),
+        }
+    }
+
+    fn now(&self) -> Result<OffsetDateTime, EvalError> {
+        self.now.ok_or(EvalError::NoReferenceTime)
+    }
+}
+
+/// Evaluates a call to one of the built-in functions, such as
+/// `days_in` or `format`, on values already evaluated.
+pub fn call(name: &str, args: &[Value], context: &mut Context) -> Result<Value, EvalError> {
+    match name {
+        "abs" => abs(name, args),
+        "auto" => auto(name, args, context),
+        "between" => between(name, args),
+        "date" => date(name, args),
+        "datetime" => datetime(name, args),
+        "days_in" => days_in(name, args),
+        "format" => format(name, args, context.locale),
+        "negate" => negate(name, args),
+        "parse" => {
+            let (value, warning) =
+                parse(name, args, context.locale, context.options.two_digit_years)?;
+            context.warnings.extend(warning);
+            Ok(value)
+        }
+        "paydays" => paydays(
+            name,
+            args,
+            context.options.paydays.as_ref(),
+            &context.options.calendar,
+        ),
+        "relative" => {
+            let now = context.now()?;
+            relative(
+                name,
+                args,
+                now,
+                current_day(now, &context.options.day_parts),
+            )
+        }
+        "time" => time(name, args),
+        "year" => year(name, args),
+        _ => Err(EvalError::UnknownFunction(name.to_string())),
+    }
+}
+
+/// `abs(x)`: an amount without its sign, so `abs(-2h)` is `2h`.
+fn abs(name: &str, args: &[Value]) -> Result<Value, EvalError> {
+    let result = match args {
+        [Value::Duration(duration)] if duration.is_negative() => {
+            duration.checked_neg().map(Value::Duration)
+        }
+        [Value::Duration(duration)] => Some(Value::Duration(*duration)),
+        [Value::WorkingDays(days)] => days.checked_abs().map(Value::WorkingDays),
+        [Value::Weekdays(days)] => days.checked_abs().map(Value::Weekdays),
+        [Value::Months(months)] => months.checked_abs().map(Value::Months),
+        [Value::Number(number)] => number.checked_abs().map(Value::Number),
+        _ => return Err(EvalError::Arguments(name.to_string(), args.to_vec())),
+    };
+    result.ok_or_else(|| EvalError::OutOfRange(format!("{}({})", name, args[0])))
+}
+
+/// `auto(text)`: a timestamp in whichever format [`detect`] finds, or else a
+/// tcalc expression for a date or date and time.
+fn auto(name: &str, args: &[Value], context: &mut Context) -> Result<Value, EvalError> {
+    let [Value::Str(text)] = args else {
+        return Err(EvalError::Arguments(name.to_string(), args.to_vec()));
+    };
+    let (value, format, warning) = match detect(text) {
+        Some(detected) => detected,
+        None => {
+            let undetected = || EvalError::Undetected(text.clone());
+            let options = context.options;
+            let ast = parse_ast(Lexer::new(text), options).map_err(|_| undetected())?;
+            let evaluation = evaluate_at(&ast, options, context.now, &[], &[], false)
+                .map_err(|_| undetected())?;
+            if !matches!(
+                evaluation.value,
+                Value::Date(_) | Value::DateTime(_) | Value::Zoned(..)
+            ) {
+                return Err(undetected());
+            }
+            context.warnings.extend(evaluation.warnings);
+            (evaluation.value, TimestampFormat::Native, None)
+        }
+    };
+    context.warnings.push(Warning::Detected { format });
+    context.warnings.extend(warning);
+    Ok(value)
+}
+
+/// `relative(x)`: how far a date or date-time is from now, in words. A date
+/// counts whole days from `today`.
+fn relative(
+    name: &str,
+    args: &[Value],
+    now: OffsetDateTime,
+    today: Date,
+) -> Result<Value, EvalError> {
+    match args {
+        [Value::Date(date)] => Ok(Value::Relative(*date - today)),
+        [Value::DateTime(datetime) | Value::Zoned(datetime, _)] => {
+            Ok(Value::Relative(*datetime - now))
+        }
+        _ => Err(EvalError::Arguments(name.to_string(), args.to_vec())),
+    }
+}
+
+/// `format(x, pattern)`: a date, time or date-time written out by a
+/// strftime-like `pattern`, as in `format(2024/03/01, "%A, %d %B")`, with
+/// month and weekday names in `locale`.
+fn format(name: &str, args: &[Value], locale: Locale) -> Result<Value, EvalError> {
+    let [value, Value::Str(pattern)] = args else {
+        return Err(EvalError::Arguments(name.to_string(), args.to_vec()));
+    };
+    let parts = match *value {
+        Value::Date(date) => Parts {
+            date: Some(date),
+            ..Parts::default()
+        },
+        Value::DateTime(at) => Parts::at(at, None),
+        Value::Zoned(at, zone) => Parts::at(at, Some(zone)),
+        Value::Time(time) => Parts {
+            time: Some(time),
+            ..Parts::default()
+        },
+        _ => return Err(EvalError::Arguments(name.to_string(), args.to_vec())),
+    };
+
+    let mut text = String::new();
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '%' => {
+                // A `%` at the very end stands for itself.
+                let directive = chars.next().unwrap_or('%');
+                write_directive(&mut text, directive, parts, locale)
+                    .ok_or_else(|| EvalError::Directive(directive, value.clone()))?;
+            }
+            c => text.push(c),
+        }
+    }
+    Ok(Value::Str(text))
+}
+
+/// What a value passed to `format` has to offer its directives.
+#[derive(Clone, Copy, Default)]
+struct Parts {
+    date: Option<Date>,
+    time: Option<Time>,
+    at: Option<OffsetDateTime>,
+    zone: Option<Zone>,
+}
+
+impl Parts {
+    fn at(at: OffsetDateTime, zone: Option<Zone>) -> Self {
+        Parts {
+            date: Some(at.date()),
+            time: Some(at.time()),
+            at: Some(at),
+            zone,
+        }
+    }
+}
+
+/// Writes the part `directive` stands for, or nothing if it is unknown or
+/// the value lacks that part.
+fn write_directive(text: &mut String, directive: char, parts: Parts, locale: Locale) -> Option<()> {
+    let date = || parts.date;
+    let time = || parts.time;
+    let offset = || parts.at.map(OffsetDateTime::offset);
+    let abbreviated = |name: &str| name.chars().take(3).collect::<String>();
+    match directive {
+        'Y' => write!(text, "{:04}", date()?.year()),
+        'y' => write!(text, "{:02}", date()?.year().rem_euclid(100)),
+        'm' => write!(text, "{:02}", u8::from(date()?.month())),
+        'd' => write!(text, "{:02}", date()?.day()),
+        'e' => write!(text, "{:2}", date()?.day()),
+        'j' => write!(text, "{:03}", date()?.ordinal()),
+        'B' => write!(text, "{}", locale.month_name(date()?.month())),
+        'b' => write!(text, "{}", abbreviated(locale.month_name(date()?.month()))),
+        'A' => write!(text, "{}", locale.weekday_name(date()?.weekday())),
+        'a' => write!(
+            text,
+            "{}",
+            abbreviated(locale.weekday_name(date()?.weekday()))
+        ),
+        'u' => write!(text, "{}", date()?.weekday().number_from_monday()),
+        'V' => write!(text, "{:02}", date()?.iso_week()),
+        'F' => {
+            let date = date()?;
+            let month = u8::from(date.month());
+            write!(text, "{:04}-{:02}-{:02}", date.year(), month, date.day())
+        }
+        'H' => write!(text, "{:02}", time()?.hour()),
+        'I' => write!(text, "{:02}", (time()?.hour() + 11) % 12 + 1),
+        'p' => write!(text, "{}", if time()?.hour() < 12 { "AM" } else { "PM" }),
+        'M' => write!(text, "{:02}", time()?.minute()),
+        'S' => write!(text, "{:02}", time()?.second()),
+        'f' => write!(text, "{:09}", time()?.nanosecond()),
+        'R' => write!(text, "{:02}:{:02}", time()?.hour(), time()?.minute()),
+        'T' => {
+            let time = time()?;
+            let (hour, minute, second) = time.as_hms();
+            write!(text, "{:02}:{:02}:{:02}", hour, minute, second)
+        }
+        'z' => write_offset(text, offset()?, ""),
+        'Z' => match (parts.zone, parts.at) {
+            (Some(zone), Some(at)) => write!(text, "{}", zone.abbreviation(at)),
+            (None, Some(at)) if at.offset().is_utc() => write!(text, "UTC"),
+            _ => write_offset(text, offset()?, ":"),
+        },
+        's' => write!(text, "{}", parts.at?.unix_timestamp()),
+        '%' => write!(text, "%"),
+        _ => return None,
+    }
+    .ok()
+}
+
+/// Writes `offset` as `+HH` and `MM`, with `separator` between them.
+fn write_offset(text: &mut String, offset: UtcOffset, separator: &str) -> core::fmt::Result {
+    let sign = if offset.is_negative() { '-' } else { '+' };
+    let (hours, minutes, _) = offset.as_hms();
+    write!(
+        text,
+        "{}{:02}{}{:02}",
+        sign,
+        hours.unsigned_abs(),
+        separator,
+        minutes.unsigned_abs()
+    )
+}
+
+/// `parse(text, pattern)`: the date, date-time, month, year or time of day
+/// that `text` writes with the directives of [`format`], as in
+/// `parse("05.01.2024", "%d.%m.%Y")`. A space in `pattern` matches any run
+/// of whitespace. A date and time without `%z` is read as UTC, with a
+/// warning saying so.
+fn parse(
+    name: &str,
+    args: &[Value],
+    locale: Locale,
+    two_digit_years: TwoDigitYears,
+) -> Result<(Value, Option<Warning>), EvalError> {
+    match args {
+        [Value::Str(text), Value::Str(pattern)] => {
+            read_pattern(text, pattern, locale, two_digit_years)
+        }
+        _ => Err(EvalError::Arguments(name.to_string(), args.to_vec())),
+    }
+}
+
+/// Reads `text` as `parse` does.
+pub(crate) fn read_pattern(
+    text: &str,
+    pattern: &str,
+    locale: Locale,
+    two_digit_years: TwoDigitYears,
+) -> Result<(Value, Option<Warning>), EvalError> {
+    match read_prefix(text, pattern, locale, two_digit_years)? {
+        Some((value, warning, length)) if length == text.len() => Ok((value, warning)),
+        _ => Err(EvalError::Unparsable(text.to_string(), pattern.to_string())),
+    }
+}
+
+/// Reads the start of `text` as `pattern` describes, with the length of
+/// text read, or nothing if `text` does not start with a match.
+pub(crate) fn read_prefix(
+    text: &str,
+    pattern: &str,
+    locale: Locale,
+    two_digit_years: TwoDigitYears,
+) -> Result<Option<(Value, Option<Warning>, usize)>, EvalError> {
+    let mut reader = Reader {
+        text,
+        locale,
+        two_digit_years,
+        read: Read::default(),
+    };
+    if reader.pattern(pattern)?.is_none() {
+        return Ok(None);
+    }
+    let length = text.len() - reader.text.len();
+    Ok(reader
+        .read
+        .value()
+        .map(|(value, warning)| (value, warning, length)))
+}
+
+/// The parts `parse` has read so far.
+#[derive(Default)]
+struct Read {
+    year: Option<i32>,
+    month: Option<Month>,
+    day: Option<u8>,
+    weekday: Option<Weekday>,
+    hour: Option<u8>,
+    /// Whether the hour is in the afternoon, from `%p`.
+    pm: Option<bool>,
+    minute: Option<u8>,
+    second: Option<u8>,
+    nanosecond: Option<u32>,
+    offset: Option<UtcOffset>,
+    timestamp: Option<i64>,
+}
+
+impl Read {
+    /// The value the parts make up, if they make a whole one.
+    fn value(&self) -> Option<(Value, Option<Warning>)> {
+        let hour = match (self.hour, self.pm) {
+            (Some(hour @ 1..=12), Some(pm)) => Some(hour % 12 + if pm { 12 } else { 0 }),
+            (_, Some(_)) => return None,
+            (hour, None) => hour,
+        };
+        let time = match (hour, self.minute) {
+            (Some(hour), Some(minute)) => {
+                let second = self.second.unwrap_or(0);
+                let nanosecond = self.nanosecond.unwrap_or(0);
+                Some(Time::from_hms_nano(hour, minute, second, nanosecond).ok()?)
+            }
+            (None, None) if self.second.is_none() && self.nanosecond.is_none() => None,
+            _ => return None,
+        };
+        let date = match (self.year, self.month, self.day) {
+            (Some(year), Some(month), Some(day)) => {
+                Some(Date::from_calendar_date(year, month, day).ok()?)
+            }
+            (_, _, None) => None,
+            _ => return None,
+        };
+        if let (Some(date), Some(weekday)) = (date, self.weekday)
+            && date.weekday() != weekday
+        {
+            return None;
+        }
+
+        let value = match (self.timestamp, date, time) {
+            (Some(timestamp), None, None) => {
+                let offset = self.offset.unwrap_or(UtcOffset::UTC);
+                // `checked_to_offset` still asserts at the edges of the
+                // supported range, so shift the wall clock by hand.
+                let at = OffsetDateTime::from_unix_timestamp(timestamp)
+                    .ok()?
+                    .checked_add(Duration::seconds(offset.whole_seconds().into()))?;
+                Value::DateTime(at.replace_offset(offset))
+            }
+            (None, Some(date), Some(time)) => match self.offset {
+                Some(offset) => Value::DateTime(date.with_time(time).assume_offset(offset)),
+                None => {
+                    let at = date.with_time(time).assume_utc();
+                    return Some((Value::DateTime(at), Some(Warning::AssumedUtc)));
+                }
+            },
+            // Only a date and time has an offset.
+            _ if self.offset.is_some() => return None,
+            (None, Some(date), None) => Value::Date(date),
+            (None, None, Some(time)) if self.year.is_none() && self.month.is_none() => {
+                Value::Time(time)
+            }
+            (None, None, None) => match (self.year, self.month) {
+                (Some(year), Some(month)) => Value::YearMonth(year, month),
+                (Some(year), None) => Value::Year(year),
+                _ => return None,
+            },
+            _ => return None,
+        };
+        Some((value, None))
+    }
+}
+
+/// Reads the parts of a date or time from the front of `text`.
+struct Reader<'a> {
+    text: &'a str,
+    locale: Locale,
+    two_digit_years: TwoDigitYears,
+    read: Read,
+}
+
+impl<'a> Reader<'a> {
+    fn literal(&mut self, c: char) -> Option<()> {
+        self.text = self.text.strip_prefix(c)?;
+        Some(())
+    }
+
+    /// Reads everything `pattern` describes, or nothing if the text does
+    /// not match it.
+    fn pattern(&mut self, pattern: &str) -> Result<Option<()>, EvalError> {
+        let mut chars = pattern.chars();
+        while let Some(c) = chars.next() {
+            let read = match c {
+                // A `%` at the very end stands for itself.
+                '%' => match chars.next().unwrap_or('%') {
+                    '%' => self.literal('%'),
+                    'F' => self.pattern("%Y-%m-%d")?,
+                    'T' => self.pattern("%H:%M:%S")?,
+                    'R' => self.pattern("%H:%M")?,
+                    directive => self.directive(directive)?,
+                },
+                c if c.is_whitespace() => {
+                    self.text = self.text.trim_start();
+                    Some(())
+                }
+                c => self.literal(c),
+            };
+            if read.is_none() {
+                return Ok(None);
+            }
+        }
+        Ok(Some(()))
+    }
+
+    /// Reads the part `directive` stands for, or nothing if the text does
+    /// not start with one. Fails on a directive `parse` does not know.
+    fn directive(&mut self, directive: char) -> Result<Option<()>, EvalError> {
+        let read = match directive {
+            'Y' => self
+                .signed(4)
+                .and_then(|year| set(&mut self.read.year, i32::try_from(year).ok()?)),
+            'y' => match (self.number(2), self.two_digit_years) {
+                (Some(year), TwoDigitYears::Strict) => return Err(EvalError::TwoDigitYear(year)),
+                (Some(year), TwoDigitYears::Pivot(pivot)) => {
+                    let century = if year < i64::from(pivot) { 2000 } else { 1900 };
+                    set(&mut self.read.year, century + year as i32)
+                }
+                (None, _) => None,
+            },
+            'm' => self
+                .small(1..=12)
+                .and_then(|month| Month::try_from(month).ok())
+                .and_then(|month| set(&mut self.read.month, month)),
+            'd' => self
+                .small(1..=31)
+                .and_then(|day| set(&mut self.read.day, day)),
+            'e' => {
+                self.text = self.text.trim_start_matches(' ');
+                self.small(1..=31)
+                    .and_then(|day| set(&mut self.read.day, day))
+            }
+            'B' | 'b' => self
+                .word()
+                .and_then(|name| {
+                    (self.locale.month_from_name(name))
+                        .or_else(|| Locale::EnGb.month_from_name(name))
+                })
+                .and_then(|month| set(&mut self.read.month, month)),
+            'A' | 'a' => self
+                .word()
+                .and_then(|name| {
+                    weekday_named(self.locale, name).or_else(|| weekday_named(Locale::EnGb, name))
+                })
+                .and_then(|weekday| set(&mut self.read.weekday, weekday)),
+            'H' => self
+                .small(0..=23)
+                .and_then(|hour| set(&mut self.read.hour, hour)),
+            'I' => self
+                .small(1..=12)
+                .and_then(|hour| set(&mut self.read.hour, hour)),
+            'M' => self
+                .small(0..=59)
+                .and_then(|minute| set(&mut self.read.minute, minute)),
+            'S' => self
+                .small(0..=59)
+                .and_then(|second| set(&mut self.read.second, second)),
+            'f' => self
+                .fraction()
+                .and_then(|nanosecond| set(&mut self.read.nanosecond, nanosecond)),
+            'p' => self
+                .word()
+                .and_then(|word| match word.to_lowercase().as_str() {
+                    "am" => Some(false),
+                    "pm" => Some(true),
+                    _ => None,
+                })
+                .and_then(|pm| set(&mut self.read.pm, pm)),
+            'z' => self
+                .offset()
+                .and_then(|offset| set(&mut self.read.offset, offset)),
+            's' => self
+                .signed(19)
+                .and_then(|timestamp| set(&mut self.read.timestamp, timestamp)),
+            _ => {
+                let rest = Value::Str(self.text.to_string());
+                return Err(EvalError::Directive(directive, rest));
+            }
+        };
+        Ok(read)
+    }
+
+    /// Reads up to `digits` digits.
+    fn number(&mut self, digits: usize) -> Option<i64> {
+        let length = self
+            .text
+            .chars()
+            .take(digits)
+            .take_while(char::is_ascii_digit)
+            .count();
+        let (number, rest) = self.text.split_at(length);
+        let number = number.parse().ok()?;
+        self.text = rest;
+        Some(number)
+    }
+
+    /// Reads up to `digits` digits after an optional sign.
+    fn signed(&mut self, digits: usize) -> Option<i64> {
+        let text = self.text;
+        let negative = match text.chars().next() {
+            Some('-') => true,
+            Some('+') => false,
+            _ => return self.number(digits),
+        };
+        self.text = &text[1..];
+        match self.number(digits) {
+            Some(number) if negative => Some(-number),
+            Some(number) => Some(number),
+            None => {
+                self.text = text;
+                None
+            }
+        }
+    }
+
+    /// Reads one or two digits making a number within `range`.
+    fn small(&mut self, range: RangeInclusive<u8>) -> Option<u8> {
+        let number = u8::try_from(self.number(2)?).ok()?;
+        range.contains(&number).then_some(number)
+    }
+
+    /// Reads the digits of a fraction of a second, up to nanoseconds.
+    fn fraction(&mut self) -> Option<u32> {
+        let text = self.text;
+        let number = self.number(9)?;
+        let digits = text.len() - self.text.len();
+        u32::try_from(number)
+            .ok()?
+            .checked_mul(10u32.pow((9 - digits) as u32))
+    }
+
+    /// Reads a run of letters.
+    fn word(&mut self) -> Option<&'a str> {
+        let length = self
+            .text
+            .find(|c: char| !c.is_alphabetic())
+            .unwrap_or(self.text.len());
+        let (word, rest) = self.text.split_at(length);
+        self.text = rest;
+        (!word.is_empty()).then_some(word)
+    }
+
+    /// Reads an offset from UTC, as `+0200` or `+02:00`, or UTC itself as
+    /// `Z`, `UTC`, `GMT` or `UT`.
+    fn offset(&mut self) -> Option<UtcOffset> {
+        if let Some(name) = ["Z", "UTC", "GMT", "UT"]
+            .into_iter()
+            .find(|name| self.text.starts_with(name))
+        {
+            self.text = &self.text[name.len()..];
+            return Some(UtcOffset::UTC);
+        }
+        let negative = match self.text.chars().next()? {
+            '+' => false,
+            '-' => true,
+            _ => return None,
+        };
+        self.text = &self.text[1..];
+        let hours = i8::try_from(self.number(2)?).ok()?;
+        if let Some(rest) = self.text.strip_prefix(':') {
+            self.text = rest;
+        }
+        let minutes = i8::try_from(self.number(2)?).ok()?;
+        let sign = if negative { -1 } else { 1 };
+        UtcOffset::from_hms(sign * hours, sign * minutes, 0).ok()
+    }
+}
+
+/// Fills in `part`, or fails if it already holds a different value, as when
+/// a date is given twice.
+fn set<T: PartialEq>(part: &mut Option<T>, value: T) -> Option<()> {
+    match part {
+        Some(existing) if *existing != value => None,
+        _ => {
+            *part = Some(value);
+            Some(())
+        }
+    }
+}
+
+/// The weekday called `name` in `locale`, or whose name starts with it, as
+/// in `Mon`.
+fn weekday_named(locale: Locale, name: &str) -> Option<Weekday> {
+    if let Some(weekday) = locale.weekday_from_name(name) {
+        return Some(weekday);
+    }
+    let name = name.to_lowercase();
+    let mut prefixed = locale
+        .weekday_names()
+        .iter()
+        .enumerate()
+        .filter(|(_, weekday)| {
+            name.chars().count() >= 2 && weekday.to_lowercase().starts_with(&name)
+        });
+    match (prefixed.next(), prefixed.next()) {
+        (Some((index, _)), None) => Some(Weekday::Monday.nth_next(index as u8)),
+        _ => None,
+    }
+}
+
+/// The number of days from the first date of `range` to the second, both
+/// included, that `recurrence` falls on. Each day is checked in turn, so the
+/// range may span at most `max` days.
+pub fn recurrence_days(
+    name: &str,
+    recurrence: Recurrence,
+    range: [Value; 2],
+    calendar: &Calendar,
+    paydays: Option<&Paydays>,
+    max: usize,
+) -> Result<i64, EvalError> {
+    if recurrence == Recurrence::Payday && paydays.is_none() {
+        return Err(EvalError::NoPaydays);
+    }
+    let [from, to] = range.each_ref().map(|value| match value {
+        &Value::Date(date) => Some(date),
+        Value::DateTime(datetime) | Value::Zoned(datetime, _) => Some(datetime.date()),
+        _ => None,
+    });
+    let (Some(from), Some(to)) = (from, to) else {
+        return Err(EvalError::Arguments(name.to_string(), Vec::from(range)));
+    };
+
+    let days = (to - from).whole_days();
+    if days.unsigned_abs() > max as u64 {
+        return Err(EvalError::TooManySteps(days, max));
+    }
+    Ok(count_days(from, to, recurrence, calendar, paydays))
+}
+
+/// `paydays(x)`, also written `paydays in x`: how many paydays fall in a
+/// year or a month.
+fn paydays(
+    name: &str,
+    args: &[Value],
+    paydays: Option<&Paydays>,
+    calendar: &Calendar,
+) -> Result<Value, EvalError> {
+    let paydays = paydays.ok_or(EvalError::NoPaydays)?;
+    let (year, months) = match args {
+        [Value::Year(year)] => (*year, Month::January..=Month::December),
+        [Value::Number(year)] => match i32::try_from(*year) {
+            Ok(year) => (year, Month::January..=Month::December),
+            Err(_) => return Err(EvalError::OutOfRange(format!("{}({})", name, args[0]))),
+        },
+        [Value::YearMonth(year, month)] => (*year, *month..=*month),
+        _ => return Err(EvalError::Arguments(name.to_string(), args.to_vec())),
+    };
+    let (first, last) = months.into_inner();
+    let from = Date::from_calendar_date(year, first, 1);
+    let to = Date::from_calendar_date(year, last, last.length(year));
+    let (Ok(from), Ok(to)) = (from, to) else {
+        return Err(EvalError::OutOfRange(format!("{}({})", name, args[0])));
+    };
+    let count = count_days(from, to, Recurrence::Payday, calendar, Some(paydays));
+    Ok(Value::Number(count))
+}
+
+/// `between(x, y)`: how far apart two dates, date-times or times are,
+/// whichever comes first.
+fn between(name: &str, args: &[Value]) -> Result<Value, EvalError> {
+    let difference = match args {
+        [Value::Date(from), Value::Date(to)] => *to - *from,
+        [
+            Value::DateTime(from) | Value::Zoned(from, _),
+            Value::DateTime(to) | Value::Zoned(to, _),
+        ] => *to - *from,
+        [Value::Time(from), Value::Time(to)] => *to - *from,
+        _ => return Err(EvalError::Arguments(name.to_string(), args.to_vec())),
+    };
+    Ok(Value::Duration(difference.abs()))
+}
+
+/// `negate(x)`: an amount with its sign flipped, the same as `-x`.
+fn negate(name: &str, args: &[Value]) -> Result<Value, EvalError> {
+    match args {
+        [
+            value @ (Value::Duration(_)
+            | Value::WorkingDays(_)
+            | Value::Weekdays(_)
+            | Value::Months(_)
+            | Value::Number(_)),
+        ] => value.clone().neg(),
+        _ => Err(EvalError::Arguments(name.to_string(), args.to_vec())),
+    }
+}
+
+/// `date(y, m, d)`: the date `y/m/d`, written without slashes so the order
+/// of its parts is never in doubt.
+fn date(name: &str, args: &[Value]) -> Result<Value, EvalError> {
+    match numbers(args) {
+        Some([year, month, day]) => Ok(Value::Date(calendar_date(year, month, day)?)),
+        None => Err(EvalError::Arguments(name.to_string(), args.to_vec())),
+    }
+}
+
+/// `time(h, m)` or `time(h, m, s)`: a time of day.
+fn time(name: &str, args: &[Value]) -> Result<Value, EvalError> {
+    let [hour, minute, second] = match (numbers(args), numbers(args)) {
+        (Some([hour, minute]), _) => [hour, minute, 0],
+        (_, Some(parts)) => parts,
+        _ => return Err(EvalError::Arguments(name.to_string(), args.to_vec())),
+    };
+    Ok(Value::Time(time_of_day(hour, minute, second)?))
+}
+
+/// `datetime(y, m, d, h, m)` or `datetime(y, m, d, h, m, s)`: a date and
+/// time in UTC.
+fn datetime(name: &str, args: &[Value]) -> Result<Value, EvalError> {
+    let [year, month, day, hour, minute, second] = match (numbers(args), numbers(args)) {
+        (Some([year, month, day, hour, minute]), _) => [year, month, day, hour, minute, 0],
+        (_, Some(parts)) => parts,
+        _ => return Err(EvalError::Arguments(name.to_string(), args.to_vec())),
+    };
+    let date = calendar_date(year, month, day)?;
+    let time = time_of_day(hour, minute, second)?;
+    Ok(Value::DateTime(date.with_time(time).assume_utc()))
+}
+
+/// The arguments as `N` plain numbers, if that is what they are.
+fn numbers<const N: usize>(args: &[Value]) -> Option<[i64; N]> {
+    let args: &[Value; N] = args.try_into().ok()?;
+    let mut numbers = [0; N];
+    for (number, arg) in numbers.iter_mut().zip(args) {
+        let Value::Number(value) = arg else {
+            return None;
+        };
+        *number = *value;
+    }
+    Some(numbers)
+}
+
+fn calendar_date(year: i64, month: i64, day: i64) -> Result<Date, EvalError> {
+    match (i32::try_from(year), u8::try_from(month), u8::try_from(day)) {
+        (Ok(year), Ok(month), Ok(day)) => date_from_parts(year, month, day),
+        _ => Err(EvalError::OutOfRange(format!(
+            "date({}, {}, {})",
+            year, month, day
+        ))),
+    }
+}
+
+fn time_of_day(hour: i64, minute: i64, second: i64) -> Result<Time, EvalError> {
+    match (
+        u8::try_from(hour),
+        u8::try_from(minute),
+        u8::try_from(second),
+    ) {
+        (Ok(hour), Ok(minute), Ok(second)) => {
+            Time::from_hms(hour, minute, second).map_err(|_| EvalError::Time(hour, minute, second))
+        }
+        _ => Err(EvalError::OutOfRange(format!(
+            "time({}, {}, {})",
+            hour, minute, second
+        ))),
+    }
+}
+
+/// `days_in(x)`: the number of days in the month or year containing `x`.
+fn days_in(name: &str, args: &[Value]) -> Result<Value, EvalError> {
+    let days = match args {
+        [Value::Date(date)] => date.month().length(date.year()),
+        [Value::DateTime(datetime) | Value::Zoned(datetime, _)] => {
+            datetime.month().length(datetime.year())
+        }
+        [Value::YearMonth(year, month)] => month.length(*year),
+        [Value::Year(year)] => return Ok(Value::Duration(days_in_year(*year))),
+        _ => return Err(EvalError::Arguments(name.to_string(), args.to_vec())),
+    };
+    Ok(Value::Duration(Duration::days(i64::from(days))))
+}
+
+/// `year(n)`: the calendar year `n`.
+fn year(name: &str, args: &[Value]) -> Result<Value, EvalError> {
+    match args {
+        [Value::Number(year)] => i32::try_from(*year)
+            .map(Value::Year)
+            .map_err(|_| EvalError::Arguments(name.to_string(), args.to_vec())),
+        [Value::Date(date)] => Ok(Value::Year(date.year())),
+        [Value::YearMonth(year, _)] => Ok(Value::Year(*year)),
+        _ => Err(EvalError::Arguments(name.to_string(), args.to_vec())),
+    }
+}
+
+fn days_in_year(year: i32) -> Duration {
+    Duration::days(i64::from(time::util::days_in_year(year)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::Month;
+
+    fn call(name: &str, args: &[Value]) -> Result<Value, EvalError> {
+        super::call(name, args, &mut Context::new(&Options::default(), None))
+    }
+
+    #[test]
+    fn test_days_in_leap_february() {
+        let result = call("days_in", &[Value::YearMonth(2024, Month::February)]).unwrap();
+        assert!(matches!(result, Value::Duration(d) if d == Duration::days(29)));
+    }
+
+    #[test]
+    fn test_format_writes_directives() {
+        let pattern = |pattern: &str| Value::Str(pattern.to_string());
+        let at = Date::from_calendar_date(2024, Month::March, 1)
+            .unwrap()
+            .with_hms(14, 5, 9)
+            .unwrap()
+            .assume_offset(UtcOffset::from_hms(-5, -30, 0).unwrap());
+
+        let text = |args: &[Value]| match format("format", args, Locale::EnGb).unwrap() {
+            Value::Str(text) => text,
+            value => panic!("expected a string, got {:?}", value),
+        };
+        assert_eq!(
+            text(&[
+                Value::DateTime(at),
+                pattern("%a %e %b %Y, %I:%M %p (%z) 100%%")
+            ]),
+            "Fri  1 Mar 2024, 02:05 PM (-0530) 100%"
+        );
+        assert_eq!(
+            text(&[Value::DateTime(at), pattern("%F %T %Z, day %j")]),
+            "2024-03-01 14:05:09 -05:30, day 061"
+        );
+        assert_eq!(
+            text(&[Value::Date(at.date()), pattern("%A, %d %B")]),
+            "Friday, 01 March"
+        );
+
+        assert!(matches!(
+            format(
+                "format",
+                &[Value::Date(at.date()), pattern("%H")],
+                Locale::EnGb
+            ),
+            Err(EvalError::Directive('H', _))
+        ));
+        assert!(matches!(
+            format(
+                "format",
+                &[Value::Date(at.date()), pattern("%Q")],
+                Locale::EnGb
+            ),
+            Err(EvalError::Directive('Q', _))
+        ));
+        assert!(format("format", &[Value::Number(1), pattern("%Y")], Locale::EnGb).is_err());
+    }
+
+    #[test]
+    fn test_parse_reads_patterns() {
+        let parsed = |text: &str, pattern: &str| {
+            let args = [
+                Value::Str(text.to_string()),
+                Value::Str(pattern.to_string()),
+            ];
+            parse("parse", &args, Locale::EnGb, TwoDigitYears::Strict)
+        };
+        let march = Date::from_calendar_date(2024, Month::March, 1).unwrap();
+
+        assert!(matches!(
+            parsed("01.03.2024", "%d.%m.%Y").unwrap(),
+            (Value::Date(date), None) if date == march
+        ));
+        assert!(matches!(
+            parsed("Fri,  1 mar 2024 2:05 pm +0530", "%a, %e %b %Y %I:%M %p %z").unwrap(),
+            (Value::DateTime(at), None) if at.date() == march
+                && at.time() == Time::from_hms(14, 5, 0).unwrap()
+                && at.offset() == UtcOffset::from_hms(5, 30, 0).unwrap()
+        ));
+        assert!(matches!(
+            parsed("2024-03-01T14:05:09", "%FT%T").unwrap(),
+            (Value::DateTime(_), Some(Warning::AssumedUtc))
+        ));
+        assert!(matches!(
+            parsed("March 2024", "%B %Y").unwrap(),
+            (Value::YearMonth(2024, Month::March), None)
+        ));
+
+        assert!(matches!(
+            parsed("Saturday 01/03/2024", "%A %d/%m/%Y"),
+            Err(EvalError::Unparsable(..))
+        ));
+        assert!(matches!(
+            parsed("01/03/2024 extra", "%d/%m/%Y"),
+            Err(EvalError::Unparsable(..))
+        ));
+        assert!(matches!(
+            parsed("01/03/24", "%d/%m/%y"),
+            Err(EvalError::TwoDigitYear(24))
+        ));
+        assert!(matches!(
+            parsed("01/03", "%d/%m"),
+            Err(EvalError::Unparsable(..))
+        ));
+        assert!(matches!(
+            parsed("253402300799 +2359", "%s %z"),
+            Err(EvalError::Unparsable(..))
+        ));
+        assert!(matches!(
+            parsed("-377705116800 -2359", "%s %z"),
+            Err(EvalError::Unparsable(..))
+        ));
+    }
+
+    #[test]
+    fn test_days_in_year() {
+        let result = call("days_in", &[Value::Year(2023)]).unwrap();
+        assert!(matches!(result, Value::Duration(d) if d == Duration::days(365)));
+    }
+
+    #[test]
+    fn test_abs_drops_sign() {
+        let result = call("abs", &[Value::Duration(Duration::hours(-2))]).unwrap();
+        assert!(matches!(result, Value::Duration(d) if d == Duration::hours(2)));
+        assert!(call("abs", &[Value::Number(i64::MIN)]).is_err());
+        assert!(call("abs", &[Value::Bool(true)]).is_err());
+    }
+
+    #[test]
+    fn test_negate_flips_sign() {
+        let result = call("negate", &[Value::WorkingDays(3)]).unwrap();
+        assert!(matches!(result, Value::WorkingDays(-3)));
+        assert!(call("negate", &[Value::Bool(true)]).is_err());
+        assert!(call("negate", &[]).is_err());
+    }
+
+    #[test]
+    fn test_between_ignores_order() {
+        let first = time::Date::from_calendar_date(2024, Month::January, 1).unwrap();
+        let later = first + Duration::days(9);
+
+        for args in [[first, later], [later, first]] {
+            let result = call("between", &args.map(Value::Date)).unwrap();
+            assert!(matches!(result, Value::Duration(d) if d == Duration::days(9)));
+        }
+    }
+
+    #[test]
+    fn test_relative_measures_from_now() {
+        let today = time::Date::from_calendar_date(2024, Month::January, 1).unwrap();
+        let now = today.midnight().assume_utc() + Duration::hours(12);
+
+        let result = relative(
+            "relative",
+            &[Value::Date(today - Duration::days(2))],
+            now,
+            today,
+        );
+        assert!(matches!(result, Ok(Value::Relative(d)) if d == Duration::days(-2)));
+        let result = relative(
+            "relative",
+            &[Value::DateTime(now + Duration::hours(3))],
+            now,
+            today,
+        );
+        assert!(matches!(result, Ok(Value::Relative(d)) if d == Duration::hours(3)));
+        assert!(relative("relative", &[Value::Number(1)], now, today).is_err());
+    }
+
+    #[test]
+    fn test_builds_dates_and_times_from_parts() {
+        let numbers = |parts: &[i64]| {
+            parts
+                .iter()
+                .map(|part| Value::Number(*part))
+                .collect::<Vec<_>>()
+        };
+
+        let result = call("date", &numbers(&[2024, 2, 29])).unwrap();
+        assert!(
+            matches!(result, Value::Date(d) if d == Date::from_calendar_date(2024, Month::February, 29).unwrap())
+        );
+        let result = call("time", &numbers(&[14, 30])).unwrap();
+        assert!(matches!(result, Value::Time(t) if t == Time::from_hms(14, 30, 0).unwrap()));
+        let result = call("datetime", &numbers(&[2024, 2, 29, 14, 30, 15])).unwrap();
+        assert!(matches!(result, Value::DateTime(dt) if dt.second() == 15 && dt.day() == 29));
+
+        assert!(matches!(
+            call("date", &numbers(&[2023, 2, 29])),
+            Err(EvalError::Date(2023, 2, 29))
+        ));
+        assert!(call("time", &numbers(&[25, 0])).is_err());
+        assert!(call("date", &numbers(&[2024, 300, 1])).is_err());
+        assert!(call("date", &numbers(&[2024, 2])).is_err());
+    }
+
+    #[test]
+    fn test_year_from_number() {
+        let result = call("year", &[Value::Number(2024)]).unwrap();
+        assert!(matches!(result, Value::Year(2024)));
+    }
+
+    #[test]
+    fn test_rejects_wrong_arguments() {
+        assert!(call("days_in", &[]).is_err());
+        assert!(call("year", &[Value::Bool(true)]).is_err());
+    }
+
+    #[test]
+    fn test_rejects_unknown_function() {
+        assert!(matches!(
+            call("nope", &[]),
+            Err(EvalError::UnknownFunction(_))
+        ));
+    }
+}
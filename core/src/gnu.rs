@@ -0,0 +1,378 @@
+//! Reads the date strings GNU `date -d` accepts, for [`Syntax::Gnu`]. Each
+//! string becomes an ordinary expression: an optional calendar date, time of
+//! day or weekday to start from, `now` without one, followed by relative
+//! items added to it in order.
+//!
+//! ```text
+//! <input>    ::= <item>*
+//! <item>     ::= 'now' | 'today' | 'tomorrow' | 'yesterday'
+//!              | ('next' | 'last' | 'this')? WEEKDAY
+//!              | NUMBER '-' NUMBER '-' NUMBER          (year, month, day)
+//!              | NUMBER '/' NUMBER '/' NUMBER          (month, day, year)
+//!              | NUMBER (':' NUMBER (':' NUMBER)?)? ('am' | 'pm')?
+//!              | <relative>
+//! <relative> ::= ('+' | '-')? NUMBER? UNIT 'ago'?
+//!              | ('next' | 'last' | 'this') UNIT 'ago'?
+//! ```
+//!
+//! A bare weekday, as with `next`, is the first one after today. Months and
+//! years are evaluated as `date` does them, by the calendar, with a day the
+//! target month lacks carried into the next.
+//!
+//! [`Syntax::Gnu`]: crate::Syntax::Gnu
+
+use alloc::{boxed::Box, format, string::String, vec::Vec};
+use core::iter::Peekable;
+
+use time::Weekday;
+
+use crate::lexer::{Lexer, Token};
+use crate::locale::Locale;
+use crate::parser::{
+    Expr, Keyword, Op, ParsingError, Unit, checked_year, date_parts, parse_time_parts,
+};
+
+/// Days in a fortnight.
+const FORTNIGHT_DAYS: i64 = 14;
+
+/// What the items read so far say.
+#[derive(Default)]
+struct Items {
+    date: Option<(i32, u8, u8)>,
+    time: Option<(u8, u8)>,
+    weekday: Option<Keyword>,
+    /// Amounts added to or taken from the start, in the order they were
+    /// written.
+    relative: Vec<(Op, i64, Unit)>,
+}
+
+/// Parses a GNU `date -d` string into the expression it stands for.
+pub(crate) fn parse(lexer: Lexer) -> Result<Expr, ParsingError> {
+    let mut tokens = lexer.peekable();
+    let mut items = Items::default();
+
+    loop {
+        match tokens.next() {
+            Some(Token::Eof) => break,
+            Some(Token::Error(err)) => return Err(ParsingError::Lex(err)),
+            Some(Token::Ident(word)) => parse_word(&mut tokens, &mut items, &word.to_lowercase())?,
+            Some(Token::Plus) => parse_relative(&mut tokens, &mut items, Op::Add)?,
+            Some(Token::Minus) => parse_relative(&mut tokens, &mut items, Op::Sub)?,
+            Some(Token::Number(number)) => parse_number(&mut tokens, &mut items, number)?,
+            Some(token) => return Err(ParsingError::UnexpectedToken(token)),
+            None => return Err(ParsingError::UnexpectedEof),
+        }
+    }
+
+    Ok(items.into_expr())
+}
+
+fn parse_word(
+    tokens: &mut Peekable<Lexer>,
+    items: &mut Items,
+    word: &str,
+) -> Result<(), ParsingError> {
+    let (op, count) = match word {
+        "now" | "today" => return Ok(()),
+        "tomorrow" => return push_relative(tokens, items, Op::Add, 1, "day"),
+        "yesterday" => return push_relative(tokens, items, Op::Sub, 1, "day"),
+        "next" => (Op::Add, 1),
+        "last" => (Op::Sub, 1),
+        "this" => (Op::Add, 0),
+        _ => match weekday_from_name(word) {
+            Some(weekday) => return set_weekday(items, Keyword::NextWeekday(weekday)),
+            None => return push_relative(tokens, items, Op::Add, 1, word),
+        },
+    };
+
+    let word = match tokens.next() {
+        Some(Token::Ident(word)) => word.to_lowercase(),
+        Some(token) => return Err(ParsingError::UnexpectedToken(token)),
+        None => return Err(ParsingError::UnexpectedEof),
+    };
+    match (weekday_from_name(&word), op) {
+        (Some(weekday), Op::Sub) => set_weekday(items, Keyword::LastWeekday(weekday)),
+        (Some(weekday), _) => set_weekday(items, Keyword::NextWeekday(weekday)),
+        (None, op) => push_relative(tokens, items, op, count, &word),
+    }
+}
+
+/// Reads a relative item after its sign.
+fn parse_relative(
+    tokens: &mut Peekable<Lexer>,
+    items: &mut Items,
+    op: Op,
+) -> Result<(), ParsingError> {
+    let count = match tokens.peek() {
+        Some(Token::Number(count)) => {
+            let count = *count;
+            tokens.next();
+            count
+        }
+        _ => 1,
+    };
+    let unit = expect_unit(tokens)?;
+    push_relative(tokens, items, op, count, &unit)
+}
+
+/// Reads an item starting with a number: a date, a time of day, or a count
+/// of units.
+fn parse_number(
+    tokens: &mut Peekable<Lexer>,
+    items: &mut Items,
+    number: i64,
+) -> Result<(), ParsingError> {
+    match tokens.peek() {
+        Some(Token::Minus) => {
+            tokens.next();
+            let month = expect_number(tokens)?;
+            expect(tokens, Token::Minus)?;
+            let day = expect_number(tokens)?;
+            set_date(items, number, month, day)
+        }
+        Some(Token::Slash) => {
+            tokens.next();
+            let day = expect_number(tokens)?;
+            expect(tokens, Token::Slash)?;
+            let year = expect_number(tokens)?;
+            set_date(items, year, number, day)
+        }
+        Some(Token::Colon) => {
+            tokens.next();
+            let minute = expect_number(tokens)?;
+            if tokens.next_if_eq(&Token::Colon).is_some() {
+                let second = expect_number(tokens)?;
+                if !(0..60).contains(&second) {
+                    return Err(ParsingError::InvalidTime(format!(
+                        "{}:{:02}:{:02}",
+                        number, minute, second
+                    )));
+                }
+                items.relative.push((Op::Add, second, Unit::Seconds));
+            }
+            set_time(tokens, items, number, minute)
+        }
+        Some(Token::Ident(word)) if matches!(word.to_lowercase().as_str(), "am" | "pm") => {
+            set_time(tokens, items, number, 0)
+        }
+        _ => {
+            let unit = expect_unit(tokens)?;
+            push_relative(tokens, items, Op::Add, number, &unit)
+        }
+    }
+}
+
+fn expect(tokens: &mut Peekable<Lexer>, expected: Token) -> Result<(), ParsingError> {
+    match tokens.next() {
+        Some(token) if token == expected => Ok(()),
+        Some(token) => Err(ParsingError::UnexpectedToken(token)),
+        None => Err(ParsingError::UnexpectedEof),
+    }
+}
+
+fn expect_number(tokens: &mut Peekable<Lexer>) -> Result<i64, ParsingError> {
+    match tokens.next() {
+        Some(Token::Number(number)) => Ok(number),
+        _ => Err(ParsingError::ExpectedNumber),
+    }
+}
+
+fn expect_unit(tokens: &mut Peekable<Lexer>) -> Result<String, ParsingError> {
+    match tokens.next() {
+        Some(Token::Ident(word)) => Ok(word.to_lowercase()),
+        _ => Err(ParsingError::ExpectedUnit),
+    }
+}
+
+/// Records `count` of the unit called `name`, turned around by a following
+/// `ago`.
+fn push_relative(
+    tokens: &mut Peekable<Lexer>,
+    items: &mut Items,
+    op: Op,
+    count: i64,
+    name: &str,
+) -> Result<(), ParsingError> {
+    let (length, unit) = unit_from_name(name)?;
+    let amount = count
+        .checked_mul(length)
+        .ok_or_else(|| ParsingError::OutOfRange(format!("{} {}", count, name)))?;
+    let ago = tokens
+        .next_if(|token| matches!(token, Token::Ident(word) if word.eq_ignore_ascii_case("ago")));
+    let op = match (ago, op) {
+        (Some(_), Op::Add) => Op::Sub,
+        (Some(_), _) => Op::Add,
+        (None, op) => op,
+    };
+    items.relative.push((op, amount, unit));
+    Ok(())
+}
+
+fn set_date(items: &mut Items, year: i64, month: i64, day: i64) -> Result<(), ParsingError> {
+    let date = date_parts(checked_year(year)?, month, day)?;
+    if items.date.replace(date).is_some() || items.weekday.is_some() {
+        return Err(ParsingError::Repeated("date"));
+    }
+    Ok(())
+}
+
+fn set_weekday(items: &mut Items, keyword: Keyword) -> Result<(), ParsingError> {
+    if items.weekday.replace(keyword).is_some() || items.date.is_some() {
+        return Err(ParsingError::Repeated("date"));
+    }
+    Ok(())
+}
+
+fn set_time(
+    tokens: &mut Peekable<Lexer>,
+    items: &mut Items,
+    hour: i64,
+    minute: i64,
+) -> Result<(), ParsingError> {
+    let meridiem = tokens.next_if(|token| {
+        matches!(token, Token::Ident(word) if matches!(word.to_lowercase().as_str(), "am" | "pm"))
+    });
+    let hour = match meridiem {
+        Some(_) if !(1..=12).contains(&hour) => return Err(ParsingError::InvalidHour(hour)),
+        Some(Token::Ident(word)) if word.eq_ignore_ascii_case("pm") => hour % 12 + 12,
+        Some(_) => hour % 12,
+        None => hour,
+    };
+    if items
+        .time
+        .replace(parse_time_parts(hour, minute)?)
+        .is_some()
+    {
+        return Err(ParsingError::Repeated("time of day"));
+    }
+    Ok(())
+}
+
+/// Looks up an English weekday by its name or the first three or more
+/// letters of it, as in `thu` or `thurs`.
+fn weekday_from_name(name: &str) -> Option<Weekday> {
+    if name.len() < 3 {
+        return None;
+    }
+    let index = Locale::EnUs
+        .weekday_names()
+        .iter()
+        .position(|weekday| weekday.to_lowercase().starts_with(name))?;
+    Some(Weekday::Monday.nth_next(index as u8))
+}
+
+/// The units GNU `date` knows, as a length in one of ours.
+fn unit_from_name(name: &str) -> Result<(i64, Unit), ParsingError> {
+    let unit = match name {
+        "year" | "years" => Unit::Years,
+        "month" | "months" => Unit::Months,
+        "fortnight" | "fortnights" => return Ok((FORTNIGHT_DAYS, Unit::Days)),
+        "week" | "weeks" => Unit::Weeks,
+        "day" | "days" => Unit::Days,
+        "hour" | "hours" => Unit::Hours,
+        "minute" | "minutes" | "min" | "mins" => Unit::Minutes,
+        "second" | "seconds" | "sec" | "secs" => Unit::Seconds,
+        _ => return Err(ParsingError::UnknownKeyword(String::from(name))),
+    };
+    Ok((1, unit))
+}
+
+impl Items {
+    fn into_expr(self) -> Expr {
+        let start = match (self.date, self.time, self.weekday) {
+            (Some((year, month, day)), Some((hour, minute)), _) => {
+                Expr::DateTime(year, month, day, hour, minute)
+            }
+            (Some((year, month, day)), None, _) => Expr::Date(year, month, day),
+            (None, Some((hour, minute)), weekday) => {
+                Expr::KeywordTime(weekday.unwrap_or(Keyword::Today), hour, minute)
+            }
+            (None, None, Some(weekday)) => Expr::Keyword(weekday),
+            (None, None, None) => Expr::Keyword(Keyword::Now),
+        };
+
+        self.relative
+            .into_iter()
+            .fold(start, |expr, (op, amount, unit)| {
+                Expr::BinOp(Box::new(expr), op, Box::new(Expr::Duration(amount, unit)))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Options, Syntax, run_with_options};
+    use alloc::string::ToString;
+    use time::OffsetDateTime;
+
+    fn translate(input: &str) -> String {
+        parse(Lexer::new(input)).unwrap().to_string()
+    }
+
+    fn run_gnu(input: &str) -> String {
+        let options = Options {
+            syntax: Syntax::Gnu,
+            now: Some(
+                OffsetDateTime::from_unix_timestamp_nanos(1_706_692_542_123_456_789).unwrap(),
+            ),
+            ..Options::default()
+        };
+        run_with_options(input, &options).unwrap()
+    }
+
+    #[test]
+    fn test_reads_relative_items() {
+        assert_eq!(translate("2 days ago"), "now - 2d");
+        assert_eq!(translate("fortnight"), "now + 14d");
+        assert_eq!(translate("next week"), "now + 1w");
+        assert_eq!(translate("last year"), "now - 1y");
+        assert_eq!(translate("+3 hours -30 min"), "now + 3h - 30m");
+        assert_eq!(translate("tomorrow"), "now + 1d");
+    }
+
+    #[test]
+    fn test_reads_dates_times_and_weekdays() {
+        assert_eq!(translate("next thursday"), "next thursday");
+        assert_eq!(translate("last Fri"), "last friday");
+        assert_eq!(translate("2024-03-01 1 day ago"), "2024/03/01 - 1d");
+        assert_eq!(translate("03/01/2024 14:30:15"), "2024/03/01 14:30 + 15s");
+        assert_eq!(translate("5pm"), "today 17:00");
+        assert_eq!(translate("monday 9am"), "next monday 09:00");
+    }
+
+    #[test]
+    fn test_matches_date_across_month_ends() {
+        // As printed by `TZ=UTC date -d '…' '+%F %T'`.
+        for (input, expected) in [
+            ("2024-01-31 +1 month", "2024-03-02"),
+            ("2024-03-31 -1 month", "2024-03-02"),
+            ("2023-01-31 +1 month", "2023-03-03"),
+            ("2024-12-31 +2 months", "2025-03-03"),
+            ("2024-02-29 +1 year", "2025-03-01"),
+            ("2024-05-31 1 month ago", "2024-05-01"),
+            ("2024-01-30 +1 month +1 day", "2024-03-02"),
+            (
+                "2024-01-31 14:30:15 next month",
+                "2024-03-02 14:30:15 +00:00",
+            ),
+            ("next month", "2024-03-02 09:15:42 +00:00"),
+        ] {
+            assert_eq!(run_gnu(input), expected, "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_shows_whole_seconds() {
+        assert_eq!(run_gnu("now"), "2024-01-31 09:15:42 +00:00");
+        assert_eq!(run_gnu("9am"), "2024-01-31 09:00:00 +00:00");
+    }
+
+    #[test]
+    fn test_rejects_unknown_and_conflicting_items() {
+        assert!(parse(Lexer::new("2 parsecs ago")).is_err());
+        assert!(parse(Lexer::new("2024-03-01 next friday")).is_err());
+        assert!(parse(Lexer::new("2024-13-01")).is_err());
+        assert!(parse(Lexer::new("13pm")).is_err());
+    }
+}
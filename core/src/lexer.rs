@@ -1,32 +1,148 @@
-use unscanny::Scanner;
+use alloc::string::{String, ToString};
+use core::fmt;
+use core::ops::Range;
+
+use crate::scanner::Scanner;
+
+/// Largest number of digits in a millisecond Unix timestamp for any date
+/// before the year 2286.
+const EPOCH_MILLIS_DIGITS: usize = 13;
 
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Token {
     Number(i64),
+    /// A number with a `%` right after it, as in `25%`.
+    Percentage(i64),
     Ident(String),
+    /// A named slot in a template, as in `{start}`.
+    Placeholder(String),
+    /// Text in double quotes, as in `"%d %B"`, without the quotes.
+    Str(String),
     Plus,
     Minus,
+    Star,
+    Percent,
     Colon,
     Slash,
+    LParen,
+    RParen,
+    Comma,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    EqualEqual,
+    BangEqual,
+    /// `|>`, passing the value on its left to the call on its right.
+    Pipe,
     Eof,
-    Illegal,
+    Error(LexError),
+}
+
+/// A piece of input the lexer could not turn into a token, with the byte
+/// range it covers.
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LexError {
+    pub kind: LexErrorKind,
+    pub span: Range<usize>,
+    pub position: Position,
+}
+
+/// A 1-based line and column (in characters) within the input.
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Position {
+    fn at(input: &str, offset: usize) -> Self {
+        let before = &input[..offset];
+        let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+        Position {
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+        }
+    }
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
 }
 
-impl std::fmt::Display for Token {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LexErrorKind {
+    UnknownChar(char),
+    NumberOverflow(String),
+    /// A `{` not followed by a name and a closing `}`.
+    Placeholder(String),
+    /// A `"` without a closing one.
+    UnterminatedString,
+}
+
+impl fmt::Display for LexError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.kind {
+            LexErrorKind::UnknownChar(c) => {
+                write!(f, "unexpected character '{}' at {}", c, self.position)
+            }
+            LexErrorKind::NumberOverflow(digits) => write!(
+                f,
+                "number '{}' at {} is too large; if it is a timestamp, \
+                 one in milliseconds has at most {} digits",
+                digits, self.position, EPOCH_MILLIS_DIGITS
+            ),
+            LexErrorKind::Placeholder(text) => write!(
+                f,
+                "malformed placeholder '{}' at {}; expected a name in braces, as in '{{start}}'",
+                text, self.position
+            ),
+            LexErrorKind::UnterminatedString => {
+                write!(f, "unterminated string starting at {}", self.position)
+            }
+        }
+    }
+}
+
+impl core::error::Error for LexError {}
+
+impl core::fmt::Display for Token {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
         match self {
             Token::Number(n) => write!(f, "Number({})", n),
+            Token::Percentage(n) => write!(f, "Percentage({})", n),
             Token::Ident(s) => write!(f, "Ident({})", s),
+            Token::Placeholder(s) => write!(f, "Placeholder({})", s),
+            Token::Str(s) => write!(f, "Str({})", s),
             Token::Plus => write!(f, "Plus"),
             Token::Minus => write!(f, "Minus"),
+            Token::Star => write!(f, "Star"),
+            Token::Percent => write!(f, "Percent"),
             Token::Colon => write!(f, "Colon"),
             Token::Slash => write!(f, "Slash"),
+            Token::LParen => write!(f, "LParen"),
+            Token::RParen => write!(f, "RParen"),
+            Token::Comma => write!(f, "Comma"),
+            Token::Less => write!(f, "Less"),
+            Token::LessEqual => write!(f, "LessEqual"),
+            Token::Greater => write!(f, "Greater"),
+            Token::GreaterEqual => write!(f, "GreaterEqual"),
+            Token::EqualEqual => write!(f, "EqualEqual"),
+            Token::BangEqual => write!(f, "BangEqual"),
+            Token::Pipe => write!(f, "Pipe"),
             Token::Eof => write!(f, "Eof"),
-            Token::Illegal => write!(f, "Illegal"),
+            Token::Error(err) => write!(f, "Error({})", err),
         }
     }
 }
 
+/// Splits an expression into [`Token`]s, ending with [`Token::Eof`].
 #[derive(Clone, Copy, Debug)]
 pub struct Lexer<'s> {
     s: Scanner<'s>,
@@ -39,41 +155,140 @@ impl<'a> Lexer<'a> {
         }
     }
 
+    /// Scans the remaining input and returns the number of tokens before the
+    /// end of input, or the first lexing error.
+    pub fn check(mut self) -> Result<usize, LexError> {
+        let mut count = 0;
+        loop {
+            match self.next_token() {
+                Token::Eof => return Ok(count),
+                Token::Error(err) => return Err(err),
+                _ => count += 1,
+            }
+        }
+    }
+
+    /// The whole input, including any part already scanned.
+    pub fn input(&self) -> &'a str {
+        self.s.string()
+    }
+
     pub fn next_token(&mut self) -> Token {
+        // Skipped in a loop, so any number of blank and comment lines takes
+        // no more stack than one.
+        loop {
+            self.s.eat_whitespace();
+            if !self.s.eat_if('#') {
+                break;
+            }
+            self.s.eat_until('\n');
+        }
+
+        let start = self.s.cursor();
         match self.s.eat() {
             Some('+') => Token::Plus,
             Some('-') => Token::Minus,
+            Some('*') => Token::Star,
+            Some('%') => Token::Percent,
             Some(':') => Token::Colon,
             Some('/') => Token::Slash,
-            Some(' ') => self.whitespace(),
-            Some('0'..='9') => self.number(),
-            Some('a'..='z') | Some('A'..='Z') => self.ident(),
+            Some('(') => Token::LParen,
+            Some(')') => Token::RParen,
+            Some(',') => Token::Comma,
+            Some('<') if self.s.eat_if('=') => Token::LessEqual,
+            Some('<') => Token::Less,
+            Some('>') if self.s.eat_if('=') => Token::GreaterEqual,
+            Some('>') => Token::Greater,
+            Some('=') if self.s.eat_if('=') => Token::EqualEqual,
+            Some('!') if self.s.eat_if('=') => Token::BangEqual,
+            Some('|') if self.s.eat_if('>') => Token::Pipe,
+            Some('{') => self.placeholder(start),
+            Some('"') => self.string(start),
+            Some('0'..='9') => self.number(start),
+            Some(c) if c.is_alphabetic() => self.ident(),
             None => Token::Eof,
-            _ => Token::Illegal,
+            Some(c) => self.error(LexErrorKind::UnknownChar(c), start),
         }
     }
 
-    fn whitespace(&mut self) -> Token {
-        self.s.eat_whitespace();
-        self.next_token()
-    }
-
-    fn number(&mut self) -> Token {
+    fn number(&mut self, start: usize) -> Token {
         self.s.uneat();
-        let number = self.s.eat_while(char::is_ascii_digit);
-        match number.parse() {
+        self.s.eat_while(|c| c.is_ascii_digit());
+        // `_` may separate digit groups, as in `1_000_000`.
+        while self.s.at('_') && self.s.scout(1).is_some_and(|c| c.is_ascii_digit()) {
+            self.s.eat();
+            self.s.eat_while(|c| c.is_ascii_digit());
+        }
+
+        let number = self.s.from(start);
+        // A `%` that another operand follows right away is the remainder
+        // operator, as in `7%2`.
+        let percentage = self.s.at('%')
+            && !self
+                .s
+                .scout(1)
+                .is_some_and(|c| c.is_ascii_digit() || c == '(');
+        match number.replace('_', "").parse() {
+            Ok(n) if percentage => {
+                self.s.eat();
+                Token::Percentage(n)
+            }
             Ok(n) => Token::Number(n),
-            Err(_) => Token::Illegal, // Number too large for i64
+            Err(_) => self.error(LexErrorKind::NumberOverflow(number.to_string()), start),
         }
     }
 
+    fn error(&self, kind: LexErrorKind, start: usize) -> Token {
+        Token::Error(LexError {
+            kind,
+            span: start..self.s.cursor(),
+            position: Position::at(self.s.string(), start),
+        })
+    }
+
+    fn placeholder(&mut self, start: usize) -> Token {
+        let name = self.s.eat_while(|c: char| c.is_alphanumeric() || c == '_');
+        if name.is_empty() || !self.s.eat_if('}') {
+            return self.error(
+                LexErrorKind::Placeholder(self.s.from(start).to_string()),
+                start,
+            );
+        }
+        Token::Placeholder(name.to_string())
+    }
+
+    fn string(&mut self, start: usize) -> Token {
+        let text = self.s.eat_until('"');
+        if !self.s.eat_if('"') {
+            return self.error(LexErrorKind::UnterminatedString, start);
+        }
+        Token::Str(text.to_string())
+    }
+
     fn ident(&mut self) -> Token {
         self.s.uneat();
-        let ident = self.s.eat_while(char::is_ascii_alphabetic);
+        let ident = self.s.eat_while(|c: char| c.is_alphabetic() || c == '_');
         Token::Ident(ident.to_string())
     }
 }
 
+/// Splits one line into its code and the `#` comment that ends it, if any,
+/// finding the comment where the lexer would, so a `#` inside a string does
+/// not start one.
+pub fn split_comment(line: &str) -> (&str, Option<&str>) {
+    let mut lexer = Lexer::new(line);
+    loop {
+        lexer.s.eat_whitespace();
+        if lexer.s.at('#') {
+            let start = lexer.s.cursor();
+            return (&line[..start], Some(&line[start..]));
+        }
+        if lexer.next_token() == Token::Eof {
+            return (line, None);
+        }
+    }
+}
+
 impl<'s> Iterator for Lexer<'s> {
     type Item = Token;
 
@@ -129,9 +344,208 @@ mod tests {
     }
 
     #[test]
-    fn test_illegal_token() {
-        let mut lexer = Lexer::new("@");
-        assert_eq!(lexer.next_token(), Token::Illegal);
+    fn test_next_token_comparisons() {
+        let input = "< <= > >= == !=";
+        let mut lexer = Lexer::new(input);
+
+        assert_eq!(lexer.next_token(), Token::Less);
+        assert_eq!(lexer.next_token(), Token::LessEqual);
+        assert_eq!(lexer.next_token(), Token::Greater);
+        assert_eq!(lexer.next_token(), Token::GreaterEqual);
+        assert_eq!(lexer.next_token(), Token::EqualEqual);
+        assert_eq!(lexer.next_token(), Token::BangEqual);
+        assert_eq!(lexer.next_token(), Token::Eof);
+    }
+
+    #[test]
+    fn test_next_token_string() {
+        let mut lexer = Lexer::new("format(now, \"%d %B\") \"open");
+
+        assert_eq!(lexer.next_token(), Token::Ident("format".to_string()));
+        assert_eq!(lexer.next_token(), Token::LParen);
+        assert_eq!(lexer.next_token(), Token::Ident("now".to_string()));
+        assert_eq!(lexer.next_token(), Token::Comma);
+        assert_eq!(lexer.next_token(), Token::Str("%d %B".to_string()));
+        assert_eq!(lexer.next_token(), Token::RParen);
+        assert!(matches!(
+            lexer.next_token(),
+            Token::Error(LexError {
+                kind: LexErrorKind::UnterminatedString,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_next_token_pipe() {
+        let mut lexer = Lexer::new("-2h |> abs | 1");
+
+        assert_eq!(lexer.next_token(), Token::Minus);
+        assert_eq!(lexer.next_token(), Token::Number(2));
+        assert_eq!(lexer.next_token(), Token::Ident("h".to_string()));
+        assert_eq!(lexer.next_token(), Token::Pipe);
+        assert_eq!(lexer.next_token(), Token::Ident("abs".to_string()));
+        assert!(matches!(lexer.next_token(), Token::Error(_)));
+    }
+
+    #[test]
+    fn test_next_token_call() {
+        let mut lexer = Lexer::new("days_in(2024/02, 1)");
+
+        assert_eq!(lexer.next_token(), Token::Ident("days_in".to_string()));
+        assert_eq!(lexer.next_token(), Token::LParen);
+        assert_eq!(lexer.next_token(), Token::Number(2024));
+        assert_eq!(lexer.next_token(), Token::Slash);
+        assert_eq!(lexer.next_token(), Token::Number(2));
+        assert_eq!(lexer.next_token(), Token::Comma);
+        assert_eq!(lexer.next_token(), Token::Number(1));
+        assert_eq!(lexer.next_token(), Token::RParen);
+        assert_eq!(lexer.next_token(), Token::Eof);
+    }
+
+    #[test]
+    fn test_next_token_unicode_ident() {
+        let mut lexer = Lexer::new("nächsten Montag");
+
+        assert_eq!(lexer.next_token(), Token::Ident("nächsten".to_string()));
+        assert_eq!(lexer.next_token(), Token::Ident("Montag".to_string()));
+        assert_eq!(lexer.next_token(), Token::Eof);
+    }
+
+    #[test]
+    fn test_number_digit_separators() {
+        let mut lexer = Lexer::new("1_000_000d 1_");
+        assert_eq!(lexer.next_token(), Token::Number(1_000_000));
+        assert_eq!(lexer.next_token(), Token::Ident("d".to_string()));
+        assert_eq!(lexer.next_token(), Token::Number(1));
+        assert!(matches!(lexer.next_token(), Token::Error(_)));
+    }
+
+    #[test]
+    fn test_placeholders() {
+        let mut lexer = Lexer::new("{start_2}+{");
+
+        assert_eq!(
+            lexer.next_token(),
+            Token::Placeholder("start_2".to_string())
+        );
+        assert_eq!(lexer.next_token(), Token::Plus);
+        assert!(matches!(
+            lexer.next_token(),
+            Token::Error(LexError {
+                kind: LexErrorKind::Placeholder(_),
+                span: std::ops::Range { start: 10, end: 11 },
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_percentage_or_remainder() {
+        let mut lexer = Lexer::new("25% of 7%2 7 % 2 5%");
+        assert_eq!(lexer.next_token(), Token::Percentage(25));
+        assert_eq!(lexer.next_token(), Token::Ident("of".to_string()));
+        assert_eq!(lexer.next_token(), Token::Number(7));
+        assert_eq!(lexer.next_token(), Token::Percent);
+        assert_eq!(lexer.next_token(), Token::Number(2));
+        assert_eq!(lexer.next_token(), Token::Number(7));
+        assert_eq!(lexer.next_token(), Token::Percent);
+        assert_eq!(lexer.next_token(), Token::Number(2));
+        assert_eq!(lexer.next_token(), Token::Percentage(5));
+        assert_eq!(lexer.next_token(), Token::Eof);
+    }
+
+    #[test]
+    fn test_number_leading_zeros() {
+        let mut lexer = Lexer::new("09:05");
+        assert_eq!(lexer.next_token(), Token::Number(9));
+        assert_eq!(lexer.next_token(), Token::Colon);
+        assert_eq!(lexer.next_token(), Token::Number(5));
+        assert_eq!(lexer.next_token(), Token::Eof);
+    }
+
+    #[test]
+    fn test_comment_runs_to_end_of_input() {
+        let mut lexer = Lexer::new("2h # two hours, + 3h");
+        assert_eq!(lexer.next_token(), Token::Number(2));
+        assert_eq!(lexer.next_token(), Token::Ident("h".to_string()));
+        assert_eq!(lexer.next_token(), Token::Eof);
+    }
+
+    #[test]
+    fn test_many_comment_lines_take_no_stack() {
+        let input = format!("{}1d", "#\n".repeat(32_000));
+        let mut lexer = Lexer::new(&input);
+        assert_eq!(lexer.next_token(), Token::Number(1));
+        assert_eq!(lexer.next_token(), Token::Ident("d".to_string()));
+        assert_eq!(lexer.next_token(), Token::Eof);
+    }
+
+    #[test]
+    fn test_long_runs_of_blank_lines_take_no_stack() {
+        let input = format!(
+            "2h{}+ # note\n{}3h",
+            " \t\n".repeat(32_000),
+            "\n#\n".repeat(8_000)
+        );
+        let mut lexer = Lexer::new(&input);
+        assert_eq!(lexer.next_token(), Token::Number(2));
+        assert_eq!(lexer.next_token(), Token::Ident("h".to_string()));
+        assert_eq!(lexer.next_token(), Token::Plus);
+        assert_eq!(lexer.next_token(), Token::Number(3));
+        assert_eq!(lexer.next_token(), Token::Ident("h".to_string()));
+        assert_eq!(lexer.next_token(), Token::Eof);
+    }
+
+    #[test]
+    fn test_split_comment_skips_strings() {
+        assert_eq!(
+            split_comment("2h # two hours"),
+            ("2h ", Some("# two hours"))
+        );
+        assert_eq!(
+            split_comment(r##"format(today, "#%d") # day"##),
+            (r##"format(today, "#%d") "##, Some("# day"))
+        );
+        assert_eq!(split_comment(r##""#"##), (r##""#"##, None));
+        assert_eq!(split_comment("# only"), ("", Some("# only")));
+    }
+
+    #[test]
+    fn test_whitespace_tabs_and_newlines() {
+        let mut lexer = Lexer::new("2h\t+\n\u{a0}3h");
+        assert_eq!(lexer.next_token(), Token::Number(2));
+        assert_eq!(lexer.next_token(), Token::Ident("h".to_string()));
+        assert_eq!(lexer.next_token(), Token::Plus);
+        assert_eq!(lexer.next_token(), Token::Number(3));
+        assert_eq!(lexer.next_token(), Token::Ident("h".to_string()));
+        assert_eq!(lexer.next_token(), Token::Eof);
+    }
+
+    #[test]
+    fn test_check_counts_tokens() {
+        assert_eq!(Lexer::new("today + 3h # later").check(), Ok(4));
+    }
+
+    #[test]
+    fn test_error_position_on_later_line() {
+        let err = Lexer::new("today # start\n  + é2d @").check().unwrap_err();
+        assert_eq!(err.position, Position { line: 2, column: 9 });
+    }
+
+    #[test]
+    fn test_unknown_char() {
+        let mut lexer = Lexer::new("2h @");
+        lexer.next_token();
+        lexer.next_token();
+        assert_eq!(
+            lexer.next_token(),
+            Token::Error(LexError {
+                kind: LexErrorKind::UnknownChar('@'),
+                span: 3..4,
+                position: Position { line: 1, column: 4 },
+            })
+        );
     }
 
     #[test]
@@ -139,6 +553,25 @@ mod tests {
         // Number larger than i64::MAX (9223372036854775807)
         let input = "99999999999999999999";
         let mut lexer = Lexer::new(input);
-        assert_eq!(lexer.next_token(), Token::Illegal);
+        assert_eq!(
+            lexer.next_token(),
+            Token::Error(LexError {
+                kind: LexErrorKind::NumberOverflow(input.to_string()),
+                span: 0..20,
+                position: Position { line: 1, column: 1 },
+            })
+        );
+    }
+
+    #[test]
+    fn test_number_overflow_suggests_epoch_millis() {
+        let err = Lexer::new("today + 99999999999999999999d")
+            .check()
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "number '99999999999999999999' at 1:9 is too large; \
+             if it is a timestamp, one in milliseconds has at most 13 digits"
+        );
     }
 }
@@ -1,82 +1,344 @@
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
+mod ast;
+pub mod build;
 mod calendar;
+#[cfg(feature = "config")]
+mod config;
+mod convert;
+mod detect;
+mod diagnostic;
 mod evaluator;
+mod format;
+mod functions;
+mod gnu;
 mod lexer;
+#[cfg(feature = "std")]
+mod lines;
+mod lint;
+mod locale;
+mod meeting;
+mod options;
 mod parser;
+pub mod prelude;
+mod pretty;
+mod rotation;
+mod scanner;
+#[cfg(feature = "std")]
+mod session;
+#[cfg(feature = "unstable-ast")]
+mod simplify;
+mod units;
+mod zone;
 
-use crate::evaluator::eval_with_calendar;
-use crate::lexer::Lexer;
-use crate::parser::{Expr, parse};
-use toml::Value;
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+#[cfg(not(feature = "std"))]
+use core::cell::OnceCell as OnceLock;
+#[cfg(feature = "std")]
+use std::sync::OnceLock;
 
-pub use crate::calendar::Calendar;
+use crate::ast::Ast;
+use crate::evaluator::{eval_with_options, evaluate as evaluate_ast, evaluate_pure, evaluate_with};
+#[cfg(feature = "unstable-ast")]
+use crate::parser::parse_readings;
+use crate::parser::{parse, parse_ast};
+// Exported as they are with `unstable-ast`.
+#[cfg(not(feature = "unstable-ast"))]
+use crate::{lexer::Lexer, parser::Expr};
+
+pub use crate::calendar::{Calendar, MissingDay};
+#[cfg(feature = "config")]
+pub use crate::config::{calendar_from_toml, options_from_toml};
+pub use crate::convert::{ConvertError, NegativeDuration};
+pub use crate::detect::{FoundTimestamp, TimestampFormat, detect, find_timestamps};
+pub use crate::diagnostic::{Diagnostic, Severity, diagnose};
+pub use crate::evaluator::{
+    EvalError, Evaluation, MonthArithmetic, Overflow, TraceStep, Value, Warning,
+};
+pub use crate::format::{DurationStyle, HourFormat, Precision};
+pub use crate::functions::{Context, call};
+pub use crate::lexer::split_comment;
+#[cfg(feature = "unstable-ast")]
+pub use crate::lexer::{LexError, LexErrorKind, Lexer, Position, Token};
+#[cfg(feature = "std")]
+pub use crate::lines::{EvalLines, EvaluatedLine, eval_lines};
+#[cfg(feature = "unstable-ast")]
+pub use crate::lint::{Lint, lint};
+pub use crate::locale::{DateStyle, Locale};
+pub use crate::meeting::{Slot, meeting_slots};
+pub use crate::options::{DayParts, Limits, Options, Paydays, Sprints};
+pub use crate::parser::{AssumeMissing, DateOrder, Op, ParsingError, Syntax, TwoDigitYears, Unit};
+#[cfg(feature = "unstable-ast")]
+pub use crate::parser::{Expr, Keyword, Period, Reading, Recurrence, SprintEdge, UnaryOp};
+pub use crate::rotation::Rotation;
+#[cfg(feature = "serde")]
+pub use crate::session::SavedSession;
+#[cfg(feature = "std")]
+pub use crate::session::{Session, Snapshot};
+#[cfg(feature = "unstable-ast")]
+pub use crate::simplify::simplify;
+pub use crate::units::{UnitName, Units};
+pub use crate::zone::Zone;
 
 pub fn run(input: &str, calendar: Option<&Calendar>) -> Result<String, String> {
-    let default_calendar = Calendar::default();
-    let calendar = calendar.unwrap_or(&default_calendar);
-    let tokens = Lexer::new(input);
-    let ast = parse(tokens).map_err(|err| format!("failed to parse expression: {}", err))?;
-    let result = eval_with_calendar(&ast, calendar)
-        .map_err(|err| format!("failed to evaluate expression: {}", err))?;
-    Ok(result.to_string())
+    let options = Options {
+        calendar: calendar.cloned().unwrap_or_default(),
+        ..Options::default()
+    };
+    run_with_options(input, &options)
 }
 
-pub fn calendar_from_holidays(holidays: &[String]) -> Result<Calendar, String> {
-    let mut calendar = Calendar::new();
+pub fn run_with_options(input: &str, options: &Options) -> Result<String, String> {
+    compile_with_options(input, options)?.run(options)
+}
 
-    for holiday in holidays {
-        add_holiday_to_calendar(&mut calendar, holiday)?;
+/// An expression parsed once and ready to be evaluated repeatedly, for
+/// example against a different [`Options::now`] each time.
+#[derive(Debug, Clone)]
+pub struct CompiledExpr {
+    ast: Ast,
+    /// The boxed tree behind [`CompiledExpr::expr`], only built when asked for.
+    expr: OnceLock<Expr>,
+}
+
+impl PartialEq for CompiledExpr {
+    fn eq(&self, other: &Self) -> bool {
+        self.ast == other.ast
     }
+}
 
-    Ok(calendar)
+impl CompiledExpr {
+    /// The parsed tree, whose shape may change in any release.
+    #[cfg(feature = "unstable-ast")]
+    pub fn expr(&self) -> &Expr {
+        self.tree()
+    }
+
+    fn tree(&self) -> &Expr {
+        self.expr.get_or_init(|| self.ast.to_expr())
+    }
+
+    pub fn eval(&self, options: &Options) -> Result<Value, String> {
+        eval_with_options(&self.ast, options)
+            .map_err(|err| format!("failed to evaluate expression: {}", err))
+    }
+
+    pub fn run(&self, options: &Options) -> Result<String, String> {
+        Ok(self.eval(options)?.display(options).to_string())
+    }
+
+    /// Evaluates the expression without reading the system clock, for build
+    /// scripts, test oracles and other places that need the same result
+    /// every time. Relative keywords resolve against [`Options::now`], and
+    /// are an error when it is not set.
+    pub fn eval_pure(&self, options: &Options) -> Result<Value, String> {
+        evaluate_pure(&self.ast, options)
+            .map(|evaluation| evaluation.value)
+            .map_err(|err| format!("failed to evaluate expression: {}", err))
+    }
+
+    /// Evaluates the expression, also returning any [`Warning`]s about
+    /// approximations made along the way.
+    pub fn evaluate(&self, options: &Options) -> Result<Evaluation, String> {
+        evaluate_ast(&self.ast, options, false)
+            .map_err(|err| format!("failed to evaluate expression: {}", err))
+    }
+
+    /// Like [`CompiledExpr::evaluate`], but also records each evaluated
+    /// sub-expression, operands before the operations that use them.
+    pub fn explain(&self, options: &Options) -> Result<Evaluation, String> {
+        evaluate_ast(&self.ast, options, true)
+            .map_err(|err| format!("failed to evaluate expression: {}", err))
+    }
+}
+
+/// An expression built with the [`build`] helpers.
+impl From<build::Part> for CompiledExpr {
+    fn from(part: build::Part) -> Self {
+        let expr = part.0;
+        CompiledExpr {
+            ast: Ast::from(&expr),
+            expr: OnceLock::from(expr),
+        }
+    }
 }
 
-pub fn calendar_from_toml(input: &str, calendar_name: Option<&str>) -> Result<Calendar, String> {
-    let value = input
-        .parse::<Value>()
-        .map_err(|err| format!("failed to parse calendar file: {}", err))?;
+/// An expression taken apart and put back together.
+#[cfg(feature = "unstable-ast")]
+impl From<Expr> for CompiledExpr {
+    fn from(expr: Expr) -> Self {
+        CompiledExpr::from(build::Part(expr))
+    }
+}
 
-    let table = match calendar_name {
-        Some(name) => value
-            .get(name)
-            .ok_or_else(|| format!("calendar '{}' not found", name))?,
-        None => &value,
-    };
+/// Parses and evaluates `input` in one step, returning the value along with
+/// any [`Warning`]s. Use [`compile_with_options`] to evaluate it again.
+pub fn evaluate(input: &str, options: &Options) -> Result<Evaluation, String> {
+    compile_with_options(input, options)?.evaluate(options)
+}
 
-    let holidays = table
-        .get("holidays")
-        .ok_or_else(|| missing_holidays_error(calendar_name))?
-        .as_array()
-        .ok_or_else(|| holidays_type_error(calendar_name))?;
+pub fn compile(input: &str) -> Result<CompiledExpr, String> {
+    compile_with_options(input, &Options::default())
+}
 
-    let mut calendar = Calendar::new();
-    for holiday in holidays {
-        let holiday = holiday
-            .as_str()
-            .ok_or_else(|| holidays_type_error(calendar_name))?;
-        add_holiday_to_calendar(&mut calendar, holiday)?;
+/// Parses `input` using the parse-time settings in `options` (locale, date
+/// order and two-digit year policy).
+pub fn compile_with_options(input: &str, options: &Options) -> Result<CompiledExpr, String> {
+    let ast = parse_ast(Lexer::new(input), options)
+        .map_err(|err| format!("failed to parse expression: {}", err))?;
+    Ok(CompiledExpr {
+        ast,
+        expr: OnceLock::new(),
+    })
+}
+
+/// An expression with `{name}` placeholders, parsed once and evaluated with
+/// values bound to them. The values never pass through text, so no binding
+/// can change what the expression means.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+    compiled: CompiledExpr,
+    placeholders: Vec<String>,
+}
+
+impl Template {
+    /// The parsed tree, whose shape may change in any release.
+    #[cfg(feature = "unstable-ast")]
+    pub fn expr(&self) -> &Expr {
+        self.compiled.tree()
     }
 
-    Ok(calendar)
+    /// The names of the placeholders, in order of first appearance.
+    pub fn placeholders(&self) -> &[String] {
+        &self.placeholders
+    }
+
+    pub fn eval(&self, bindings: &[(&str, Value)], options: &Options) -> Result<Value, String> {
+        Ok(self.evaluate(bindings, options)?.value)
+    }
+
+    pub fn run(&self, bindings: &[(&str, Value)], options: &Options) -> Result<String, String> {
+        Ok(self.eval(bindings, options)?.display(options).to_string())
+    }
+
+    /// Evaluates the template with `bindings`, also returning any
+    /// [`Warning`]s. Every placeholder needs a binding.
+    pub fn evaluate(
+        &self,
+        bindings: &[(&str, Value)],
+        options: &Options,
+    ) -> Result<Evaluation, String> {
+        evaluate_with(&self.compiled.ast, options, bindings, &[], false)
+            .map_err(|err| format!("failed to evaluate expression: {}", err))
+    }
+}
+
+/// A template built with the [`build`] helpers.
+impl From<build::Part> for Template {
+    fn from(part: build::Part) -> Self {
+        let mut placeholders = Vec::new();
+        collect_placeholders(&part.0, &mut placeholders);
+        Template {
+            compiled: CompiledExpr::from(part),
+            placeholders,
+        }
+    }
+}
+
+/// A template taken apart and put back together.
+#[cfg(feature = "unstable-ast")]
+impl From<Expr> for Template {
+    fn from(expr: Expr) -> Self {
+        Template::from(build::Part(expr))
+    }
+}
+
+/// Parses a template such as `{start} + {length}`, with the parse-time
+/// settings in `options`.
+pub fn compile_template(input: &str, options: &Options) -> Result<Template, String> {
+    let compiled = compile_with_options(input, options)?;
+    let mut placeholders = Vec::new();
+    collect_placeholders(compiled.tree(), &mut placeholders);
+    Ok(Template {
+        compiled,
+        placeholders,
+    })
+}
+
+fn collect_placeholders(expr: &Expr, names: &mut Vec<String>) {
+    match expr {
+        Expr::Placeholder(name) if !names.contains(name) => names.push(name.clone()),
+        Expr::UnaryOp(_, operand) => collect_placeholders(operand, names),
+        Expr::BinOp(left, _, right) => {
+            collect_placeholders(left, names);
+            collect_placeholders(right, names);
+        }
+        Expr::Call(_, args) => args.iter().for_each(|arg| collect_placeholders(arg, names)),
+        _ => {}
+    }
 }
 
-fn missing_holidays_error(calendar_name: Option<&str>) -> String {
-    match calendar_name {
-        Some(name) => format!("calendar '{}' must define holidays", name),
-        None => "calendar file must define top-level holidays or use --calendar-name".to_string(),
+/// Every distinct way `input` parses under the possible date orders, so a
+/// front end can ask which one was meant instead of picking silently. See
+/// [`Reading`].
+#[cfg(feature = "unstable-ast")]
+pub fn readings(input: &str, options: &Options) -> Vec<Reading> {
+    parse_readings(Lexer::new(input), options)
+}
+
+/// Rewrites each line of an expression file in canonical form, keeping blank
+/// lines and `#` comments.
+pub fn format_source(input: &str, options: &Options) -> Result<String, String> {
+    let mut output = String::new();
+
+    for (index, line) in input.lines().enumerate() {
+        let (code, comment) = split_comment(line);
+        let comment = comment.map(str::trim_end);
+
+        let code = if code.trim().is_empty() {
+            String::new()
+        } else {
+            compile_with_options(code, options)
+                .map_err(|err| format!("line {}: {}", index + 1, err))?
+                .tree()
+                .to_string()
+        };
+
+        match (code.is_empty(), comment) {
+            (true, Some(comment)) => output.push_str(comment),
+            (false, Some(comment)) => {
+                output.push_str(&code);
+                output.push(' ');
+                output.push_str(comment);
+            }
+            (_, None) => output.push_str(&code),
+        }
+        output.push('\n');
     }
+
+    Ok(output)
 }
 
-fn holidays_type_error(calendar_name: Option<&str>) -> String {
-    match calendar_name {
-        Some(name) => format!(
-            "calendar '{}' holidays must be an array of date strings",
-            name
-        ),
-        None => "calendar holidays must be an array of date strings".to_string(),
+pub fn calendar_from_holidays(holidays: &[String]) -> Result<Calendar, String> {
+    let mut calendar = Calendar::new();
+
+    for holiday in holidays {
+        add_holiday_to_calendar(&mut calendar, holiday)?;
     }
+
+    Ok(calendar)
 }
 
-fn add_holiday_to_calendar(calendar: &mut Calendar, holiday: &str) -> Result<(), String> {
+pub(crate) fn add_holiday_to_calendar(
+    calendar: &mut Calendar,
+    holiday: &str,
+) -> Result<(), String> {
     let tokens = Lexer::new(holiday);
     let ast =
         parse(tokens).map_err(|err| format!("failed to parse holiday '{}': {}", holiday, err))?;
@@ -104,53 +366,476 @@ mod tests {
     }
 
     #[test]
-    fn calendar_from_holidays_rejects_non_date() {
-        let holidays = vec!["2h".to_string()];
+    fn run_steps_weekdays_through_holidays() {
+        let holidays = vec!["2024/04/29".to_string()];
+        let calendar = calendar_from_holidays(&holidays).unwrap();
 
-        assert!(calendar_from_holidays(&holidays).is_err());
+        assert_eq!(
+            run("2024/04/26 + 2 weekdays", Some(&calendar)).unwrap(),
+            "2024-04-30"
+        );
+        assert_eq!(
+            run("2024/04/30 - 1 weekday", Some(&calendar)).unwrap(),
+            "2024-04-29"
+        );
+        assert_eq!(
+            run("2 * 3 weekdays - 1 weekday", None).unwrap(),
+            "5 weekdays"
+        );
+        assert!(run("1wd + 1 weekday", None).is_err());
     }
 
     #[test]
-    fn calendar_from_toml_reads_top_level_holidays() {
-        let calendar = calendar_from_toml(
-            r#"
-            holidays = ["2024/04/29"]
-            "#,
-            None,
-        )
-        .unwrap();
+    fn run_evaluates_compound_condition() {
+        let result = run("2024/07/04 > 2024/06/01 and 2024/07/04 < 2024/09/01", None).unwrap();
 
-        let result = run("2024/04/26 + 1wd", Some(&calendar)).unwrap();
+        assert_eq!(result, "true");
+    }
 
-        assert_eq!(result, "2024-04-30");
+    #[test]
+    fn run_accepts_digit_separators() {
+        let result = run("2024/03/07 09:05 + 1_440m", None).unwrap();
+
+        assert_eq!(result, "2024-03-08 09:05 +00:00");
     }
 
     #[test]
-    fn calendar_from_toml_reads_named_calendar() {
-        let calendar = calendar_from_toml(
-            r#"
-            [italy]
-            holidays = ["2024/04/29"]
-            "#,
-            Some("italy"),
-        )
-        .unwrap();
+    fn compiled_expr_evaluates_against_each_reference_time() {
+        let compiled = compile("tomorrow + 1d").unwrap();
+        let mut options = Options::default();
+        let feb_28 = |year| {
+            time::Date::from_calendar_date(year, time::Month::February, 28)
+                .unwrap()
+                .midnight()
+                .assume_utc()
+        };
 
-        let result = run("2024/04/26 + 1wd", Some(&calendar)).unwrap();
+        options.now = Some(feb_28(2024));
+        assert_eq!(compiled.run(&options).unwrap(), "2024-03-01");
 
-        assert_eq!(result, "2024-04-30");
+        options.now = Some(feb_28(2023));
+        assert_eq!(compiled.run(&options).unwrap(), "2023-03-02");
+    }
+
+    #[test]
+    fn eval_pure_never_reads_the_clock() {
+        let compiled = compile("today + 1d").unwrap();
+        let mut options = Options::default();
+        assert!(compiled.eval_pure(&options).is_err());
+        let leap = compile("2024/01/31 + 29d").unwrap();
+        assert_eq!(leap.eval_pure(&options).unwrap().to_string(), "2024-02-29");
+
+        options.now = Some(
+            time::Date::from_calendar_date(2024, time::Month::February, 28)
+                .unwrap()
+                .midnight()
+                .assume_utc(),
+        );
+        assert_eq!(
+            compiled.eval_pure(&options).unwrap().to_string(),
+            "2024-02-29"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn run_reads_the_clock_once_per_expression() {
+        assert_eq!(run("now - now", None).unwrap(), "0s");
+        assert_eq!(
+            run("(now - 1d) - yesterday 00:00 < 1d", None).unwrap(),
+            "true"
+        );
+        assert_eq!(
+            run("2024/05/02 10:00 - 2024/05/01 08:30", None).unwrap(),
+            "1d1h30m"
+        );
+    }
+
+    #[test]
+    fn run_sets_time_of_day_on_keywords() {
+        let options = Options {
+            now: Some(
+                time::Date::from_calendar_date(2024, time::Month::March, 10)
+                    .unwrap()
+                    .with_hms(8, 15, 0)
+                    .unwrap()
+                    .assume_utc(),
+            ),
+            ..Options::default()
+        };
+
+        assert_eq!(
+            run_with_options("yesterday 17:00", &options).unwrap(),
+            "2024-03-09 17:00 +00:00"
+        );
+        assert_eq!(run_with_options("now 9am > now", &options).unwrap(), "true");
+    }
+
+    #[test]
+    fn run_starts_days_at_configured_time() {
+        let mut options = Options {
+            now: Some(
+                time::Date::from_calendar_date(2024, time::Month::March, 10)
+                    .unwrap()
+                    .with_hms(3, 0, 0)
+                    .unwrap()
+                    .assume_utc(),
+            ),
+            ..Options::default()
+        };
+        assert_eq!(run_with_options("today", &options).unwrap(), "2024-03-10");
+        assert_eq!(
+            run_with_options("end of day", &options).unwrap(),
+            "2024-03-11 00:00 +00:00"
+        );
+
+        options.day_parts.start = time::Time::from_hms(6, 0, 0).unwrap();
+        assert_eq!(run_with_options("today", &options).unwrap(), "2024-03-09");
+        assert_eq!(
+            run_with_options("tonight", &options).unwrap(),
+            "2024-03-09 21:00 +00:00"
+        );
+        assert_eq!(
+            run_with_options("today 02:00", &options).unwrap(),
+            "2024-03-10 02:00 +00:00"
+        );
+        assert_eq!(
+            run_with_options("end of day", &options).unwrap(),
+            "2024-03-10 06:00 +00:00"
+        );
     }
 
     #[test]
-    fn calendar_from_toml_requires_name_for_named_calendar_only_file() {
-        let result = calendar_from_toml(
-            r#"
-            [italy]
-            holidays = ["2024/04/29"]
-            "#,
-            None,
+    fn evaluate_reports_assumed_components() {
+        let options = Options {
+            now: Some(
+                time::Date::from_calendar_date(2024, time::Month::March, 10)
+                    .unwrap()
+                    .with_hms(9, 0, 0)
+                    .unwrap()
+                    .assume_utc(),
+            ),
+            assume_missing: AssumeMissing::Current,
+            ..Options::default()
+        };
+
+        let evaluation = compile_with_options("14:30", &options)
+            .unwrap()
+            .evaluate(&options)
+            .unwrap();
+        assert_eq!(
+            evaluation.value.display(&options).to_string(),
+            "2024-03-10 14:30 +00:00"
+        );
+        assert_eq!(evaluation.warnings, [Warning::AssumedToday]);
+
+        let evaluation = compile_with_options("feb 29th", &options)
+            .unwrap()
+            .evaluate(&options)
+            .unwrap();
+        assert_eq!(evaluation.value.display(&options).to_string(), "2024-02-29");
+        assert_eq!(evaluation.warnings, [Warning::AssumedYear { year: 2024 }]);
+
+        assert_eq!(
+            run_with_options("14:30", &Options::default()).unwrap(),
+            "14:30"
+        );
+    }
+
+    #[test]
+    fn format_source_keeps_comments_and_blank_lines() {
+        let input = "# deadlines\n\ntoday+3wd   # review\n";
+
+        let result = format_source(input, &Options::default()).unwrap();
+
+        assert_eq!(result, "# deadlines\n\ntoday + 3wd # review\n");
+    }
+
+    #[test]
+    fn format_source_reports_failing_line() {
+        let result = format_source("today\n2024/13/01\n", &Options::default());
+
+        assert!(result.unwrap_err().starts_with("line 2:"));
+    }
+
+    #[test]
+    fn compiled_expr_explains_each_step() {
+        let compiled = compile("2024/01/31 + 1 month - 2d").unwrap();
+
+        let evaluation = compiled.explain(&Options::default()).unwrap();
+
+        assert_eq!(evaluation.trace.len(), 5);
+        assert_eq!(evaluation.trace[2].expr.to_string(), "2024/01/31 + 1 month");
+        assert_eq!(evaluation.trace[2].value.to_string(), "2024-03-01");
+        assert_eq!(evaluation.value.to_string(), "2024-02-28");
+        assert_eq!(evaluation.warnings.len(), 1);
+    }
+
+    #[test]
+    fn run_shifts_year_month_by_months() {
+        let result = run("jan 2024 + 2 months", None).unwrap();
+
+        assert_eq!(result, "2024-03");
+    }
+
+    #[test]
+    fn run_scales_sub_second_durations() {
+        assert_eq!(run("250ms * 4", None).unwrap(), "1s");
+        assert_eq!(run("1s - 350ms", None).unwrap(), "650ms");
+        assert!(run("today * 2", None).is_err());
+    }
+
+    #[test]
+    fn run_adds_decades_and_centuries_as_calendar_years() {
+        let options = Options {
+            month_arithmetic: MonthArithmetic::Error,
+            ..Options::default()
+        };
+
+        assert_eq!(run("1924/01/01 + 1 century", None).unwrap(), "2024-01-01");
+        assert_eq!(run("2024/02/29 - 2 decades", None).unwrap(), "2004-02-29");
+        assert_eq!(
+            run_with_options("1924/01/01 + 1 century", &options).unwrap(),
+            "2024-01-01"
+        );
+        assert_eq!(run("year(1994) + 3 decades", None).unwrap(), "2024");
+    }
+
+    #[test]
+    fn run_accepts_defined_units() {
+        let mut options = Options::default();
+        options
+            .units
+            .define("pomodoro", &["pomodoros"], 25, Unit::Minutes);
+        options.units.define("sprint", &["sprints"], 2, Unit::Weeks);
+
+        assert_eq!(run_with_options("3 pomodoros", &options).unwrap(), "1h15m");
+        assert_eq!(run_with_options("half a sprint", &options).unwrap(), "7d");
+        assert_eq!(
+            run_with_options("2024/03/01 + 2 sprints and a half", &options).unwrap(),
+            "2024-04-05"
+        );
+        assert!(run("3 pomodoros", None).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "colloquial")]
+    fn run_accepts_colloquial_units() {
+        assert_eq!(run("2024/03/01 + 1 fortnight", None).unwrap(), "2024-03-15");
+        assert_eq!(run("half a fortnight", None).unwrap(), "7d");
+        assert_eq!(run("2024/01 + 2 quarters", None).unwrap(), "2024-07");
+        assert_eq!(run("2024/01/01 + 1 quarter", None).unwrap(), "2024-03-31");
+        let calendar = Options {
+            month_arithmetic: MonthArithmetic::Calendar,
+            ..Options::default()
+        };
+        assert_eq!(
+            run_with_options("2024/01/01 + 1 quarter", &calendar).unwrap(),
+            "2024-04-01"
+        );
+        assert_eq!(
+            compile("3 fortnights + 1 quarter")
+                .unwrap()
+                .tree()
+                .to_string(),
+            "3 fortnights + 1 quarter"
+        );
+    }
+
+    #[test]
+    fn run_follows_missing_day_policy() {
+        let options = |missing_day| Options {
+            month_arithmetic: MonthArithmetic::Calendar,
+            missing_day,
+            ..Options::default()
+        };
+        let run = |input, missing_day| run_with_options(input, &options(missing_day));
+
+        assert_eq!(
+            run("2024/02/29 + 1 year", MissingDay::Clamp).unwrap(),
+            "2025-02-28"
+        );
+        assert_eq!(
+            run("2024/02/29 + 1 year", MissingDay::Roll).unwrap(),
+            "2025-03-01"
+        );
+        assert_eq!(
+            run("2024/02/29 + 1 year", MissingDay::Error).unwrap_err(),
+            "failed to evaluate expression: February 2025 has no day 29 (missing_day = \"error\")"
+        );
+    }
+
+    #[test]
+    fn run_takes_remainder_of_durations() {
+        assert_eq!(run("95m % 1h", None).unwrap(), "35m");
+        assert_eq!(run("-95m % 1h", None).unwrap(), "25m");
+        assert_eq!(run("2h + 95m % 1h", None).unwrap(), "2h35m");
+        assert!(run("95m % 0s", None).is_err());
+    }
+
+    #[test]
+    fn run_handles_years_before_the_common_era() {
+        assert_eq!(run("15 march 44 bc + 1d", None).unwrap(), "-0043-03-16");
+        assert_eq!(run("1 january 1 ad - 1d", None).unwrap(), "0000-12-31");
+    }
+
+    #[test]
+    #[cfg(feature = "tz")]
+    fn run_keeps_named_zone_across_daylight_saving() {
+        let run = |input| run(input, None).unwrap();
+
+        assert_eq!(
+            run("2024/03/30 12:00 Europe/Rome"),
+            "2024-03-30 12:00 +01:00 Europe/Rome"
+        );
+        assert_eq!(
+            run("2024/03/30 12:00 Europe/Rome + 1d"),
+            "2024-03-31 12:00 +02:00 Europe/Rome"
+        );
+        assert_eq!(
+            run("2024/03/31 01:30 Europe/Rome + 1h"),
+            "2024-03-31 03:30 +02:00 Europe/Rome"
+        );
+        assert_eq!(
+            run("2024/03/31 12:00 Europe/Rome - 2024/03/30 12:00 Europe/Rome"),
+            "23h"
+        );
+        assert_eq!(
+            run("2024/03/30 12:00 Europe/Rome == 2024/03/30 11:00"),
+            "true"
+        );
+        assert_eq!(
+            run("2024/03/31 02:30 Europe/Rome"),
+            "2024-03-31 03:30 +02:00 Europe/Rome"
+        );
+        assert!(
+            super::run("2024/03/30 12:00 Mars/Olympus", None)
+                .unwrap_err()
+                .contains("unknown time zone 'Mars/Olympus'")
+        );
+    }
+
+    #[test]
+    fn run_measures_between_two_dates() {
+        assert_eq!(
+            run("between 2024/03/10 and 2024/03/01", None).unwrap(),
+            "9d"
+        );
+        assert_eq!(run("between(09:30, 17:00)", None).unwrap(), "7h30m");
+    }
+
+    #[test]
+    fn run_applies_sign_functions() {
+        assert_eq!(run("abs(2024/01/01 - 2024/01/04)", None).unwrap(), "3d");
+        assert_eq!(run("negate(1h30m) + 2h", None).unwrap(), "30m");
+        assert!(run("negate(2024/01/01)", None).is_err());
+    }
+
+    #[test]
+    fn run_takes_percentages() {
+        assert_eq!(run("25% of 8h", None).unwrap(), "2h");
+        assert_eq!(run("25% of 8h + 1h", None).unwrap(), "3h");
+        assert_eq!(run("8h * 12%", None).unwrap(), "57m36s");
+        assert_eq!(run("3 * 25%", None).unwrap(), "75%");
+        assert_eq!(run("7%2", None).unwrap(), "1");
+        assert!(run("25% of 2024/01/01", None).is_err());
+    }
+
+    #[test]
+    fn run_evaluates_long_flat_chains() {
+        let options = Options::default();
+        let chain = vec!["1h"; 1000].join(" + ");
+        let compiled = compile(&chain).unwrap();
+
+        assert_eq!(compiled.run(&options).unwrap(), "41d16h");
+        assert_eq!(compiled.explain(&options).unwrap().trace.len(), 1999);
+        assert!(format_source(&chain, &options).is_ok());
+        assert!(compile(&format!("({}) * 2", chain)).is_ok());
+        assert!(compile_template(&chain, &options).is_ok());
+    }
+
+    #[test]
+    fn run_divides_durations() {
+        assert_eq!(run("90m / 8h", None).unwrap(), "18.75%");
+        assert_eq!(run("8h / 3", None).unwrap(), "2h40m");
+        assert_eq!(run("-1h / 4h", None).unwrap(), "-25%");
+        assert_eq!(run("1h / 3h", None).unwrap(), "33.33%");
+        assert_eq!(run("(90m / 8h) * 8h", None).unwrap(), "1h30m");
+        assert_eq!(
+            run("1h / 0s", None).unwrap_err(),
+            "failed to evaluate expression: cannot divide '1h' by zero"
+        );
+    }
+
+    #[test]
+    fn run_sums_over_recurrences() {
+        assert_eq!(
+            run("sum(2h every weekday from 2025/01/06 to 2025/01/31)", None).unwrap(),
+            run("20 * 2h", None).unwrap()
+        );
+        assert_eq!(
+            run("sum(30m every friday from 2025/01/01 to 2025/01/31)", None).unwrap(),
+            "2h30m"
+        );
+        assert_eq!(run("sum(1h, 30m, 15m)", None).unwrap(), "1h45m");
+        assert!(run("sum(1h every day from 2025/01/01 to 9999/01/01)", None).is_err());
+        assert!(run("every day", None).is_err());
+    }
+
+    #[test]
+    fn template_binds_values_to_placeholders() {
+        let options = Options::default();
+        let template = compile_template("{start} + {len} * 2 - {len}", &options).unwrap();
+        assert_eq!(template.placeholders(), ["start", "len"]);
+
+        let start = compile("2024/03/01").unwrap().eval(&options).unwrap();
+        let len = compile("3d").unwrap().eval(&options).unwrap();
+        assert_eq!(
+            template
+                .run(&[("start", start.clone()), ("len", len)], &options)
+                .unwrap(),
+            "2024-03-04"
         );
 
-        assert!(result.is_err());
+        let err = template.run(&[("start", start)], &options).unwrap_err();
+        assert!(err.contains("'{len}'"), "{}", err);
+        assert!(compile_template("{start + 1d", &options).is_err());
+    }
+
+    #[test]
+    fn run_counts_days_in_month() {
+        let result = run("days_in(2024/02)", None).unwrap();
+
+        assert_eq!(result, "29d");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn expr_and_value_round_trip_through_json() {
+        let options = Options::default();
+        let expr = parse(Lexer::new("2024/03/07 + 2 months")).unwrap();
+
+        let json = serde_json::to_string(&expr).unwrap();
+        let decoded: Expr = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded, expr);
+
+        let value = eval_with_options(&Ast::from(&decoded), &options).unwrap();
+        let json = serde_json::to_string(&value).unwrap();
+        let decoded: Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded.display(&options).to_string(), "2024-05-06");
+    }
+
+    #[test]
+    fn run_rejects_working_day_steps_beyond_limit() {
+        let err = run("2024/01/01 + 200_000wd", None).unwrap_err();
+
+        assert!(err.contains("cannot count 200000 working days; the limit is 100000"));
+    }
+
+    #[test]
+    fn calendar_from_holidays_rejects_non_date() {
+        let holidays = vec!["2h".to_string()];
+
+        assert!(calendar_from_holidays(&holidays).is_err());
     }
 }
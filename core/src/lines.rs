@@ -0,0 +1,99 @@
+//! Evaluates newline-separated expressions as they are read.
+
+use std::io::{self, BufRead, Lines};
+
+use crate::evaluator::Evaluation;
+use crate::lexer::split_comment;
+use crate::options::Options;
+use crate::session::Session;
+
+/// The outcome of one expression line.
+#[derive(Debug, Clone)]
+pub struct EvaluatedLine {
+    /// The 1-based line number in the input.
+    pub line: usize,
+    /// The line as read, without its line ending.
+    pub source: String,
+    pub result: Result<Evaluation, String>,
+}
+
+/// Iterator returned by [`eval_lines`].
+pub struct EvalLines<R> {
+    lines: Lines<R>,
+    session: Session,
+    line: usize,
+}
+
+/// Lexes, parses and evaluates each line of `reader` as its own expression,
+/// one line at a time, so the input is never buffered as a whole. Lines that
+/// are blank or hold only a `#` comment are skipped; a failing line yields
+/// its error and evaluation carries on with the next one. All lines share
+/// one [`Session`], and so one reference time.
+pub fn eval_lines<R: BufRead>(reader: R, options: &Options) -> EvalLines<R> {
+    EvalLines {
+        lines: reader.lines(),
+        session: Session::new(options.clone()),
+        line: 0,
+    }
+}
+
+impl<R: BufRead> Iterator for EvalLines<R> {
+    type Item = io::Result<EvaluatedLine>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let source = match self.lines.next()? {
+                Ok(source) => source,
+                Err(err) => return Some(Err(err)),
+            };
+            self.line += 1;
+
+            let code = split_comment(&source).0;
+            if code.trim().is_empty() {
+                continue;
+            }
+
+            let result = self.session.evaluate(code);
+            return Some(Ok(EvaluatedLine {
+                line: self.line,
+                source,
+                result,
+            }));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_evaluates_each_expression_line() {
+        let input = "# deadlines\n2024/01/31 + 1d\n\n2024/13/01\n2h + 30m # break\n";
+
+        let lines = eval_lines(input.as_bytes(), &Options::default())
+            .map(|line| line.unwrap())
+            .collect::<Vec<_>>();
+
+        let numbers = lines.iter().map(|line| line.line).collect::<Vec<_>>();
+        assert_eq!(numbers, [2, 4, 5]);
+        let value = |index: usize| lines[index].result.as_ref().map(|e| e.value.to_string());
+        assert_eq!(value(0).unwrap(), "2024-02-01");
+        assert!(value(1).is_err());
+        assert_eq!(value(2).unwrap(), "2h30m");
+        assert_eq!(lines[2].source, "2h + 30m # break");
+    }
+
+    #[test]
+    fn test_keeps_hashes_inside_strings() {
+        let input = "format(2024/01/01, \"#%d\") # day\n";
+
+        let lines = eval_lines(input.as_bytes(), &Options::default())
+            .map(|line| line.unwrap())
+            .collect::<Vec<_>>();
+
+        assert_eq!(lines.len(), 1);
+        let value = lines[0].result.as_ref().map(|e| e.value.to_string());
+        assert_eq!(value.unwrap(), "#01");
+    }
+}
@@ -0,0 +1,178 @@
+//! Flags expressions that evaluate fine but probably do not mean what they
+//! say.
+
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::parser::{Expr, Op};
+
+/// Nanoseconds in a day, the range a time of day stays within.
+const DAY_NANOS: i128 = 24 * 3600 * 1_000_000_000;
+
+/// A suspicious construct found by [`lint`], holding the sub-expression it
+/// was found in.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Lint {
+    /// Two dates added together, where their difference was likely meant.
+    AddedDates(Expr),
+    /// A month or year count mixed with a fixed duration or a time of day,
+    /// which counts each month as 30 days.
+    ApproximatedMonths(Expr),
+    /// A time of day moved past midnight, losing the change of day.
+    WrappedMidnight(Expr),
+}
+
+impl fmt::Display for Lint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Lint::AddedDates(expr) => write!(
+                f,
+                "'{}' adds two dates; subtract them for the time between",
+                expr
+            ),
+            Lint::ApproximatedMonths(expr) => write!(
+                f,
+                "'{}' counts months as 30 days; add them to a date to count calendar months",
+                expr
+            ),
+            Lint::WrappedMidnight(expr) => write!(
+                f,
+                "'{}' wraps past midnight; use a date and time to keep the day",
+                expr
+            ),
+        }
+    }
+}
+
+impl Lint {
+    /// The sub-expression the lint was found in.
+    pub fn expr(&self) -> &Expr {
+        match self {
+            Lint::AddedDates(expr)
+            | Lint::ApproximatedMonths(expr)
+            | Lint::WrappedMidnight(expr) => expr,
+        }
+    }
+}
+
+/// Looks through `expr` for likely mistakes, without evaluating it, and
+/// returns them innermost first.
+pub fn lint(expr: &Expr) -> Vec<Lint> {
+    let mut lints = Vec::new();
+    visit(expr, &mut lints);
+    lints
+}
+
+fn visit(expr: &Expr, lints: &mut Vec<Lint>) {
+    match expr {
+        Expr::UnaryOp(_, operand) => visit(operand, lints),
+        Expr::BinOp(left, op, right) => {
+            visit(left, lints);
+            visit(right, lints);
+            if let Some(lint) = check(left, op, right) {
+                lints.push(lint(expr.clone()));
+            }
+        }
+        Expr::Call(_, args) => args.iter().for_each(|arg| visit(arg, lints)),
+        _ => {}
+    }
+}
+
+fn check(left: &Expr, op: &Op, right: &Expr) -> Option<fn(Expr) -> Lint> {
+    match (left, op, right) {
+        (left, Op::Add, right) if is_date(left) && is_date(right) => Some(Lint::AddedDates),
+        (Expr::Time(hour, minute), Op::Add | Op::Sub, duration)
+        | (duration, Op::Add, Expr::Time(hour, minute))
+            if fixed_nanos(duration).is_some() =>
+        {
+            let time = (i128::from(*hour) * 60 + i128::from(*minute)) * 60 * 1_000_000_000;
+            let nanos = fixed_nanos(duration)?;
+            let moved = match op {
+                Op::Sub => time - nanos,
+                _ => time + nanos,
+            };
+            (!(0..DAY_NANOS).contains(&moved)).then_some(Lint::WrappedMidnight)
+        }
+        (left, _, right)
+            if (is_months(left) && is_fixed(right)) || (is_fixed(left) && is_months(right)) =>
+        {
+            Some(Lint::ApproximatedMonths)
+        }
+        _ => None,
+    }
+}
+
+/// Whether `expr` is written as a date or a date and time.
+fn is_date(expr: &Expr) -> bool {
+    matches!(
+        expr,
+        Expr::Date(..)
+            | Expr::DateTime(..)
+            | Expr::ZonedDateTime(..)
+            | Expr::MonthDay(..)
+            | Expr::Sprint(..)
+            | Expr::Payday(_)
+            | Expr::Keyword(_)
+            | Expr::KeywordTime(..)
+    )
+}
+
+fn is_months(expr: &Expr) -> bool {
+    matches!(expr, Expr::Duration(_, unit) if unit.months().is_some())
+}
+
+fn is_fixed(expr: &Expr) -> bool {
+    matches!(expr, Expr::Time(..)) || fixed_nanos(expr).is_some()
+}
+
+/// The length of a fixed duration literal, in nanoseconds.
+fn fixed_nanos(expr: &Expr) -> Option<i128> {
+    match expr {
+        Expr::Duration(value, unit) => Some(i128::from(*value) * i128::from(unit.nanoseconds()?)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::Lexer;
+    use crate::parser::parse;
+
+    fn lint_input(input: &str) -> Vec<Lint> {
+        lint(&parse(Lexer::new(input)).unwrap())
+    }
+
+    #[test]
+    fn test_flags_suspicious_constructs() {
+        assert!(matches!(
+            lint_input("today + 2024/12/25").as_slice(),
+            [Lint::AddedDates(_)]
+        ));
+        assert!(matches!(
+            lint_input("today + (1 month + 3d)").as_slice(),
+            [Lint::ApproximatedMonths(_)]
+        ));
+        assert!(matches!(
+            lint_input("abs(23:00 + 2h)").as_slice(),
+            [Lint::WrappedMidnight(_)]
+        ));
+        assert_eq!(
+            lint_input("00:30 - 1h")[0].to_string(),
+            "'00:30 - 1h' wraps past midnight; use a date and time to keep the day"
+        );
+    }
+
+    #[test]
+    fn test_accepts_ordinary_expressions() {
+        for input in [
+            "today + 1 month + 3d",
+            "2024/12/25 - today",
+            "09:00 + 8h",
+            "1 year + 2 months",
+        ] {
+            assert_eq!(lint_input(input), [], "{}", input);
+        }
+    }
+}
@@ -0,0 +1,497 @@
+use alloc::{format, string::String};
+use core::str::FromStr;
+
+use time::{Date, Month, Weekday};
+
+const MONTHS_EN: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+const WEEKDAYS_EN: [&str; 7] = [
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+    "Sunday",
+];
+
+#[cfg(feature = "locales")]
+const MONTHS_IT: [&str; 12] = [
+    "gennaio",
+    "febbraio",
+    "marzo",
+    "aprile",
+    "maggio",
+    "giugno",
+    "luglio",
+    "agosto",
+    "settembre",
+    "ottobre",
+    "novembre",
+    "dicembre",
+];
+#[cfg(feature = "locales")]
+const WEEKDAYS_IT: [&str; 7] = [
+    "lunedì",
+    "martedì",
+    "mercoledì",
+    "giovedì",
+    "venerdì",
+    "sabato",
+    "domenica",
+];
+
+#[cfg(feature = "locales")]
+const MONTHS_DE: [&str; 12] = [
+    "Januar",
+    "Februar",
+    "März",
+    "April",
+    "Mai",
+    "Juni",
+    "Juli",
+    "August",
+    "September",
+    "Oktober",
+    "November",
+    "Dezember",
+];
+#[cfg(feature = "locales")]
+const WEEKDAYS_DE: [&str; 7] = [
+    "Montag",
+    "Dienstag",
+    "Mittwoch",
+    "Donnerstag",
+    "Freitag",
+    "Samstag",
+    "Sonntag",
+];
+
+#[cfg(feature = "locales")]
+const MONTHS_FR: [&str; 12] = [
+    "janvier",
+    "février",
+    "mars",
+    "avril",
+    "mai",
+    "juin",
+    "juillet",
+    "août",
+    "septembre",
+    "octobre",
+    "novembre",
+    "décembre",
+];
+#[cfg(feature = "locales")]
+const WEEKDAYS_FR: [&str; 7] = [
+    "lundi", "mardi", "mercredi", "jeudi", "vendredi", "samedi", "dimanche",
+];
+
+#[cfg(feature = "locales")]
+const MONTHS_ES: [&str; 12] = [
+    "enero",
+    "febrero",
+    "marzo",
+    "abril",
+    "mayo",
+    "junio",
+    "julio",
+    "agosto",
+    "septiembre",
+    "octubre",
+    "noviembre",
+    "diciembre",
+];
+#[cfg(feature = "locales")]
+const WEEKDAYS_ES: [&str; 7] = [
+    "lunes",
+    "martes",
+    "miércoles",
+    "jueves",
+    "viernes",
+    "sábado",
+    "domingo",
+];
+
+/// Singular and plural names of days down to nanoseconds, for durations in
+/// words.
+const DURATION_UNITS_EN: [[&str; 2]; 7] = [
+    ["day", "days"],
+    ["hour", "hours"],
+    ["minute", "minutes"],
+    ["second", "seconds"],
+    ["millisecond", "milliseconds"],
+    ["microsecond", "microseconds"],
+    ["nanosecond", "nanoseconds"],
+];
+#[cfg(feature = "locales")]
+const DURATION_UNITS_IT: [[&str; 2]; 7] = [
+    ["giorno", "giorni"],
+    ["ora", "ore"],
+    ["minuto", "minuti"],
+    ["secondo", "secondi"],
+    ["millisecondo", "millisecondi"],
+    ["microsecondo", "microsecondi"],
+    ["nanosecondo", "nanosecondi"],
+];
+#[cfg(feature = "locales")]
+const DURATION_UNITS_DE: [[&str; 2]; 7] = [
+    ["Tag", "Tage"],
+    ["Stunde", "Stunden"],
+    ["Minute", "Minuten"],
+    ["Sekunde", "Sekunden"],
+    ["Millisekunde", "Millisekunden"],
+    ["Mikrosekunde", "Mikrosekunden"],
+    ["Nanosekunde", "Nanosekunden"],
+];
+#[cfg(feature = "locales")]
+const DURATION_UNITS_FR: [[&str; 2]; 7] = [
+    ["jour", "jours"],
+    ["heure", "heures"],
+    ["minute", "minutes"],
+    ["seconde", "secondes"],
+    ["milliseconde", "millisecondes"],
+    ["microseconde", "microsecondes"],
+    ["nanoseconde", "nanosecondes"],
+];
+#[cfg(feature = "locales")]
+const DURATION_UNITS_ES: [[&str; 2]; 7] = [
+    ["día", "días"],
+    ["hora", "horas"],
+    ["minuto", "minutos"],
+    ["segundo", "segundos"],
+    ["milisegundo", "milisegundos"],
+    ["microsegundo", "microsegundos"],
+    ["nanosegundo", "nanosegundos"],
+];
+
+/// A language and region for names and long dates. Languages other than
+/// English come with the `locales` feature, so a `match` needs a wildcard arm.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum Locale {
+    EnUs,
+    EnGb,
+    #[cfg(feature = "locales")]
+    ItIt,
+    #[cfg(feature = "locales")]
+    DeDe,
+    #[cfg(feature = "locales")]
+    FrFr,
+    #[cfg(feature = "locales")]
+    EsEs,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DateStyle {
+    #[default]
+    Numeric,
+    Long,
+}
+
+impl Locale {
+    pub const ALL: &'static [Locale] = &[
+        Locale::EnUs,
+        Locale::EnGb,
+        #[cfg(feature = "locales")]
+        Locale::ItIt,
+        #[cfg(feature = "locales")]
+        Locale::DeDe,
+        #[cfg(feature = "locales")]
+        Locale::FrFr,
+        #[cfg(feature = "locales")]
+        Locale::EsEs,
+    ];
+
+    pub fn tag(self) -> &'static str {
+        match self {
+            Locale::EnUs => "en-US",
+            Locale::EnGb => "en-GB",
+            #[cfg(feature = "locales")]
+            Locale::ItIt => "it-IT",
+            #[cfg(feature = "locales")]
+            Locale::DeDe => "de-DE",
+            #[cfg(feature = "locales")]
+            Locale::FrFr => "fr-FR",
+            #[cfg(feature = "locales")]
+            Locale::EsEs => "es-ES",
+        }
+    }
+
+    pub fn month_names(self) -> &'static [&'static str; 12] {
+        match self {
+            Locale::EnUs | Locale::EnGb => &MONTHS_EN,
+            #[cfg(feature = "locales")]
+            Locale::ItIt => &MONTHS_IT,
+            #[cfg(feature = "locales")]
+            Locale::DeDe => &MONTHS_DE,
+            #[cfg(feature = "locales")]
+            Locale::FrFr => &MONTHS_FR,
+            #[cfg(feature = "locales")]
+            Locale::EsEs => &MONTHS_ES,
+        }
+    }
+
+    pub fn weekday_names(self) -> &'static [&'static str; 7] {
+        match self {
+            Locale::EnUs | Locale::EnGb => &WEEKDAYS_EN,
+            #[cfg(feature = "locales")]
+            Locale::ItIt => &WEEKDAYS_IT,
+            #[cfg(feature = "locales")]
+            Locale::DeDe => &WEEKDAYS_DE,
+            #[cfg(feature = "locales")]
+            Locale::FrFr => &WEEKDAYS_FR,
+            #[cfg(feature = "locales")]
+            Locale::EsEs => &WEEKDAYS_ES,
+        }
+    }
+
+    /// Singular and plural names of days, hours, minutes, seconds,
+    /// milliseconds, microseconds and nanoseconds.
+    pub fn duration_unit_names(self) -> &'static [[&'static str; 2]; 7] {
+        match self {
+            Locale::EnUs | Locale::EnGb => &DURATION_UNITS_EN,
+            #[cfg(feature = "locales")]
+            Locale::ItIt => &DURATION_UNITS_IT,
+            #[cfg(feature = "locales")]
+            Locale::DeDe => &DURATION_UNITS_DE,
+            #[cfg(feature = "locales")]
+            Locale::FrFr => &DURATION_UNITS_FR,
+            #[cfg(feature = "locales")]
+            Locale::EsEs => &DURATION_UNITS_ES,
+        }
+    }
+
+    /// Whether a count takes the singular in this language: only one does in
+    /// English, but zero does too in French.
+    pub fn is_singular(self, count: u64) -> bool {
+        match self {
+            #[cfg(feature = "locales")]
+            Locale::FrFr => count <= 1,
+            _ => count == 1,
+        }
+    }
+
+    /// The name of a duration unit, by its index in
+    /// [`Locale::duration_unit_names`], agreeing with `count`.
+    pub fn duration_unit_name(self, index: usize, count: u64) -> &'static str {
+        let [singular, plural] = self.duration_unit_names()[index];
+        if self.is_singular(count) {
+            singular
+        } else {
+            plural
+        }
+    }
+
+    pub fn month_name(self, month: Month) -> &'static str {
+        self.month_names()[usize::from(u8::from(month)) - 1]
+    }
+
+    pub fn weekday_name(self, weekday: Weekday) -> &'static str {
+        self.weekday_names()[usize::from(weekday.number_days_from_monday())]
+    }
+
+    pub fn next_words(self) -> &'static [&'static str] {
+        match self {
+            Locale::EnUs | Locale::EnGb => &["next"],
+            #[cfg(feature = "locales")]
+            Locale::ItIt => &["prossimo", "prossima"],
+            #[cfg(feature = "locales")]
+            Locale::DeDe => &["nächsten", "nächste", "nächster", "kommenden"],
+            #[cfg(feature = "locales")]
+            Locale::FrFr => &["prochain", "prochaine"],
+            #[cfg(feature = "locales")]
+            Locale::EsEs => &["próximo", "próxima"],
+        }
+    }
+
+    pub fn last_words(self) -> &'static [&'static str] {
+        match self {
+            Locale::EnUs | Locale::EnGb => &["last"],
+            #[cfg(feature = "locales")]
+            Locale::ItIt => &["scorso", "scorsa"],
+            #[cfg(feature = "locales")]
+            Locale::DeDe => &["letzten", "letzte", "letzter", "vergangenen"],
+            #[cfg(feature = "locales")]
+            Locale::FrFr => &["dernier", "dernière"],
+            #[cfg(feature = "locales")]
+            Locale::EsEs => &["pasado", "pasada"],
+        }
+    }
+
+    /// Looks up a month by its (case-insensitive) name in this locale. Unique
+    /// prefixes of at least three letters, such as `jan` or `sept`, also match.
+    pub fn month_from_name(self, name: &str) -> Option<Month> {
+        let name = name.to_lowercase();
+        let names = self.month_names().map(str::to_lowercase);
+
+        let index = match names.iter().position(|month| *month == name) {
+            Some(index) => index,
+            None if name.chars().count() >= 3 => {
+                let mut prefixed = names
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, month)| month.starts_with(&name));
+                match (prefixed.next(), prefixed.next()) {
+                    (Some((index, _)), None) => index,
+                    _ => return None,
+                }
+            }
+            None => return None,
+        };
+        Month::try_from(index as u8 + 1).ok()
+    }
+
+    /// Looks up a weekday by its (case-insensitive) name in this locale.
+    pub fn weekday_from_name(self, name: &str) -> Option<Weekday> {
+        let name = name.to_lowercase();
+        let index = self
+            .weekday_names()
+            .iter()
+            .position(|weekday| weekday.to_lowercase() == name)?;
+        Some(Weekday::Monday.nth_next(index as u8))
+    }
+
+    pub fn format_year_month(self, year: i32, month: Month, style: DateStyle) -> String {
+        match (style, self) {
+            #[cfg(feature = "locales")]
+            (DateStyle::Numeric, Locale::DeDe) => format!("{:02}.{:04}", u8::from(month), year),
+            (DateStyle::Numeric, _) => format!("{:02}/{:04}", u8::from(month), year),
+            #[cfg(feature = "locales")]
+            (DateStyle::Long, Locale::EsEs) => format!("{} de {}", self.month_name(month), year),
+            (DateStyle::Long, _) => format!("{} {}", self.month_name(month), year),
+        }
+    }
+
+    pub fn format_date(self, date: Date, style: DateStyle) -> String {
+        let (year, day) = (date.year(), date.day());
+        let month = u8::from(date.month());
+
+        match style {
+            DateStyle::Numeric => match self {
+                Locale::EnUs => format!("{:02}/{:02}/{:04}", month, day, year),
+                #[cfg(feature = "locales")]
+                Locale::DeDe => format!("{:02}.{:02}.{:04}", day, month, year),
+                _ => format!("{:02}/{:02}/{:04}", day, month, year),
+            },
+            DateStyle::Long => {
+                let weekday = self.weekday_name(date.weekday());
+                let month = self.month_name(date.month());
+                match self {
+                    Locale::EnUs => format!("{}, {} {}, {}", weekday, month, day, year),
+                    Locale::EnGb => format!("{} {} {} {}", weekday, day, month, year),
+                    #[cfg(feature = "locales")]
+                    Locale::DeDe => format!("{}, {}. {} {}", weekday, day, month, year),
+                    #[cfg(feature = "locales")]
+                    Locale::EsEs => format!("{}, {} de {} de {}", weekday, day, month, year),
+                    #[cfg(feature = "locales")]
+                    Locale::ItIt | Locale::FrFr => {
+                        format!("{} {} {} {}", weekday, day, month, year)
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl FromStr for Locale {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Locale::ALL
+            .iter()
+            .copied()
+            .find(|locale| locale.tag().eq_ignore_ascii_case(&value.replace('_', "-")))
+            .ok_or_else(|| format!("unsupported locale '{}'", value))
+    }
+}
+
+impl FromStr for DateStyle {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "numeric" => Ok(DateStyle::Numeric),
+            "long" => Ok(DateStyle::Long),
+            _ => Err(format!(
+                "invalid date style '{}', expected numeric or long",
+                value
+            )),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "locales"))]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: Month, day: u8) -> Date {
+        Date::from_calendar_date(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn parse_locale_tag() {
+        assert_eq!("it-IT".parse::<Locale>(), Ok(Locale::ItIt));
+        assert_eq!("en_us".parse::<Locale>(), Ok(Locale::EnUs));
+        assert!("xx-XX".parse::<Locale>().is_err());
+    }
+
+    #[test]
+    fn lookup_names_case_insensitively() {
+        assert_eq!(
+            Locale::ItIt.month_from_name("Gennaio"),
+            Some(Month::January)
+        );
+        assert_eq!(
+            Locale::DeDe.weekday_from_name("montag"),
+            Some(Weekday::Monday)
+        );
+        assert_eq!(Locale::EnUs.month_from_name("gennaio"), None);
+        assert_eq!(Locale::EnUs.month_from_name("Sept"), Some(Month::September));
+        assert_eq!(Locale::FrFr.month_from_name("jui"), None);
+    }
+
+    #[test]
+    fn format_numeric_dates_in_regional_order() {
+        let date = date(2024, Month::January, 31);
+        assert_eq!(
+            Locale::ItIt.format_date(date, DateStyle::Numeric),
+            "31/01/2024"
+        );
+        assert_eq!(
+            Locale::EnUs.format_date(date, DateStyle::Numeric),
+            "01/31/2024"
+        );
+        assert_eq!(
+            Locale::DeDe.format_date(date, DateStyle::Numeric),
+            "31.01.2024"
+        );
+    }
+
+    #[test]
+    fn format_long_dates_with_localized_names() {
+        let date = date(2024, Month::January, 31);
+        assert_eq!(
+            Locale::ItIt.format_date(date, DateStyle::Long),
+            "mercoledì 31 gennaio 2024"
+        );
+        assert_eq!(
+            Locale::EnUs.format_date(date, DateStyle::Long),
+            "Wednesday, January 31, 2024"
+        );
+    }
+}
@@ -0,0 +1,128 @@
+//! Finds the times on a day that fall within working hours in each of a set
+//! of time zones, for scheduling a meeting across them.
+
+use alloc::vec::Vec;
+
+use time::{Date, Duration, OffsetDateTime, Time, Weekday};
+
+use crate::zone::Zone;
+
+/// A stretch of time from `start` up to, but not including, `end`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Slot {
+    pub start: OffsetDateTime,
+    pub end: OffsetDateTime,
+}
+
+impl Slot {
+    pub fn length(&self) -> Duration {
+        self.end - self.start
+    }
+}
+
+/// The slots at least `length` long on `day`, a day in the first of `zones`,
+/// during which every zone is within its working hours. Working hours run
+/// from `hours.0` to `hours.1` in each zone's local time, Monday to Friday.
+pub fn meeting_slots(
+    day: Date,
+    zones: &[Zone],
+    hours: (Time, Time),
+    length: Duration,
+) -> Vec<Slot> {
+    let Some((first, _)) = zones.split_first() else {
+        return Vec::new();
+    };
+    let whole_day = day.next_day().and_then(|next| {
+        Some(Slot {
+            start: first.local(day.midnight())?,
+            end: first.local(next.midnight())?,
+        })
+    });
+
+    zones
+        .iter()
+        .map(|zone| working_hours(*zone, day, hours))
+        .fold(whole_day.into_iter().collect::<Vec<_>>(), |slots, hours| {
+            intersect(&slots, &hours)
+        })
+        .into_iter()
+        .filter(|slot| slot.length() >= length)
+        .collect()
+}
+
+/// The working hours of `zone` on the local days around `day`, which cover
+/// every instant of `day` in any other zone.
+fn working_hours(zone: Zone, day: Date, (open, close): (Time, Time)) -> Vec<Slot> {
+    [day.previous_day(), Some(day), day.next_day()]
+        .into_iter()
+        .flatten()
+        .filter(|date| !matches!(date.weekday(), Weekday::Saturday | Weekday::Sunday))
+        .filter_map(|date| {
+            Some(Slot {
+                start: zone.local(date.with_time(open))?,
+                end: zone.local(date.with_time(close))?,
+            })
+        })
+        .filter(|slot| slot.start < slot.end)
+        .collect()
+}
+
+/// The times in both `left` and `right`, each sorted and without overlaps.
+fn intersect(left: &[Slot], right: &[Slot]) -> Vec<Slot> {
+    let mut slots = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while let (Some(a), Some(b)) = (left.get(i), right.get(j)) {
+        let start = a.start.max(b.start);
+        let end = a.end.min(b.end);
+        if start < end {
+            slots.push(Slot { start, end });
+        }
+        if a.end < b.end {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    slots
+}
+
+#[cfg(all(test, feature = "tz"))]
+mod tests {
+    use super::*;
+    use time::{Month, UtcOffset};
+
+    fn time(hour: u8) -> Time {
+        Time::from_hms(hour, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn test_finds_overlapping_working_hours() {
+        let zones = ["Europe/Rome", "America/New_York"].map(|name| Zone::from_name(name).unwrap());
+        let tuesday = Date::from_calendar_date(2024, Month::October, 15).unwrap();
+        let rome = UtcOffset::from_hms(2, 0, 0).unwrap();
+
+        let slots = meeting_slots(tuesday, &zones, (time(9), time(18)), Duration::HOUR);
+        assert_eq!(
+            slots,
+            [Slot {
+                start: tuesday.with_time(time(15)).assume_offset(rome),
+                end: tuesday.with_time(time(18)).assume_offset(rome),
+            }]
+        );
+        assert_eq!(slots[0].length(), Duration::hours(3));
+
+        let long = meeting_slots(tuesday, &zones, (time(9), time(18)), Duration::hours(4));
+        assert_eq!(long, []);
+    }
+
+    #[test]
+    fn test_skips_weekends() {
+        let zones = [Zone::from_name("Asia/Tokyo").unwrap()];
+        let saturday = Date::from_calendar_date(2024, Month::October, 19).unwrap();
+
+        assert_eq!(
+            meeting_slots(saturday, &zones, (time(9), time(18)), Duration::HOUR),
+            []
+        );
+    }
+}
@@ -0,0 +1,128 @@
+use time::{Date, OffsetDateTime, Time};
+
+use crate::calendar::{Calendar, MissingDay};
+use crate::evaluator::{MonthArithmetic, Overflow};
+use crate::format::{DurationStyle, HourFormat, Precision};
+use crate::locale::{DateStyle, Locale};
+use crate::parser::{AssumeMissing, DateOrder, Syntax, TwoDigitYears};
+use crate::rotation::Rotation;
+use crate::units::Units;
+
+#[derive(Debug, Clone, Default)]
+pub struct Options {
+    pub calendar: Calendar,
+    pub day_parts: DayParts,
+    pub hour_format: HourFormat,
+    pub locale: Option<Locale>,
+    pub date_style: DateStyle,
+    /// Follow printed dates and date-times with their weekday, as in
+    /// `2024-07-04 (Thursday)`. The long date style names it already.
+    pub show_weekday: bool,
+    /// Print results in ASCII only, for consoles that cannot show more:
+    /// letters lose their accents and `µs` becomes `us`.
+    pub ascii: bool,
+    pub duration_style: DurationStyle,
+    pub precision: Precision,
+    pub syntax: Syntax,
+    /// The unit names durations can be written with, beyond the built-in
+    /// ones.
+    pub units: Units,
+    pub date_order: DateOrder,
+    pub two_digit_years: TwoDigitYears,
+    pub assume_missing: AssumeMissing,
+    pub month_arithmetic: MonthArithmetic,
+    pub missing_day: MissingDay,
+    pub overflow: Overflow,
+    pub limits: Limits,
+    /// How sprints are laid out, for `start of sprint`, `end of sprint` and
+    /// `sprint 14`. Those fail to evaluate without it.
+    pub sprints: Option<Sprints>,
+    /// When paydays fall, for `next payday`, `last payday`, `paydays in
+    /// 2025` and `every payday`. Those fail to evaluate without it.
+    pub paydays: Option<Paydays>,
+    /// Who takes turns on call, for finding who is on call at a time and
+    /// when someone's next shift starts.
+    pub rotation: Option<Rotation>,
+    /// The reference time `now`, `today` and other relative keywords resolve
+    /// against. Defaults to the current UTC time, read once per evaluation
+    /// so that `now - now` is exactly zero.
+    pub now: Option<OffsetDateTime>,
+}
+
+/// Bounds on the work a single expression may cause, so untrusted input
+/// fails with an error instead of exhausting the stack or the CPU.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Limits {
+    /// Deepest nesting of parentheses, calls and unary operators. A chain of
+    /// binary operators such as `1h + 2h + 3h` counts once; see
+    /// [`Limits::max_operators`].
+    pub max_depth: usize,
+    /// Most binary operators in one expression, however flat.
+    pub max_operators: usize,
+    /// Longest input, in bytes.
+    pub max_input_length: usize,
+    /// Most tokens in one expression, not counting comments.
+    pub max_tokens: usize,
+    /// Most days a single working-day step may count through.
+    pub max_iterations: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_depth: 256,
+            max_operators: 1024,
+            max_input_length: 64 * 1024,
+            max_tokens: 8192,
+            max_iterations: 100_000,
+        }
+    }
+}
+
+/// Back-to-back sprints of equal length, numbered from 1.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sprints {
+    /// The first day of sprint 1. Sprints before it count down to 0 and
+    /// below.
+    pub start: Date,
+    /// How many days each sprint lasts, at least one.
+    pub length: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Paydays {
+    /// Every `days` days from `start`, such as every two weeks.
+    Every { start: Date, days: u32 },
+    /// On two days of each month, the earlier first. A day past the end of
+    /// a shorter month falls on its last day, so `SemiMonthly(15, 31)` pays
+    /// on the 15th and the last day of every month.
+    SemiMonthly(u8, u8),
+}
+
+/// Times of day that `this morning`, `this afternoon`, `this evening` and
+/// `tonight` resolve to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DayParts {
+    /// When a day begins, midnight by default. With a later start, such as
+    /// 06:00 for night shifts, the hours before it still belong to the
+    /// previous day: `today` at 03:00 is yesterday's date, `tonight` at
+    /// 02:00 is the early hours of the next date, and `end of day` is the
+    /// next start.
+    pub start: Time,
+    pub morning: Time,
+    pub afternoon: Time,
+    pub evening: Time,
+    pub night: Time,
+}
+
+impl Default for DayParts {
+    fn default() -> Self {
+        Self {
+            start: Time::MIDNIGHT,
+            morning: Time::from_hms(9, 0, 0).expect("valid time"),
+            afternoon: Time::from_hms(15, 0, 0).expect("valid time"),
+            evening: Time::from_hms(19, 0, 0).expect("valid time"),
+            night: Time::from_hms(21, 0, 0).expect("valid time"),
+        }
+    }
+}
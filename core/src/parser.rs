@@ -1,72 +1,403 @@
-use std::iter::Peekable;
+use alloc::{
+    boxed::Box,
+    format,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+use core::iter::Peekable;
 
-use crate::lexer::{Lexer, Token};
+use time::{Month, Weekday};
+
+use crate::ast::{Ast, Node, NodeId};
+use crate::gnu;
+use crate::lexer::{LexError, Lexer, Token};
+use crate::locale::Locale;
+use crate::options::Options;
+use crate::units;
 
 const HOURS_IN_HALF_DAY: i64 = 12;
+const HALF: i64 = 2;
+const QUARTER: i64 = 4;
+const MIN_YEAR: i64 = -9999;
+const MAX_YEAR: i64 = 9999;
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Expr {
-    Date(u32, u8, u8),
+    Number(i64),
+    Date(i32, u8, u8),
+    YearMonth(i32, u8),
+    /// A month and day without a year, as in `jan 5` when
+    /// [`AssumeMissing::Current`] fills in the year, or `3rd of november`.
+    MonthDay(u8, u8),
     Time(u8, u8),
-    DateTime(u32, u8, u8, u8, u8),
+    DateTime(i32, u8, u8, u8, u8),
+    /// A date and time in a named zone, as in `2024/03/30 12:00 Europe/Rome`.
+    ZonedDateTime(i32, u8, u8, u8, u8, String),
     Keyword(Keyword),
+    /// A day keyword refined with a time of day, as in `yesterday 17:00`.
+    KeywordTime(Keyword, u8, u8),
+    /// The first payday after today, `next payday`, or with `false` the last
+    /// one before it, `last payday`.
+    Payday(bool),
+    /// The first or last day of a sprint, by number or, without one, the
+    /// current sprint: `sprint 14`, `start of sprint`, `end of sprint`.
+    Sprint(SprintEdge, Option<i64>),
     Duration(i64, Unit),
+    /// A percentage, as in `25%`.
+    Percent(i64),
+    /// The days a recurrence falls on, as in `every weekday`. Only written
+    /// inside `sum(2h every weekday from <date> to <date>)`.
+    Recurrence(Recurrence),
+    Bool(bool),
+    /// An earlier result by number, as in `%2`, counting from 1.
+    History(u32),
+    /// The latest earlier result, `ans`.
+    Ans,
+    /// A slot in a [`Template`](crate::Template), as in `{start}`, filled in
+    /// with a value when the template is evaluated.
+    Placeholder(String),
+    /// Text in double quotes, as in the pattern of `format(x, "%d %B")`.
+    Str(String),
+    UnaryOp(UnaryOp, Box<Expr>),
     BinOp(Box<Expr>, Op, Box<Expr>),
+    Call(String, Vec<Expr>),
+}
+
+/// Order of the components in slash-separated numeric dates. A first
+/// component that cannot be a day or month is always read as the year.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum DateOrder {
+    #[default]
+    Ymd,
+    Dmy,
+    Mdy,
+}
+
+impl core::str::FromStr for DateOrder {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "ymd" => Ok(DateOrder::Ymd),
+            "dmy" => Ok(DateOrder::Dmy),
+            "mdy" => Ok(DateOrder::Mdy),
+            _ => Err(format!(
+                "invalid date order '{}', expected ymd, dmy or mdy",
+                value
+            )),
+        }
+    }
+}
+
+impl core::fmt::Display for DateOrder {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.write_str(match self {
+            DateOrder::Ymd => "ymd",
+            DateOrder::Dmy => "dmy",
+            DateOrder::Mdy => "mdy",
+        })
+    }
+}
+
+/// How years below 100 are read. `Pivot(70)` maps `69` to 2069 and `70` to
+/// 1970; `Strict` rejects them as ambiguous.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum TwoDigitYears {
+    #[default]
+    Strict,
+    Pivot(u8),
+}
+
+impl core::str::FromStr for TwoDigitYears {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "strict" => Ok(TwoDigitYears::Strict),
+            _ => match value.parse::<u8>() {
+                Ok(pivot @ 0..=100) => Ok(TwoDigitYears::Pivot(pivot)),
+                _ => Err(format!(
+                    "invalid two-digit year policy '{}', expected strict or a pivot between 0 and 100",
+                    value
+                )),
+            },
+        }
+    }
+}
+
+/// How inputs that leave out part of a date are completed. `Strict` keeps
+/// `14:30` a time of day and reads `jan 5` as a month and a year; `Current`
+/// takes the missing parts from the reference time, so `14:30` is today at
+/// 14:30 and `jan 5` is January 5 of the current year, each with a warning.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum AssumeMissing {
+    #[default]
+    Strict,
+    Current,
+}
+
+impl core::str::FromStr for AssumeMissing {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "strict" => Ok(AssumeMissing::Strict),
+            "current" => Ok(AssumeMissing::Current),
+            _ => Err(format!(
+                "invalid missing component policy '{}', expected strict or current",
+                value
+            )),
+        }
+    }
+}
+
+/// Which language inputs are written in. `Gnu` reads the date strings GNU
+/// `date -d` accepts instead, such as `2 days ago` or `next thursday`, so
+/// shell scripts written for it keep working.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Syntax {
+    #[default]
+    Tcalc,
+    Gnu,
+}
+
+impl core::str::FromStr for Syntax {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "tcalc" => Ok(Syntax::Tcalc),
+            "gnu" => Ok(Syntax::Gnu),
+            _ => Err(format!("invalid syntax '{}', expected tcalc or gnu", value)),
+        }
+    }
 }
 
+/// A binary operator, as passed to [`build::binary`](crate::build::binary).
+/// New operators may be added, so a `match` needs a wildcard arm.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
 pub enum Op {
     Add,
     Sub,
+    Mul,
+    Div,
+    Rem,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    Equal,
+    NotEqual,
+    And,
+    Or,
+}
+
+impl Op {
+    /// Binding strength of the operator; higher binds tighter.
+    pub fn precedence(&self) -> u8 {
+        match self {
+            Op::Or => 1,
+            Op::And => 2,
+            Op::Less
+            | Op::LessEqual
+            | Op::Greater
+            | Op::GreaterEqual
+            | Op::Equal
+            | Op::NotEqual => 4,
+            Op::Add | Op::Sub => 5,
+            Op::Mul | Op::Div | Op::Rem => 6,
+        }
+    }
+
+    pub fn is_comparison(&self) -> bool {
+        matches!(
+            self,
+            Op::Less | Op::LessEqual | Op::Greater | Op::GreaterEqual | Op::Equal | Op::NotEqual
+        )
+    }
 }
 
-impl std::fmt::Display for Op {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+impl core::fmt::Display for Op {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
         match self {
             Op::Add => write!(f, "+"),
             Op::Sub => write!(f, "-"),
+            Op::Mul => write!(f, "*"),
+            Op::Div => write!(f, "/"),
+            Op::Rem => write!(f, "%"),
+            Op::Less => write!(f, "<"),
+            Op::LessEqual => write!(f, "<="),
+            Op::Greater => write!(f, ">"),
+            Op::GreaterEqual => write!(f, ">="),
+            Op::Equal => write!(f, "=="),
+            Op::NotEqual => write!(f, "!="),
+            Op::And => write!(f, "and"),
+            Op::Or => write!(f, "or"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum UnaryOp {
+    Not,
+    Neg,
+}
+
+impl UnaryOp {
+    /// Binding strength of the operator, on the same scale as
+    /// [`Op::precedence`].
+    pub fn precedence(&self) -> u8 {
+        match self {
+            UnaryOp::Not => 3,
+            UnaryOp::Neg => 7,
+        }
+    }
+}
+
+impl core::fmt::Display for UnaryOp {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self {
+            UnaryOp::Not => write!(f, "not"),
+            UnaryOp::Neg => write!(f, "-"),
         }
     }
 }
 
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Keyword {
     Today,
     Now,
     Tomorrow,
     Yesterday,
+    Morning,
+    Afternoon,
+    Evening,
+    Tonight,
+    /// The start of the next day, as in `end of day`.
+    EndOfDay,
+    This(Period),
+    Next(Period),
+    Last(Period),
+    NextWeekday(Weekday),
+    LastWeekday(Weekday),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SprintEdge {
+    Start,
+    /// The last day of the sprint, not the start of the next one.
+    End,
+}
+
+/// Which days a recurrence falls on.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Recurrence {
+    Day,
+    /// Monday to Friday.
+    Weekday,
+    /// Monday to Friday, except the calendar's holidays.
+    WorkingDay,
+    On(Weekday),
+    /// The paydays set in [`Options::paydays`](crate::Options::paydays).
+    Payday,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Period {
+    Week,
+    Month,
+    Year,
 }
 
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Unit {
+    /// A hundred years, always added to dates as calendar years.
+    Centuries,
+    /// Ten years, always added to dates as calendar years.
+    Decades,
     Years,
+    /// Three months, with the `colloquial` feature. Exact on dates only
+    /// under calendar month arithmetic; by default a quarter is 90 days.
+    Quarters,
     Months,
+    /// Two weeks, with the `colloquial` feature.
+    Fortnights,
+    Weeks,
     Days,
     WorkingDays,
+    /// Monday to Friday, counted without the calendar's holidays.
+    Weekdays,
     Hours,
     Minutes,
     Seconds,
+    Milliseconds,
+    Microseconds,
+    Nanoseconds,
+}
+
+impl Unit {
+    /// Length of the unit in nanoseconds, for units with a fixed length.
+    pub fn nanoseconds(&self) -> Option<i64> {
+        const SECOND: i64 = 1_000_000_000;
+        match self {
+            Unit::Fortnights => Some(14 * 24 * 3600 * SECOND),
+            Unit::Weeks => Some(7 * 24 * 3600 * SECOND),
+            Unit::Days => Some(24 * 3600 * SECOND),
+            Unit::Hours => Some(3600 * SECOND),
+            Unit::Minutes => Some(60 * SECOND),
+            Unit::Seconds => Some(SECOND),
+            Unit::Milliseconds => Some(1_000_000),
+            Unit::Microseconds => Some(1_000),
+            Unit::Nanoseconds => Some(1),
+            Unit::Centuries
+            | Unit::Decades
+            | Unit::Years
+            | Unit::Quarters
+            | Unit::Months
+            | Unit::WorkingDays
+            | Unit::Weekdays => None,
+        }
+    }
+
+    /// Length of the unit in calendar months, for units counted in months.
+    pub fn months(&self) -> Option<i64> {
+        match self {
+            Unit::Centuries => Some(1200),
+            Unit::Decades => Some(120),
+            Unit::Years => Some(12),
+            Unit::Quarters => Some(3),
+            Unit::Months => Some(1),
+            _ => None,
+        }
+    }
 }
 
 impl TryFrom<&str> for Unit {
     type Error = ParsingError;
 
+    /// Looks up a built-in unit name, without the ones in
+    /// [`Options::units`](crate::Options::units).
     fn try_from(value: &str) -> Result<Self, Self::Error> {
-        match value {
-            "years" | "year" | "y" => Ok(Unit::Years),
-            "months" | "month" => Ok(Unit::Months),
-            "days" | "day" | "d" => Ok(Unit::Days),
-            "workingdays" | "workingday" | "workdays" | "workday" | "wd" => Ok(Unit::WorkingDays),
-            "hours" | "hour" | "h" => Ok(Unit::Hours),
-            "minutes" | "minute" | "m" => Ok(Unit::Minutes),
-            "seconds" | "second" | "s" => Ok(Unit::Seconds),
-            _ => Err(ParsingError::UnknownKeyword(value.to_string())),
-        }
+        units::builtin(value).ok_or_else(|| ParsingError::UnknownKeyword(value.to_string()))
     }
 }
 
+/// Why an input could not be parsed. New reasons may be added, so a `match`
+/// needs a wildcard arm.
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum ParsingError {
+    Lex(LexError),
     UnexpectedToken(Token),
     UnknownKeyword(String),
     UnexpectedEof,
@@ -74,16 +405,37 @@ pub enum ParsingError {
     ExpectedNumber,
     ExpectedSlash,
     ExpectedColon,
+    ExpectedLParen,
+    ExpectedRParen,
+    TooDeep(usize),
+    /// More binary operators than [`Limits::max_operators`](crate::Limits::max_operators).
+    TooManyOperators(usize),
+    TooLong(usize),
+    TooManyTokens(usize),
     ExpectedUnit,
     InvalidYear(i64),
+    TwoDigitYear(i64),
     InvalidMonth(i64),
     InvalidDay(i64),
+    /// A day past the end of its month, as in `2023/02/29`.
+    DayOutOfMonth(i32, u8, u8),
+    InvalidHour(i64),
+    InvalidMinute(i64),
     InvalidTime(String),
+    InvalidFraction(String),
+    InvalidDuration(String),
+    /// An amount too large to hold, as in `999999999999999999 fortnights`.
+    OutOfRange(String),
+    /// A part of a date given twice, as in `monday tuesday`.
+    Repeated(&'static str),
+    /// An ordinal suffix that does not fit its day, as in `3th`.
+    OrdinalSuffix(i64, String),
 }
 
-impl std::fmt::Display for ParsingError {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+impl core::fmt::Display for ParsingError {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
         match self {
+            ParsingError::Lex(err) => err.fmt(f),
             ParsingError::UnexpectedToken(token) => write!(f, "unexpected token '{}'", token),
             ParsingError::UnknownKeyword(keyword) => write!(f, "unknown keyword '{}'", keyword),
             ParsingError::UnexpectedEof => write!(f, "unexpected end of input"),
@@ -91,398 +443,2071 @@ impl std::fmt::Display for ParsingError {
             ParsingError::ExpectedNumber => write!(f, "expected number"),
             ParsingError::ExpectedSlash => write!(f, "expected slash"),
             ParsingError::ExpectedColon => write!(f, "expected colon"),
+            ParsingError::ExpectedLParen => write!(f, "expected '('"),
+            ParsingError::ExpectedRParen => write!(f, "expected ')'"),
+            ParsingError::TooDeep(max) => {
+                write!(f, "expression nests deeper than {} levels", max)
+            }
+            ParsingError::TooManyOperators(max) => {
+                write!(f, "expression has more than {} operators", max)
+            }
+            ParsingError::TooLong(max) => write!(f, "input is longer than {} bytes", max),
+            ParsingError::TooManyTokens(max) => {
+                write!(f, "expression has more than {} tokens", max)
+            }
             ParsingError::ExpectedUnit => write!(f, "expected unit"),
-            ParsingError::InvalidYear(year) => write!(f, "invalid year '{}'", year),
-            ParsingError::InvalidMonth(month) => write!(f, "invalid month '{}'", month),
-            ParsingError::InvalidDay(day) => write!(f, "invalid day '{}'", day),
+            ParsingError::InvalidYear(year) => write!(
+                f,
+                "invalid year '{}', expected {} to {}",
+                year, MIN_YEAR, MAX_YEAR
+            ),
+            ParsingError::TwoDigitYear(year) => {
+                write!(
+                    f,
+                    "ambiguous two-digit year '{}', set a pivot to accept it",
+                    year
+                )
+            }
+            ParsingError::InvalidMonth(month) => {
+                write!(f, "invalid month '{}', expected 1 to 12", month)
+            }
+            ParsingError::InvalidDay(day) => write!(f, "invalid day '{}', expected 1 to 31", day),
+            ParsingError::DayOutOfMonth(year, month, day) => {
+                let length = Month::try_from(*month).map_or(31, |m| m.length(*year));
+                write!(
+                    f,
+                    "invalid day '{}', {:04}/{:02} has {} days",
+                    day, year, month, length
+                )
+            }
+            ParsingError::InvalidHour(hour) => {
+                write!(f, "invalid hour '{}', expected 0 to 23", hour)
+            }
+            ParsingError::InvalidMinute(minute) => {
+                write!(f, "invalid minute '{}', expected 0 to 59", minute)
+            }
             ParsingError::InvalidTime(time_string) => write!(f, "invalid time '{}'", time_string),
+            ParsingError::InvalidFraction(fraction) => {
+                write!(f, "cannot express '{}' exactly", fraction)
+            }
+            ParsingError::InvalidDuration(duration) => {
+                write!(f, "cannot combine units in '{}'", duration)
+            }
+            ParsingError::OutOfRange(amount) => write!(f, "'{}' is out of range", amount),
+            ParsingError::Repeated(part) => write!(f, "more than one {} given", part),
+            ParsingError::OrdinalSuffix(day, suffix) => write!(
+                f,
+                "invalid ordinal '{}{}', expected '{}{}'",
+                day,
+                suffix,
+                day,
+                ordinal_suffix(*day)
+            ),
         }
     }
 }
 
-impl std::error::Error for ParsingError {}
+impl core::error::Error for ParsingError {}
 
 /// Grammar
 ///
-/// <expr> ::= <primary> (('+' | '-') <primary>)*
-/// <primary> ::= <datetime> | <time> | <duration> | <keyword>
-/// <datetime> ::= <date> <time>?
-/// <date> ::= NUMBER '/' NUMBER '/' NUMBER
-/// <time> ::= NUMBER ':' NUMBER | NUMBER ("am" | "pm")
+/// <expr> ::= <binary> ("|>" IDENT ('(' (<expr> (',' <expr>)*)? ')')?)*
+/// <binary> ::= <prefix> (BINARY-OP <prefix>)*
+/// <prefix> ::= "not" <expr> | '-' <prefix> | <primary>
+/// <primary> ::= '(' <expr> ')' | <datetime> | <named-date> | <year-month> | <time> | <duration> | <keyword>
+///             | <weekday> | <call> | NUMBER | STRING | "true" | "false"
+/// <call> ::= IDENT '(' (<expr> (',' <expr>)*)? ')'
+/// <year-month> ::= NUMBER '/' NUMBER | MONTH NUMBER
+/// <keyword> ::= ("today" | "tomorrow" | "yesterday" | "now") <time-of-day>? | "tonight"
+///             | "this" ("morning" | "afternoon" | "evening")
+///             | ("this" | "next" | "last") ("week" | "month" | "year")
+/// <duration> ::= (NUMBER | "a" | "an") UNIT ("and" ("a" | "an") "half")? | <fraction>
+///              | NUMBER UNIT (NUMBER UNIT)+
+/// <fraction> ::= ("a" | "an")? ("half" | "quarter") "of"? ("a" | "an")? UNIT
+/// <datetime> ::= <date> ('T'? (NUMBER ':' NUMBER | <military>))?
+///              | NUMBER '-' NUMBER '-' NUMBER 'T' (NUMBER ':' NUMBER | <military>)
+/// <date> ::= NUMBER '/' NUMBER '/' NUMBER (ordered by [`DateOrder`])
+/// <time> ::= <time-of-day> | "t" <military>
+/// <time-of-day> ::= NUMBER ':' NUMBER | NUMBER ("am" | "pm")
+/// <military> ::= NUMBER "h"?
+/// <named-date> ::= (NUMBER | <ordinal> "of"?) MONTH NUMBER | MONTH (NUMBER | <ordinal>) NUMBER
+///                | <ordinal> "of"? MONTH | MONTH <ordinal>
+/// <ordinal> ::= NUMBER ("st" | "nd" | "rd" | "th")
+/// <weekday> ::= NEXT WEEKDAY | LAST WEEKDAY | WEEKDAY (NEXT | LAST)
+///
+/// Operators bind from loosest to tightest as `or`, `and`, `not`, the
+/// comparisons (`<`, `<=`, `>`, `>=`, `==`, `!=`), `+`/`-`, `*`/`%`, and
+/// unary `-`; see [`Op::precedence`]. Comparisons cannot be chained.
+///
+/// A pipeline `x |> f(a)` binds looser than every operator and is read as
+/// the call `f(x, a)`, with the value on the left as the first argument;
+/// `x |> f` is `f(x)`.
+///
+/// MONTH, WEEKDAY, NEXT and LAST are matched against English and the
+/// locale selected in [`Options`].
 pub fn parse(lexer: Lexer) -> Result<Expr, ParsingError> {
-    let mut tokens = lexer.into_iter().peekable();
-    let expr = parse_expr(&mut tokens)?;
+    parse_with_options(lexer, &Options::default())
+}
 
-    match tokens.next() {
-        Some(Token::Eof) => Ok(expr),
-        Some(token) => Err(ParsingError::UnexpectedToken(token)),
-        None => Err(ParsingError::UnexpectedEof),
-    }
+pub fn parse_with_options(lexer: Lexer, options: &Options) -> Result<Expr, ParsingError> {
+    Ok(parse_ast(lexer, options)?.to_expr())
 }
 
-fn parse_expr(tokens: &mut Peekable<Lexer>) -> Result<Expr, ParsingError> {
-    let mut left = parse_primary(tokens)?;
+/// One way to read an input, under a particular [`DateOrder`].
+#[cfg(any(feature = "unstable-ast", test))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reading {
+    pub date_order: DateOrder,
+    pub expr: Expr,
+}
 
-    while let Some(Token::Plus | Token::Minus) = tokens.peek() {
-        let op = match tokens.next() {
-            Some(Token::Plus) => Op::Add,
-            Some(Token::Minus) => Op::Sub,
-            Some(token) => return Err(ParsingError::UnexpectedToken(token)),
-            None => return Err(ParsingError::UnexpectedEof),
+/// Parses `lexer` under every [`DateOrder`], keeping each distinct result.
+/// More than one reading means the input is ambiguous, as `03/04/2024` is
+/// between March 4 and April 3; the order in `options` comes first. An
+/// input that fails to parse under every order has no readings.
+#[cfg(any(feature = "unstable-ast", test))]
+pub fn parse_readings(lexer: Lexer, options: &Options) -> Vec<Reading> {
+    let orders = [DateOrder::Ymd, DateOrder::Dmy, DateOrder::Mdy];
+    let orders = core::iter::once(options.date_order).chain(
+        orders
+            .into_iter()
+            .filter(|order| *order != options.date_order),
+    );
+
+    let mut readings: Vec<Reading> = Vec::new();
+    for date_order in orders {
+        let options = Options {
+            date_order,
+            ..options.clone()
         };
+        if let Ok(expr) = parse_with_options(lexer, &options)
+            && readings.iter().all(|reading| reading.expr != expr)
+        {
+            readings.push(Reading { date_order, expr });
+        }
+    }
+    readings
+}
 
-        let right = parse_primary(tokens)?;
-        left = Expr::BinOp(Box::new(left), op, Box::new(right));
+/// Like [`parse_with_options`], but keeps the tree in an [`Ast`] instead of
+/// boxing every node.
+pub fn parse_ast(lexer: Lexer, options: &Options) -> Result<Ast, ParsingError> {
+    let limits = options.limits;
+    if lexer.input().len() > limits.max_input_length {
+        return Err(ParsingError::TooLong(limits.max_input_length));
+    }
+    if lexer.check().map_err(ParsingError::Lex)? > limits.max_tokens {
+        return Err(ParsingError::TooManyTokens(limits.max_tokens));
+    }
+    if options.syntax == Syntax::Gnu {
+        return gnu::parse(lexer).map(|expr| Ast::from(&expr));
     }
 
-    Ok(left)
-}
+    let mut parser = Parser {
+        tokens: lexer.peekable(),
+        options,
+        depth: 0,
+        operators: 0,
+        ast: Ast::default(),
+    };
+    parser.parse_expr()?;
 
-fn parse_primary(tokens: &mut Peekable<Lexer>) -> Result<Expr, ParsingError> {
-    match tokens.peek() {
-        Some(Token::Number(_)) => parse_number(tokens),
-        Some(Token::Ident(_)) => parse_ident(tokens),
-        Some(token) => Err(ParsingError::UnexpectedToken(token.clone())),
+    match parser.tokens.next() {
+        Some(Token::Eof) => Ok(parser.ast),
+        Some(token) => Err(ParsingError::UnexpectedToken(token)),
         None => Err(ParsingError::UnexpectedEof),
     }
 }
 
-fn parse_ident(tokens: &mut Peekable<Lexer>) -> Result<Expr, ParsingError> {
-    match tokens.next() {
-        Some(Token::Ident(s)) => match s.as_str() {
-            "today" => Ok(Expr::Keyword(Keyword::Today)),
-            "tomorrow" => Ok(Expr::Keyword(Keyword::Tomorrow)),
-            "yesterday" => Ok(Expr::Keyword(Keyword::Yesterday)),
-            "now" => Ok(Expr::Keyword(Keyword::Now)),
-            _ => Err(ParsingError::UnknownKeyword(s)),
-        },
-        _ => Err(ParsingError::ExpectedIdent),
-    }
+struct Parser<'a> {
+    tokens: Peekable<Lexer<'a>>,
+    options: &'a Options,
+    depth: usize,
+    /// Binary operators read so far, which the evaluator walks in a loop
+    /// rather than nesting.
+    operators: usize,
+    ast: Ast,
 }
 
-fn parse_number(tokens: &mut Peekable<Lexer>) -> Result<Expr, ParsingError> {
-    let first_num = expect_number(tokens)?;
+impl Parser<'_> {
+    fn parse_expr(&mut self) -> Result<NodeId, ParsingError> {
+        let depth = self.depth;
+        let result = self.parse_pipeline();
+        self.depth = depth;
+        result
+    }
 
-    match tokens.peek() {
-        Some(Token::Slash) => parse_date(tokens, first_num),
-        Some(Token::Colon) => parse_time(tokens, first_num),
-        Some(Token::Ident(ident)) => match ident.as_str() {
-            "am" => {
-                tokens.next();
-                match first_num {
-                    1..=11 => Ok(Expr::Time(first_num as u8, 0)),
-                    12 => Ok(Expr::Time(0, 0)),
-                    _ => Err(ParsingError::InvalidTime(format!("{first_num} am"))),
-                }
-            }
-            "pm" => {
-                tokens.next();
-                match first_num {
-                    1..=11 => Ok(Expr::Time((first_num + HOURS_IN_HALF_DAY) as u8, 0)),
-                    12 => Ok(Expr::Time(12, 0)),
-                    _ => Err(ParsingError::InvalidTime(format!("{first_num} pm"))),
-                }
-            }
-            _ => parse_duration(tokens, first_num),
-        },
-        Some(token) => Err(ParsingError::UnexpectedToken(token.clone())),
-        None => Err(ParsingError::UnexpectedEof),
+    /// Parses a value followed by any number of `|> call` stages, each
+    /// taking the value so far as its first argument.
+    fn parse_pipeline(&mut self) -> Result<NodeId, ParsingError> {
+        let mut value = self.parse_binary(0)?;
+        while let Some(Token::Pipe) = self.tokens.peek() {
+            self.tokens.next();
+            // Each stage nests the pipeline so far one level deeper.
+            self.enter()?;
+            let name = match self.tokens.next() {
+                Some(Token::Ident(name)) => name,
+                Some(Token::Eof) | None => return Err(ParsingError::UnexpectedEof),
+                Some(token) => return Err(ParsingError::UnexpectedToken(token)),
+            };
+            value = match self.tokens.peek() {
+                Some(Token::LParen) => self.parse_call(name, vec![value])?,
+                _ => self.ast.push(Node::Call(name, vec![value])),
+            };
+        }
+        Ok(value)
     }
-}
 
-fn parse_date(tokens: &mut Peekable<Lexer>, year: i64) -> Result<Expr, ParsingError> {
-    expect_token(tokens, Token::Slash, ParsingError::ExpectedSlash)?;
-    let month = expect_number(tokens)?;
-    expect_token(tokens, Token::Slash, ParsingError::ExpectedSlash)?;
-    let day = expect_number(tokens)?;
+    /// Parses operators that bind tighter than `min_precedence`, climbing
+    /// to tighter operators for each right operand. Binary operators are
+    /// left-associative, except comparisons which cannot be chained.
+    fn parse_binary(&mut self, min_precedence: u8) -> Result<NodeId, ParsingError> {
+        let depth = self.depth;
+        let result = self.parse_binary_chain(min_precedence);
+        self.depth = depth;
+        result
+    }
 
-    let year = parse_year(year)?;
-    let month = parse_month(month)?;
-    let day = parse_day(day)?;
+    fn parse_binary_chain(&mut self, min_precedence: u8) -> Result<NodeId, ParsingError> {
+        let mut left = self.parse_prefix()?;
 
-    if let Some(Token::Number(_)) = tokens.peek() {
-        let hour = expect_number(tokens)?;
-        expect_token(tokens, Token::Colon, ParsingError::ExpectedColon)?;
-        let minute = expect_number(tokens)?;
-        let (hour, minute) = parse_time_parts(hour, minute)?;
-        Ok(Expr::DateTime(year, month, day, hour, minute))
-    } else {
-        Ok(Expr::Date(year, month, day))
-    }
-}
+        while let Some(op) = self.peek_op().filter(|op| op.precedence() > min_precedence) {
+            self.tokens.next();
+            self.count_operator()?;
+            let right = self.parse_binary(op.precedence())?;
 
-fn parse_time(tokens: &mut Peekable<Lexer>, hour: i64) -> Result<Expr, ParsingError> {
-    expect_token(tokens, Token::Colon, ParsingError::ExpectedColon)?;
-    let minute = expect_number(tokens)?;
-    let (hour, minute) = parse_time_parts(hour, minute)?;
-    Ok(Expr::Time(hour, minute))
-}
+            if op.is_comparison() && self.peek_op().is_some_and(|next| next.is_comparison()) {
+                let token = self.tokens.next().unwrap_or(Token::Eof);
+                return Err(ParsingError::UnexpectedToken(token));
+            }
+            left = self.ast.push(Node::BinOp(left, op, right));
+        }
 
-fn parse_duration(tokens: &mut Peekable<Lexer>, value: i64) -> Result<Expr, ParsingError> {
-    match tokens.next() {
-        Some(Token::Ident(u)) => Ok(Expr::Duration(value, Unit::try_from(u.as_str())?)),
-        _ => Err(ParsingError::ExpectedUnit),
+        Ok(left)
     }
-}
 
-fn expect_token(
-    tokens: &mut Peekable<Lexer>,
-    expected: Token,
-    err: ParsingError,
-) -> Result<(), ParsingError> {
-    match tokens.next() {
-        Some(t) if t == expected => Ok(()),
-        Some(t) => Err(ParsingError::UnexpectedToken(t)),
-        None => Err(err),
+    /// The binary operator at the next token, if any.
+    fn peek_op(&mut self) -> Option<Op> {
+        match self.tokens.peek()? {
+            Token::Plus => Some(Op::Add),
+            Token::Minus => Some(Op::Sub),
+            Token::Star => Some(Op::Mul),
+            Token::Slash => Some(Op::Div),
+            Token::Percent => Some(Op::Rem),
+            Token::Less => Some(Op::Less),
+            Token::LessEqual => Some(Op::LessEqual),
+            Token::Greater => Some(Op::Greater),
+            Token::GreaterEqual => Some(Op::GreaterEqual),
+            Token::EqualEqual => Some(Op::Equal),
+            Token::BangEqual => Some(Op::NotEqual),
+            Token::Ident(ident) if ident == "and" => Some(Op::And),
+            Token::Ident(ident) if ident == "or" => Some(Op::Or),
+            _ => None,
+        }
     }
-}
 
-fn expect_number(tokens: &mut Peekable<Lexer>) -> Result<i64, ParsingError> {
-    match tokens.next() {
-        Some(Token::Number(n)) => Ok(n),
-        _ => Err(ParsingError::ExpectedNumber),
+    /// Counts one more level of nesting, failing beyond the depth limit.
+    fn enter(&mut self) -> Result<(), ParsingError> {
+        self.depth += 1;
+        match self.options.limits.max_depth {
+            max if self.depth > max => Err(ParsingError::TooDeep(max)),
+            _ => Ok(()),
+        }
     }
-}
 
-fn parse_year(year: i64) -> Result<u32, ParsingError> {
-    u32::try_from(year).map_err(|_| ParsingError::InvalidYear(year))
-}
+    /// Counts one more binary operator, failing beyond the operator limit.
+    fn count_operator(&mut self) -> Result<(), ParsingError> {
+        self.operators += 1;
+        match self.options.limits.max_operators {
+            max if self.operators > max => Err(ParsingError::TooManyOperators(max)),
+            _ => Ok(()),
+        }
+    }
 
-fn parse_month(month: i64) -> Result<u8, ParsingError> {
-    match month {
-        1..=12 => Ok(month as u8),
-        _ => Err(ParsingError::InvalidMonth(month)),
+    fn parse_prefix(&mut self) -> Result<NodeId, ParsingError> {
+        self.enter()?;
+        let result = self.parse_prefix_operand();
+        self.depth -= 1;
+        result
     }
-}
 
-fn parse_day(day: i64) -> Result<u8, ParsingError> {
-    match day {
-        1..=31 => Ok(day as u8),
-        _ => Err(ParsingError::InvalidDay(day)),
+    fn parse_prefix_operand(&mut self) -> Result<NodeId, ParsingError> {
+        if self.next_is_ident("not") {
+            self.tokens.next();
+            let operand = self.parse_binary(UnaryOp::Not.precedence())?;
+            return Ok(self.ast.push(Node::UnaryOp(UnaryOp::Not, operand)));
+        }
+
+        if let Some(Token::Minus) = self.tokens.peek() {
+            self.tokens.next();
+            let operand = self.parse_prefix()?;
+            return Ok(self.ast.push(Node::UnaryOp(UnaryOp::Neg, operand)));
+        }
+
+        self.parse_primary()
     }
-}
 
-fn parse_time_parts(hour: i64, minute: i64) -> Result<(u8, u8), ParsingError> {
-    match (hour, minute) {
-        (0..=23, 0..=59) => Ok((hour as u8, minute as u8)),
-        _ => Err(ParsingError::InvalidTime(format!("{hour}:{minute}"))),
+    fn parse_primary(&mut self) -> Result<NodeId, ParsingError> {
+        match self.tokens.peek() {
+            Some(Token::Number(_)) => {
+                let expr = self.parse_number()?;
+                Ok(self.ast.push(Node::Leaf(expr)))
+            }
+            Some(Token::Ident(_)) => self.parse_ident(),
+            Some(Token::Percentage(_)) => self.parse_percentage(),
+            Some(Token::Placeholder(_)) => match self.tokens.next() {
+                Some(Token::Placeholder(name)) => {
+                    Ok(self.ast.push(Node::Leaf(Expr::Placeholder(name))))
+                }
+                _ => unreachable!("peeked a placeholder"),
+            },
+            Some(Token::Str(_)) => match self.tokens.next() {
+                Some(Token::Str(text)) => Ok(self.ast.push(Node::Leaf(Expr::Str(text)))),
+                _ => unreachable!("peeked a string"),
+            },
+            Some(Token::Percent) => {
+                self.tokens.next();
+                match self.tokens.next() {
+                    Some(Token::Number(n)) if n >= 1 && n <= i64::from(u32::MAX) => {
+                        Ok(self.ast.push(Node::Leaf(Expr::History(n as u32))))
+                    }
+                    Some(token) => Err(ParsingError::UnexpectedToken(token)),
+                    None => Err(ParsingError::UnexpectedEof),
+                }
+            }
+            Some(Token::LParen) => {
+                self.tokens.next();
+                let expr = self.parse_expr()?;
+                match self.tokens.next() {
+                    Some(Token::RParen) => Ok(expr),
+                    Some(Token::Eof) | None => Err(ParsingError::ExpectedRParen),
+                    Some(token) => Err(ParsingError::UnexpectedToken(token)),
+                }
+            }
+            Some(token) => Err(ParsingError::UnexpectedToken(token.clone())),
+            None => Err(ParsingError::UnexpectedEof),
+        }
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    /// Parses `25%`, or `25% of <operand>` as the percentage times the
+    /// operand. The operand takes operators that bind tighter than `*`, so
+    /// `25% of 8h + 1h` adds the hour to the quarter.
+    fn parse_percentage(&mut self) -> Result<NodeId, ParsingError> {
+        let percent = match self.tokens.next() {
+            Some(Token::Percentage(percent)) => percent,
+            _ => return Err(ParsingError::UnexpectedEof),
+        };
+        let percent = self.ast.push(Node::Leaf(Expr::Percent(percent)));
+        if !self.next_is_ident("of") {
+            return Ok(percent);
+        }
 
-    #[test]
-    fn test_parse_date() {
-        let lexer = Lexer::new("2023/01/01");
-        let expr = parse(lexer).unwrap();
-        assert_eq!(expr, Expr::Date(2023, 1, 1));
+        self.tokens.next();
+        self.enter()?;
+        let operand = self.parse_binary(Op::Mul.precedence())?;
+        Ok(self.ast.push(Node::BinOp(percent, Op::Mul, operand)))
     }
 
-    #[test]
-    fn test_parse_date_rejects_year_overflow() {
-        let lexer = Lexer::new("999999999999/01/01");
-        assert!(parse(lexer).is_err());
+    fn parse_ident(&mut self) -> Result<NodeId, ParsingError> {
+        match self.tokens.next() {
+            Some(Token::Ident(s)) if matches!(self.tokens.peek(), Some(Token::LParen)) => {
+                self.parse_call(s, Vec::new())
+            }
+            Some(Token::Ident(s)) if s == "between" => self.parse_between(),
+            Some(Token::Ident(s)) if s == "paydays" && self.next_is_ident("in") => {
+                self.tokens.next();
+                let period = self.parse_binary(Op::Mul.precedence())?;
+                Ok(self.ast.push(Node::Call(s, vec![period])))
+            }
+            Some(Token::Ident(s)) => {
+                let expr = self.parse_word(s)?;
+                Ok(self.ast.push(Node::Leaf(expr)))
+            }
+            _ => Err(ParsingError::ExpectedIdent),
+        }
     }
 
-    #[test]
-    fn test_parse_date_rejects_month_overflow() {
-        let lexer = Lexer::new("2023/257/01");
-        assert!(parse(lexer).is_err());
+    /// Parses the operand that starts with the word `s`.
+    fn parse_word(&mut self, s: String) -> Result<Expr, ParsingError> {
+        match s.as_str() {
+            "today" => self.parse_keyword_time(Keyword::Today),
+            "tomorrow" => self.parse_keyword_time(Keyword::Tomorrow),
+            "yesterday" => self.parse_keyword_time(Keyword::Yesterday),
+            "now" => self.parse_keyword_time(Keyword::Now),
+            "tonight" => Ok(Expr::Keyword(Keyword::Tonight)),
+            "ans" => Ok(Expr::Ans),
+            "sprint" if matches!(self.tokens.peek(), Some(Token::Number(_))) => {
+                Ok(Expr::Sprint(SprintEdge::Start, Some(self.expect_number()?)))
+            }
+            "start" if self.next_is_ident("of") => {
+                self.tokens.next();
+                match self.tokens.next() {
+                    Some(Token::Ident(s)) if s == "sprint" => {
+                        Ok(self.parse_sprint(SprintEdge::Start))
+                    }
+                    Some(Token::Ident(s)) => {
+                        Err(ParsingError::UnknownKeyword(format!("start of {s}")))
+                    }
+                    _ => Err(ParsingError::ExpectedIdent),
+                }
+            }
+            "end" if self.next_is_ident("of") => {
+                self.tokens.next();
+                match self.tokens.next() {
+                    Some(Token::Ident(s)) if s == "day" => Ok(Expr::Keyword(Keyword::EndOfDay)),
+                    Some(Token::Ident(s)) if s == "sprint" => {
+                        Ok(self.parse_sprint(SprintEdge::End))
+                    }
+                    Some(Token::Ident(s)) => {
+                        Err(ParsingError::UnknownKeyword(format!("end of {s}")))
+                    }
+                    _ => Err(ParsingError::ExpectedIdent),
+                }
+            }
+            "this" => self.parse_this(),
+            "next" | "last" if self.next_is_ident("payday") => {
+                self.tokens.next();
+                Ok(Expr::Payday(s == "next"))
+            }
+            "next" if !self.next_is_weekday() => {
+                Ok(Expr::Keyword(Keyword::Next(self.expect_period()?)))
+            }
+            "last" if !self.next_is_weekday() => {
+                Ok(Expr::Keyword(Keyword::Last(self.expect_period()?)))
+            }
+            "true" => Ok(Expr::Bool(true)),
+            "false" => Ok(Expr::Bool(false)),
+            "half" => self.parse_fraction(HALF),
+            "quarter" => self.parse_fraction(QUARTER),
+            "a" | "an" if self.next_is_ident("half") => {
+                self.tokens.next();
+                self.parse_fraction(HALF)
+            }
+            "a" | "an" if self.next_is_ident("quarter") => {
+                self.tokens.next();
+                self.parse_fraction(QUARTER)
+            }
+            "a" | "an" => self.parse_duration(1),
+            "t" if matches!(self.tokens.peek(), Some(Token::Number(_))) => {
+                let value = self.expect_number()?;
+                let (hour, minute) = self.parse_military_time(value)?;
+                Ok(Expr::Time(hour, minute))
+            }
+            _ => self.parse_localized_ident(s),
+        }
     }
 
-    #[test]
-    fn test_parse_date_rejects_day_overflow() {
-        let lexer = Lexer::new("2023/01/257");
-        assert!(parse(lexer).is_err());
+    /// Reads the optional sprint number after `start of sprint` or `end of
+    /// sprint`.
+    fn parse_sprint(&mut self, edge: SprintEdge) -> Expr {
+        let number = match self.tokens.peek() {
+            Some(Token::Number(_)) => self.expect_number().ok(),
+            _ => None,
+        };
+        Expr::Sprint(edge, number)
     }
 
-    #[test]
-    fn test_parse_date_rejects_trailing_ident() {
-        let lexer = Lexer::new("2023/01/01foo");
-        assert!(parse(lexer).is_err());
+    /// Parses the arguments of a call to `name`, after any given in `args`.
+    fn parse_call(&mut self, name: String, mut args: Vec<NodeId>) -> Result<NodeId, ParsingError> {
+        self.expect_token(Token::LParen, ParsingError::ExpectedLParen)?;
+
+        if let Some(Token::RParen) = self.tokens.peek() {
+            self.tokens.next();
+            return Ok(self.ast.push(Node::Call(name, args)));
+        }
+
+        loop {
+            args.push(self.parse_expr()?);
+            if name == "sum" && args.len() == 1 && self.next_is_ident("every") {
+                return self.parse_recurrence_sum(name, args[0]);
+            }
+
+            match self.tokens.next() {
+                Some(Token::Comma) => continue,
+                Some(Token::RParen) => return Ok(self.ast.push(Node::Call(name, args))),
+                Some(token) => return Err(ParsingError::UnexpectedToken(token)),
+                None => return Err(ParsingError::UnexpectedEof),
+            }
+        }
     }
 
-    #[test]
-    fn test_parse_time_24h() {
-        let lexer = Lexer::new("14:30");
-        let expr = parse(lexer).unwrap();
-        assert_eq!(expr, Expr::Time(14, 30));
+    /// Parses the rest of `sum(<amount> every <recurrence> from <date> to
+    /// <date>)` after the amount, as a call with the recurrence as its
+    /// second argument.
+    fn parse_recurrence_sum(
+        &mut self,
+        name: String,
+        amount: NodeId,
+    ) -> Result<NodeId, ParsingError> {
+        self.tokens.next();
+        let recurrence = self.expect_recurrence()?;
+        let recurrence = self.ast.push(Node::Leaf(Expr::Recurrence(recurrence)));
+
+        let mut bounds = [amount; 2];
+        for (bound, word) in bounds.iter_mut().zip(["from", "to"]) {
+            match self.tokens.next() {
+                Some(Token::Ident(s)) if s == word => {}
+                Some(Token::Eof) | None => return Err(ParsingError::UnexpectedEof),
+                Some(token) => return Err(ParsingError::UnexpectedToken(token)),
+            }
+            *bound = self.parse_expr()?;
+        }
+        self.expect_token(Token::RParen, ParsingError::ExpectedRParen)?;
+
+        let [from, to] = bounds;
+        Ok(self
+            .ast
+            .push(Node::Call(name, vec![amount, recurrence, from, to])))
     }
 
-    #[test]
-    fn test_parse_time_rejects_24h_overflow() {
-        let lexer = Lexer::new("257:00");
-        assert!(parse(lexer).is_err());
+    /// Reads `day`, `weekday`, `working day` or a weekday name.
+    fn expect_recurrence(&mut self) -> Result<Recurrence, ParsingError> {
+        let locales = self.locales();
+        match self.tokens.next() {
+            Some(Token::Ident(s)) => match s.as_str() {
+                "day" => Ok(Recurrence::Day),
+                "weekday" => Ok(Recurrence::Weekday),
+                "workday" => Ok(Recurrence::WorkingDay),
+                "payday" => Ok(Recurrence::Payday),
+                "working" if self.next_is_ident("day") => {
+                    self.tokens.next();
+                    Ok(Recurrence::WorkingDay)
+                }
+                _ => weekday_from_name(&locales, &s)
+                    .map(Recurrence::On)
+                    .ok_or(ParsingError::UnknownKeyword(s)),
+            },
+            _ => Err(ParsingError::ExpectedIdent),
+        }
     }
 
-    #[test]
-    fn test_parse_time_rejects_minute_overflow() {
-        let lexer = Lexer::new("14:257");
-        assert!(parse(lexer).is_err());
+    /// Parses `between x and y` as a call to `between(x, y)`. The operands
+    /// stop before `and`, so comparing or combining the result needs
+    /// parentheses around it.
+    fn parse_between(&mut self) -> Result<NodeId, ParsingError> {
+        let from = self.parse_binary(Op::And.precedence())?;
+        match self.tokens.next() {
+            Some(Token::Ident(s)) if s == "and" => {}
+            Some(Token::Eof) | None => return Err(ParsingError::UnexpectedEof),
+            Some(token) => return Err(ParsingError::UnexpectedToken(token)),
+        }
+        let to = self.parse_binary(Op::And.precedence())?;
+        Ok(self
+            .ast
+            .push(Node::Call("between".to_string(), vec![from, to])))
     }
 
-    #[test]
-    fn test_parse_time_rejects_trailing_ident() {
-        let lexer = Lexer::new("14:30pm");
-        assert!(parse(lexer).is_err());
+    /// Handles month and weekday names from English and the selected locale:
+    /// `<month> <day> <year>`, `next <weekday>`, and `<weekday> next`.
+    fn parse_localized_ident(&mut self, ident: String) -> Result<Expr, ParsingError> {
+        let locales = self.locales();
+
+        if let Some(month) = month_from_name(&locales, &ident) {
+            let first = self.expect_number()?;
+            let ordinal = self.eat_ordinal_suffix(first)?;
+            let has_year = matches!(self.tokens.peek(), Some(Token::Number(_)));
+            if !has_year && self.assumes_year(first, ordinal) {
+                return month_day(month, first);
+            }
+            if ordinal || has_year {
+                let year = self.expect_number()?;
+                let year = self.parse_era_year(year)?;
+                let (year, month, day) = date_parts(year, u8::from(month).into(), first)?;
+                return self.parse_time_of_date(year, month, day);
+            }
+            return Ok(Expr::YearMonth(
+                self.parse_era_year(first)?,
+                u8::from(month),
+            ));
+        }
+
+        if is_next_word(&locales, &ident) {
+            return Ok(Expr::Keyword(Keyword::NextWeekday(self.expect_weekday()?)));
+        }
+
+        if is_last_word(&locales, &ident) {
+            return Ok(Expr::Keyword(Keyword::LastWeekday(self.expect_weekday()?)));
+        }
+
+        if let Some(weekday) = weekday_from_name(&locales, &ident) {
+            return match self.tokens.next() {
+                Some(Token::Ident(s)) if is_next_word(&locales, &s) => {
+                    Ok(Expr::Keyword(Keyword::NextWeekday(weekday)))
+                }
+                Some(Token::Ident(s)) if is_last_word(&locales, &s) => {
+                    Ok(Expr::Keyword(Keyword::LastWeekday(weekday)))
+                }
+                Some(token) => Err(ParsingError::UnexpectedToken(token)),
+                None => Err(ParsingError::UnexpectedEof),
+            };
+        }
+
+        Err(ParsingError::UnknownKeyword(ident))
     }
 
-    #[test]
-    fn test_parse_time_am() {
-        let lexer = Lexer::new("2am");
-        let expr = parse(lexer).unwrap();
-        assert_eq!(expr, Expr::Time(2, 0));
+    fn locales(&self) -> Vec<Locale> {
+        let mut locales = vec![Locale::EnGb];
+        locales.extend(self.options.locale);
+        locales
     }
 
-    #[test]
-    fn test_parse_time_pm() {
-        let lexer = Lexer::new("2pm");
-        let expr = parse(lexer).unwrap();
-        assert_eq!(expr, Expr::Time(14, 0));
+    fn next_is_weekday(&mut self) -> bool {
+        let locales = self.locales();
+        matches!(self.tokens.peek(), Some(Token::Ident(ident)) if weekday_from_name(&locales, ident).is_some())
     }
 
-    #[test]
-    fn test_parse_time_12am() {
-        let lexer = Lexer::new("12am");
-        let expr = parse(lexer).unwrap();
-        assert_eq!(expr, Expr::Time(0, 0));
+    fn expect_month(&mut self) -> Result<Month, ParsingError> {
+        let locales = self.locales();
+        match self.tokens.next() {
+            Some(Token::Ident(s)) => {
+                month_from_name(&locales, &s).ok_or(ParsingError::UnknownKeyword(s))
+            }
+            _ => Err(ParsingError::ExpectedIdent),
+        }
+    }
+
+    fn expect_weekday(&mut self) -> Result<Weekday, ParsingError> {
+        let locales = self.locales();
+        match self.tokens.next() {
+            Some(Token::Ident(s)) => {
+                weekday_from_name(&locales, &s).ok_or(ParsingError::UnknownKeyword(s))
+            }
+            _ => Err(ParsingError::ExpectedIdent),
+        }
+    }
+
+    fn parse_this(&mut self) -> Result<Expr, ParsingError> {
+        match self.tokens.next() {
+            Some(Token::Ident(s)) => match s.as_str() {
+                "morning" => Ok(Expr::Keyword(Keyword::Morning)),
+                "afternoon" => Ok(Expr::Keyword(Keyword::Afternoon)),
+                "evening" => Ok(Expr::Keyword(Keyword::Evening)),
+                _ => match period_from_ident(&s) {
+                    Some(period) => Ok(Expr::Keyword(Keyword::This(period))),
+                    None => Err(ParsingError::UnknownKeyword(format!("this {s}"))),
+                },
+            },
+            _ => Err(ParsingError::ExpectedIdent),
+        }
+    }
+
+    fn expect_period(&mut self) -> Result<Period, ParsingError> {
+        match self.tokens.next() {
+            Some(Token::Ident(s)) => period_from_ident(&s).ok_or(ParsingError::UnknownKeyword(s)),
+            _ => Err(ParsingError::ExpectedIdent),
+        }
+    }
+
+    fn parse_number(&mut self) -> Result<Expr, ParsingError> {
+        let first_num = self.expect_number()?;
+        let locales = self.locales();
+
+        if self.next_is_iso_datetime() {
+            return self.parse_iso_datetime(first_num);
+        }
+
+        match self.tokens.peek() {
+            Some(Token::Slash) => self.parse_date(first_num),
+            Some(Token::Colon) => self.parse_time(first_num),
+            Some(Token::Ident(ident)) => match ident.as_str() {
+                "am" | "pm" => {
+                    let (hour, minute) = self.parse_meridiem(first_num)?;
+                    Ok(Expr::Time(hour, minute))
+                }
+                suffix if is_ordinal_suffix(suffix) => {
+                    self.eat_ordinal_suffix(first_num)?;
+                    if self.next_is_ident("of") {
+                        self.tokens.next();
+                    }
+                    self.parse_day_month_year(first_num, true)
+                }
+                name if month_from_name(&locales, name).is_some() => {
+                    self.parse_day_month_year(first_num, false)
+                }
+                _ => self.parse_duration(first_num),
+            },
+            _ => Ok(Expr::Number(first_num)),
+        }
+    }
+
+    /// Parses the `<month> <year>` that follows a day in `5 January 2024`, or
+    /// in `5th January`, where the day is `ordinal`.
+    fn parse_day_month_year(&mut self, day: i64, ordinal: bool) -> Result<Expr, ParsingError> {
+        let month = self.expect_month()?;
+        if !matches!(self.tokens.peek(), Some(Token::Number(_))) && self.assumes_year(day, ordinal)
+        {
+            return month_day(month, day);
+        }
+        let year = self.expect_number()?;
+        let year = self.parse_era_year(year)?;
+        let (year, month, day) = date_parts(year, u8::from(month).into(), day)?;
+        self.parse_time_of_date(year, month, day)
+    }
+
+    fn parse_date(&mut self, first: i64) -> Result<Expr, ParsingError> {
+        self.expect_token(Token::Slash, ParsingError::ExpectedSlash)?;
+        let second = self.expect_number()?;
+
+        if !matches!(self.tokens.peek(), Some(Token::Slash)) {
+            let (year, month) = match self.options.date_order {
+                DateOrder::Ymd => (first, second),
+                _ if first > 12 => (first, second),
+                DateOrder::Dmy | DateOrder::Mdy => (second, first),
+            };
+            return Ok(Expr::YearMonth(self.parse_year(year)?, parse_month(month)?));
+        }
+
+        self.expect_token(Token::Slash, ParsingError::ExpectedSlash)?;
+        let third = self.expect_number()?;
+
+        let (year, month, day) = match self.options.date_order {
+            _ if first > 31 => (first, second, third),
+            DateOrder::Ymd => (first, second, third),
+            DateOrder::Dmy => (third, second, first),
+            DateOrder::Mdy => (third, first, second),
+        };
+
+        self.parse_date_time(year, month, day)
+    }
+
+    /// Parses an ISO `YYYY-MM-DDTHH:MM` datetime, starting after the year.
+    /// The dashes were already checked by `next_is_iso_datetime`.
+    fn parse_iso_datetime(&mut self, year: i64) -> Result<Expr, ParsingError> {
+        self.tokens.next();
+        let month = self.expect_number()?;
+        self.tokens.next();
+        let day = self.expect_number()?;
+        self.parse_date_time(year, month, day)
+    }
+
+    /// Validates a numeric date and reads the time of day that may follow it.
+    fn parse_date_time(&mut self, year: i64, month: i64, day: i64) -> Result<Expr, ParsingError> {
+        let year = self.parse_year(year)?;
+        let (year, month, day) = date_parts(year, month, day)?;
+        self.parse_time_of_date(year, month, day)
+    }
+
+    /// Reads the time of day that may follow a checked date, either after
+    /// whitespace or after a `T` separator.
+    fn parse_time_of_date(&mut self, year: i32, month: u8, day: u8) -> Result<Expr, ParsingError> {
+        let separated = self.next_is_ident("T") || self.next_is_ident("t");
+        if separated {
+            self.tokens.next();
+        }
+
+        if separated || matches!(self.tokens.peek(), Some(Token::Number(_))) {
+            let hour = self.expect_number()?;
+            let (hour, minute) = if let Some(Token::Colon) = self.tokens.peek() {
+                self.tokens.next();
+                let minute = self.expect_number()?;
+                parse_time_parts(hour, minute)?
+            } else {
+                self.parse_military_time(hour)?
+            };
+            match self.parse_zone_name()? {
+                Some(zone) => Ok(Expr::ZonedDateTime(year, month, day, hour, minute, zone)),
+                None => Ok(Expr::DateTime(year, month, day, hour, minute)),
+            }
+        } else {
+            Ok(Expr::Date(year, month, day))
+        }
+    }
+
+    /// Reads a time zone name after a date and time: `UTC`, or an IANA name
+    /// such as `Europe/Rome` or `America/Argentina/Buenos_Aires`. Whether the
+    /// zone exists is checked when evaluating.
+    fn parse_zone_name(&mut self) -> Result<Option<String>, ParsingError> {
+        let mut ahead = self.tokens.clone();
+        let Some(Token::Ident(area)) = ahead.next() else {
+            return Ok(None);
+        };
+        if area.eq_ignore_ascii_case("utc") {
+            self.tokens.next();
+            return Ok(Some("UTC".to_string()));
+        }
+        if !matches!(
+            (ahead.next(), ahead.next()),
+            (Some(Token::Slash), Some(Token::Ident(_)))
+        ) {
+            return Ok(None);
+        }
+
+        self.tokens.next();
+        let mut name = area;
+        while let Some(Token::Slash) = self.tokens.peek() {
+            self.tokens.next();
+            match self.tokens.next() {
+                Some(Token::Ident(part)) => {
+                    name.push('/');
+                    name.push_str(&part);
+                }
+                _ => return Err(ParsingError::ExpectedIdent),
+            }
+        }
+        Ok(Some(name))
+    }
+
+    /// Parses compact `HHMM` times, optionally followed by an `h` suffix.
+    fn parse_military_time(&mut self, value: i64) -> Result<(u8, u8), ParsingError> {
+        if self.next_is_ident("h") {
+            self.tokens.next();
+        }
+
+        match value {
+            0..=2359 => parse_time_parts(value / 100, value % 100),
+            _ => Err(ParsingError::InvalidTime(value.to_string())),
+        }
+    }
+
+    /// Reads the `am` or `pm` that follows an hour.
+    fn parse_meridiem(&mut self, hour: i64) -> Result<(u8, u8), ParsingError> {
+        let suffix = if self.next_is_ident("pm") { "pm" } else { "am" };
+        self.tokens.next();
+
+        match (hour, suffix) {
+            (1..=11, "am") => Ok((hour as u8, 0)),
+            (12, "am") => Ok((0, 0)),
+            (1..=11, _) => Ok(((hour + HOURS_IN_HALF_DAY) as u8, 0)),
+            (12, _) => Ok((12, 0)),
+            _ => Err(ParsingError::InvalidTime(format!("{hour} {suffix}"))),
+        }
+    }
+
+    /// Parses the time of day that may follow `today`, `tomorrow`,
+    /// `yesterday` or `now`.
+    fn parse_keyword_time(&mut self, keyword: Keyword) -> Result<Expr, ParsingError> {
+        if !matches!(self.tokens.peek(), Some(Token::Number(_))) {
+            return Ok(Expr::Keyword(keyword));
+        }
+
+        let hour = self.expect_number()?;
+        let (hour, minute) = match self.tokens.peek() {
+            Some(Token::Colon) => {
+                self.tokens.next();
+                let minute = self.expect_number()?;
+                parse_time_parts(hour, minute)?
+            }
+            Some(Token::Ident(suffix)) if suffix == "am" || suffix == "pm" => {
+                self.parse_meridiem(hour)?
+            }
+            _ => return Err(ParsingError::ExpectedColon),
+        };
+        Ok(Expr::KeywordTime(keyword, hour, minute))
+    }
+
+    fn parse_time(&mut self, hour: i64) -> Result<Expr, ParsingError> {
+        self.expect_token(Token::Colon, ParsingError::ExpectedColon)?;
+        let minute = self.expect_number()?;
+        let (hour, minute) = parse_time_parts(hour, minute)?;
+        Ok(Expr::Time(hour, minute))
+    }
+
+    fn parse_duration(&mut self, value: i64) -> Result<Expr, ParsingError> {
+        let (amount, unit) = self.expect_unit()?;
+
+        if self.next_is_and_a_half() {
+            for _ in 0..3 {
+                self.tokens.next();
+            }
+            let (whole, unit) = scale(value, (amount, unit))?;
+            return fractional_duration(whole, amount, HALF, unit);
+        }
+
+        // Compound durations such as `2h45m` or `1d 6h` fold into one.
+        let (mut value, mut unit) = scale(value, (amount, unit))?;
+        while unit.nanoseconds().is_some() && matches!(self.tokens.peek(), Some(Token::Number(_))) {
+            let next = self.expect_number()?;
+            let (next, next_unit) = scale(next, self.expect_unit()?)?;
+            (value, unit) = sum_durations((value, &unit), (next, &next_unit)).ok_or_else(|| {
+                ParsingError::InvalidDuration(format!(
+                    "{}{}",
+                    Expr::Duration(value, unit.clone()),
+                    Expr::Duration(next, next_unit)
+                ))
+            })?;
+        }
+
+        Ok(Expr::Duration(value, unit))
+    }
+
+    fn parse_fraction(&mut self, denominator: i64) -> Result<Expr, ParsingError> {
+        if self.next_is_ident("of") {
+            self.tokens.next();
+        }
+        if self.next_is_ident("a") || self.next_is_ident("an") {
+            self.tokens.next();
+        }
+
+        let (amount, unit) = self.expect_unit()?;
+        fractional_duration(0, amount, denominator, unit)
+    }
+
+    /// Whether the tokens after a number continue a `YYYY-MM-DDT...`
+    /// datetime rather than a subtraction.
+    fn next_is_iso_datetime(&self) -> bool {
+        let mut lookahead = self.tokens.clone();
+        matches!(lookahead.next(), Some(Token::Minus))
+            && matches!(lookahead.next(), Some(Token::Number(_)))
+            && matches!(lookahead.next(), Some(Token::Minus))
+            && matches!(lookahead.next(), Some(Token::Number(_)))
+            && matches!(lookahead.next(), Some(Token::Ident(t)) if t.eq_ignore_ascii_case("t"))
+    }
+
+    fn next_is_and_a_half(&self) -> bool {
+        let mut lookahead = self.tokens.clone();
+        let mut next_ident = |expected: &[&str]| matches!(lookahead.next(), Some(Token::Ident(ident)) if expected.contains(&ident.as_str()));
+
+        next_ident(&["and"]) && next_ident(&["a", "an"]) && next_ident(&["half"])
+    }
+
+    /// Reads a unit name, as the amount of a built-in unit it stands for.
+    fn expect_unit(&mut self) -> Result<(i64, Unit), ParsingError> {
+        match self.tokens.next() {
+            Some(Token::Ident(name)) => match self.options.units.get(&name) {
+                Some(unit) => Ok(unit),
+                None => Err(ParsingError::UnknownKeyword(name)),
+            },
+            _ => Err(ParsingError::ExpectedUnit),
+        }
+    }
+
+    fn next_is_ident(&mut self, expected: &str) -> bool {
+        matches!(self.tokens.peek(), Some(Token::Ident(ident)) if ident == expected)
+    }
+
+    /// Whether a month and `day` without a year that follows them are read as
+    /// a day of the current year. An ordinal day always is, as it cannot be a
+    /// year; a bare number only with [`AssumeMissing::Current`] and if it can
+    /// be a day, so `jan 2024` stays a month.
+    fn assumes_year(&self, day: i64, ordinal: bool) -> bool {
+        ordinal
+            || (self.options.assume_missing == AssumeMissing::Current && (1..=31).contains(&day))
+    }
+
+    /// Skips the ordinal suffix after `day`, if there is one, returning
+    /// whether there was. A suffix that does not fit the day, as in `3th`,
+    /// is an error.
+    fn eat_ordinal_suffix(&mut self, day: i64) -> Result<bool, ParsingError> {
+        let suffix = match self.tokens.peek() {
+            Some(Token::Ident(suffix)) if is_ordinal_suffix(suffix) => suffix.clone(),
+            _ => return Ok(false),
+        };
+        self.tokens.next();
+        if suffix != ordinal_suffix(day) {
+            return Err(ParsingError::OrdinalSuffix(day, suffix));
+        }
+        Ok(true)
+    }
+
+    fn parse_year(&self, year: i64) -> Result<i32, ParsingError> {
+        let year = match (year, self.options.two_digit_years) {
+            (0..=99, TwoDigitYears::Strict) => return Err(ParsingError::TwoDigitYear(year)),
+            (0..=99, TwoDigitYears::Pivot(pivot)) if year < i64::from(pivot) => 2000 + year,
+            (0..=99, TwoDigitYears::Pivot(_)) => 1900 + year,
+            _ => year,
+        };
+        checked_year(year)
+    }
+
+    /// Reads a year that may be followed by an era, as in `44 BC` or
+    /// `79 AD`. An explicit era is never a two-digit shorthand. Years before
+    /// the common era count astronomically: 1 BC is year 0.
+    fn parse_era_year(&mut self, year: i64) -> Result<i32, ParsingError> {
+        let era = match self.tokens.peek() {
+            Some(Token::Ident(era)) => era.to_ascii_lowercase(),
+            _ => return self.parse_year(year),
+        };
+        match era.as_str() {
+            "bc" | "bce" if year >= 1 => {
+                self.tokens.next();
+                checked_year(1 - year)
+            }
+            "ad" | "ce" if year >= 1 => {
+                self.tokens.next();
+                checked_year(year)
+            }
+            "bc" | "bce" | "ad" | "ce" => Err(ParsingError::InvalidYear(year)),
+            _ => self.parse_year(year),
+        }
+    }
+
+    fn expect_token(&mut self, expected: Token, err: ParsingError) -> Result<(), ParsingError> {
+        match self.tokens.next() {
+            Some(t) if t == expected => Ok(()),
+            Some(t) => Err(ParsingError::UnexpectedToken(t)),
+            None => Err(err),
+        }
+    }
+
+    fn expect_number(&mut self) -> Result<i64, ParsingError> {
+        match self.tokens.next() {
+            Some(Token::Number(n)) => Ok(n),
+            _ => Err(ParsingError::ExpectedNumber),
+        }
+    }
+}
+
+fn month_from_name(locales: &[Locale], name: &str) -> Option<Month> {
+    locales
+        .iter()
+        .find_map(|locale| locale.month_from_name(name))
+}
+
+fn weekday_from_name(locales: &[Locale], name: &str) -> Option<Weekday> {
+    locales
+        .iter()
+        .find_map(|locale| locale.weekday_from_name(name))
+}
+
+fn is_next_word(locales: &[Locale], word: &str) -> bool {
+    locales
+        .iter()
+        .any(|locale| locale.next_words().contains(&word))
+}
+
+fn is_last_word(locales: &[Locale], word: &str) -> bool {
+    locales
+        .iter()
+        .any(|locale| locale.last_words().contains(&word))
+}
+
+/// Whether `ident` is the suffix of an ordinal day such as `3rd`.
+fn is_ordinal_suffix(ident: &str) -> bool {
+    matches!(ident, "st" | "nd" | "rd" | "th")
+}
+
+/// The suffix English writes after `day`: `1st`, `2nd`, `3rd`, `11th`.
+fn ordinal_suffix(day: i64) -> &'static str {
+    match (day % 10, day % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    }
+}
+
+fn period_from_ident(ident: &str) -> Option<Period> {
+    match ident {
+        "week" => Some(Period::Week),
+        "month" => Some(Period::Month),
+        "year" => Some(Period::Year),
+        _ => None,
+    }
+}
+
+/// Adds two fixed-length durations, expressing the sum in the smaller unit.
+/// Returns `None` if either unit has no fixed length or the sum overflows.
+pub(crate) fn sum_durations(left: (i64, &Unit), right: (i64, &Unit)) -> Option<(i64, Unit)> {
+    let (left_nanos, right_nanos) = (left.1.nanoseconds()?, right.1.nanoseconds()?);
+    let (smaller, nanos) = if left_nanos <= right_nanos {
+        (left.1, left_nanos)
+    } else {
+        (right.1, right_nanos)
+    };
+
+    let left = left.0.checked_mul(left_nanos / nanos)?;
+    let right = right.0.checked_mul(right_nanos / nanos)?;
+    Some((left.checked_add(right)?, smaller.clone()))
+}
+
+/// `count` of a unit name that stands for `amount` of `unit`.
+fn scale(count: i64, (amount, unit): (i64, Unit)) -> Result<(i64, Unit), ParsingError> {
+    match count.checked_mul(amount) {
+        Some(value) => Ok((value, unit)),
+        None => Err(ParsingError::OutOfRange(format!(
+            "{} * {}",
+            count,
+            Expr::Duration(amount, unit)
+        ))),
+    }
+}
+
+/// Expresses `whole + numerator / denominator` units as a whole number of a
+/// smaller unit, stepping down as far as the fraction needs, so fractional
+/// phrases still evaluate to exact durations: `half a week` is 84 hours.
+fn fractional_duration(
+    whole: i64,
+    numerator: i64,
+    denominator: i64,
+    unit: Unit,
+) -> Result<Expr, ParsingError> {
+    let fraction = || format!("{numerator}/{denominator} {unit:?}").to_lowercase();
+
+    if numerator % denominator == 0 {
+        return whole
+            .checked_add(numerator / denominator)
+            .map(|value| Expr::Duration(value, unit.clone()))
+            .ok_or_else(|| ParsingError::InvalidFraction(fraction()));
+    }
+
+    let (factor, smaller): (i64, Unit) = match unit {
+        Unit::Centuries => (100, Unit::Years),
+        Unit::Decades => (10, Unit::Years),
+        Unit::Years => (12, Unit::Months),
+        Unit::Quarters => (3, Unit::Months),
+        Unit::Fortnights => (14, Unit::Days),
+        Unit::Weeks => (7, Unit::Days),
+        Unit::Days => (24, Unit::Hours),
+        Unit::Hours => (60, Unit::Minutes),
+        Unit::Minutes => (60, Unit::Seconds),
+        Unit::Seconds => (1000, Unit::Milliseconds),
+        Unit::Milliseconds => (1000, Unit::Microseconds),
+        Unit::Microseconds => (1000, Unit::Nanoseconds),
+        _ => return Err(ParsingError::InvalidFraction(fraction())),
+    };
+
+    // What is left after the whole smaller units carries on down.
+    let (whole, numerator) = factor
+        .checked_mul(numerator)
+        .and_then(|share| {
+            let whole = whole
+                .checked_mul(factor)?
+                .checked_add(share / denominator)?;
+            Some((whole, share % denominator))
+        })
+        .ok_or_else(|| ParsingError::InvalidFraction(fraction()))?;
+    fractional_duration(whole, numerator, denominator, smaller)
+}
+
+pub(crate) fn checked_year(year: i64) -> Result<i32, ParsingError> {
+    match year {
+        MIN_YEAR..=MAX_YEAR => Ok(year as i32),
+        _ => Err(ParsingError::InvalidYear(year)),
+    }
+}
+
+/// Checks each component of a date, including that the day exists in that
+/// month and year.
+pub(crate) fn date_parts(year: i32, month: i64, day: i64) -> Result<(i32, u8, u8), ParsingError> {
+    let month = parse_month(month)?;
+    let day = parse_day(day)?;
+
+    let length = Month::try_from(month)
+        .map(|month| month.length(year))
+        .unwrap_or(31);
+    if day > length {
+        return Err(ParsingError::DayOutOfMonth(year, month, day));
+    }
+    Ok((year, month, day))
+}
+
+fn parse_month(month: i64) -> Result<u8, ParsingError> {
+    match month {
+        1..=12 => Ok(month as u8),
+        _ => Err(ParsingError::InvalidMonth(month)),
+    }
+}
+
+/// Checks a day against the longest the month can be, in a leap year.
+fn month_day(month: Month, day: i64) -> Result<Expr, ParsingError> {
+    match parse_day(day)? {
+        day if day > month.length(2024) => Err(ParsingError::InvalidDay(day.into())),
+        day => Ok(Expr::MonthDay(u8::from(month), day)),
+    }
+}
+
+fn parse_day(day: i64) -> Result<u8, ParsingError> {
+    match day {
+        1..=31 => Ok(day as u8),
+        _ => Err(ParsingError::InvalidDay(day)),
+    }
+}
+
+pub(crate) fn parse_time_parts(hour: i64, minute: i64) -> Result<(u8, u8), ParsingError> {
+    match (hour, minute) {
+        (0..=23, 0..=59) => Ok((hour as u8, minute as u8)),
+        (0..=23, _) => Err(ParsingError::InvalidMinute(minute)),
+        _ => Err(ParsingError::InvalidHour(hour)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_date() {
+        let lexer = Lexer::new("2023/01/01");
+        let expr = parse(lexer).unwrap();
+        assert_eq!(expr, Expr::Date(2023, 1, 1));
+    }
+
+    #[test]
+    fn test_parse_date_rejects_year_overflow() {
+        let lexer = Lexer::new("999999999999/01/01");
+        assert!(parse(lexer).is_err());
+    }
+
+    #[test]
+    fn test_parse_date_rejects_month_overflow() {
+        let lexer = Lexer::new("2023/257/01");
+        assert!(parse(lexer).is_err());
+    }
+
+    #[test]
+    fn test_parse_date_rejects_day_overflow() {
+        let lexer = Lexer::new("2023/01/257");
+        assert!(parse(lexer).is_err());
+    }
+
+    #[test]
+    fn test_parse_date_rejects_day_past_end_of_month() {
+        let err = parse(Lexer::new("2023/02/29")).unwrap_err();
+        assert_eq!(err.to_string(), "invalid day '29', 2023/02 has 28 days");
+        assert!(parse(Lexer::new("2024/02/29")).is_ok());
+        assert!(parse(Lexer::new("31 april 2024")).is_err());
+    }
+
+    #[test]
+    fn test_parse_time_reports_invalid_component() {
+        let err = parse(Lexer::new("99:30")).unwrap_err();
+        assert_eq!(err.to_string(), "invalid hour '99', expected 0 to 23");
+        let err = parse(Lexer::new("2024/01/01 12:99")).unwrap_err();
+        assert_eq!(err.to_string(), "invalid minute '99', expected 0 to 59");
+        let err = parse(Lexer::new("2024/55/01")).unwrap_err();
+        assert_eq!(err.to_string(), "invalid month '55', expected 1 to 12");
+    }
+
+    #[test]
+    fn test_parse_date_rejects_trailing_ident() {
+        let lexer = Lexer::new("2023/01/01foo");
+        assert!(parse(lexer).is_err());
+    }
+
+    #[test]
+    fn test_parse_time_24h() {
+        let lexer = Lexer::new("14:30");
+        let expr = parse(lexer).unwrap();
+        assert_eq!(expr, Expr::Time(14, 30));
+    }
+
+    #[test]
+    fn test_parse_time_rejects_24h_overflow() {
+        let lexer = Lexer::new("257:00");
+        assert!(parse(lexer).is_err());
+    }
+
+    #[test]
+    fn test_parse_time_rejects_minute_overflow() {
+        let lexer = Lexer::new("14:257");
+        assert!(parse(lexer).is_err());
+    }
+
+    #[test]
+    fn test_parse_time_rejects_trailing_ident() {
+        let lexer = Lexer::new("14:30pm");
+        assert!(parse(lexer).is_err());
+    }
+
+    #[test]
+    fn test_parse_time_am() {
+        let lexer = Lexer::new("2am");
+        let expr = parse(lexer).unwrap();
+        assert_eq!(expr, Expr::Time(2, 0));
+    }
+
+    #[test]
+    fn test_parse_time_pm() {
+        let lexer = Lexer::new("2pm");
+        let expr = parse(lexer).unwrap();
+        assert_eq!(expr, Expr::Time(14, 0));
+    }
+
+    #[test]
+    fn test_parse_time_12am() {
+        let lexer = Lexer::new("12am");
+        let expr = parse(lexer).unwrap();
+        assert_eq!(expr, Expr::Time(0, 0));
+    }
+
+    #[test]
+    fn test_parse_time_12pm() {
+        let lexer = Lexer::new("12pm");
+        let expr = parse(lexer).unwrap();
+        assert_eq!(expr, Expr::Time(12, 0));
+    }
+
+    #[test]
+    fn test_parse_time_invalid_hour_overflow() {
+        let lexer = Lexer::new("34pm");
+        assert!(parse(lexer).is_err());
+    }
+
+    #[test]
+    fn test_parse_time_invalid_hour_zero() {
+        let lexer = Lexer::new("0am");
+        assert!(parse(lexer).is_err());
+    }
+
+    #[test]
+    fn test_parse_duration_hours() {
+        let lexer = Lexer::new("2h");
+        let expr = parse(lexer).unwrap();
+        assert_eq!(expr, Expr::Duration(2, Unit::Hours));
+    }
+
+    #[test]
+    fn test_parse_duration_minutes() {
+        let lexer = Lexer::new("30m");
+        let expr = parse(lexer).unwrap();
+        assert_eq!(expr, Expr::Duration(30, Unit::Minutes));
+    }
+
+    #[test]
+    fn test_parse_duration_working_days() {
+        let lexer = Lexer::new("40wd");
+        let expr = parse(lexer).unwrap();
+        assert_eq!(expr, Expr::Duration(40, Unit::WorkingDays));
+    }
+
+    #[test]
+    fn test_parse_keyword_today() {
+        let lexer = Lexer::new("today");
+        let expr = parse(lexer).unwrap();
+        assert_eq!(expr, Expr::Keyword(Keyword::Today));
+    }
+
+    #[test]
+    fn test_parse_keyword_tomorrow() {
+        let lexer = Lexer::new("tomorrow");
+        let expr = parse(lexer).unwrap();
+        assert_eq!(expr, Expr::Keyword(Keyword::Tomorrow));
+    }
+
+    #[test]
+    fn test_parse_datetime() {
+        let lexer = Lexer::new("2023/01/01 14:30");
+        let expr = parse(lexer).unwrap();
+        assert_eq!(expr, Expr::DateTime(2023, 1, 1, 14, 30));
+    }
+
+    #[test]
+    fn test_parse_addition() {
+        let lexer = Lexer::new("today + 2h");
+        let expr = parse(lexer).unwrap();
+        assert_eq!(
+            expr,
+            Expr::BinOp(
+                Box::new(Expr::Keyword(Keyword::Today)),
+                Op::Add,
+                Box::new(Expr::Duration(2, Unit::Hours))
+            )
+        );
+    }
+
+    #[test]
+    fn test_parse_subtraction() {
+        let lexer = Lexer::new("2am - 30m");
+        let expr = parse(lexer).unwrap();
+        assert_eq!(
+            expr,
+            Expr::BinOp(
+                Box::new(Expr::Time(2, 0)),
+                Op::Sub,
+                Box::new(Expr::Duration(30, Unit::Minutes))
+            )
+        );
+    }
+
+    #[test]
+    fn test_parse_chained_operations() {
+        let lexer = Lexer::new("today - 2h + 30m");
+        let expr = parse(lexer).unwrap();
+        // Should be: ((today - 2h) + 30m)
+        assert_eq!(
+            expr,
+            Expr::BinOp(
+                Box::new(Expr::BinOp(
+                    Box::new(Expr::Keyword(Keyword::Today)),
+                    Op::Sub,
+                    Box::new(Expr::Duration(2, Unit::Hours))
+                )),
+                Op::Add,
+                Box::new(Expr::Duration(30, Unit::Minutes))
+            )
+        );
+    }
+
+    #[test]
+    fn test_parse_duration_addition() {
+        let lexer = Lexer::new("2h + 30m");
+        let expr = parse(lexer).unwrap();
+        assert_eq!(
+            expr,
+            Expr::BinOp(
+                Box::new(Expr::Duration(2, Unit::Hours)),
+                Op::Add,
+                Box::new(Expr::Duration(30, Unit::Minutes))
+            )
+        );
+    }
+
+    #[test]
+    fn test_parse_date_arithmetic() {
+        let lexer = Lexer::new("2023/12/25 + 7d");
+        let expr = parse(lexer).unwrap();
+        assert_eq!(
+            expr,
+            Expr::BinOp(
+                Box::new(Expr::Date(2023, 12, 25)),
+                Op::Add,
+                Box::new(Expr::Duration(7, Unit::Days))
+            )
+        );
+    }
+
+    #[test]
+    fn test_parse_comparison() {
+        let lexer = Lexer::new("today > 2024/06/01");
+        let expr = parse(lexer).unwrap();
+        assert_eq!(
+            expr,
+            Expr::BinOp(
+                Box::new(Expr::Keyword(Keyword::Today)),
+                Op::Greater,
+                Box::new(Expr::Date(2024, 6, 1))
+            )
+        );
+    }
+
+    #[test]
+    fn test_parse_comparison_binds_looser_than_sum() {
+        let lexer = Lexer::new("2h + 30m >= 150m");
+        let expr = parse(lexer).unwrap();
+        assert_eq!(
+            expr,
+            Expr::BinOp(
+                Box::new(Expr::BinOp(
+                    Box::new(Expr::Duration(2, Unit::Hours)),
+                    Op::Add,
+                    Box::new(Expr::Duration(30, Unit::Minutes))
+                )),
+                Op::GreaterEqual,
+                Box::new(Expr::Duration(150, Unit::Minutes))
+            )
+        );
+    }
+
+    #[test]
+    fn test_parse_product_binds_tighter_than_sum() {
+        let lexer = Lexer::new("1s - 350ms * 2");
+        let expr = parse(lexer).unwrap();
+        assert_eq!(
+            expr,
+            Expr::BinOp(
+                Box::new(Expr::Duration(1, Unit::Seconds)),
+                Op::Sub,
+                Box::new(Expr::BinOp(
+                    Box::new(Expr::Duration(350, Unit::Milliseconds)),
+                    Op::Mul,
+                    Box::new(Expr::Number(2))
+                ))
+            )
+        );
+    }
+
+    #[test]
+    fn test_parse_parentheses_override_precedence() {
+        let lexer = Lexer::new("(1s - 350ms) * 2");
+        let expr = parse(lexer).unwrap();
+        assert_eq!(
+            expr,
+            Expr::BinOp(
+                Box::new(Expr::BinOp(
+                    Box::new(Expr::Duration(1, Unit::Seconds)),
+                    Op::Sub,
+                    Box::new(Expr::Duration(350, Unit::Milliseconds))
+                )),
+                Op::Mul,
+                Box::new(Expr::Number(2))
+            )
+        );
+
+        let lexer = Lexer::new("(1h + 2h");
+        assert!(matches!(parse(lexer), Err(ParsingError::ExpectedRParen)));
+    }
+
+    #[test]
+    fn test_parse_rejects_deep_nesting() {
+        let nested = format!("{}1h{}", "(".repeat(1000), ")".repeat(1000));
+        assert!(matches!(
+            parse(Lexer::new(&nested)),
+            Err(ParsingError::TooDeep(256))
+        ));
+
+        let chain = vec!["1h"; 2000].join(" + ");
+        assert!(matches!(
+            parse(Lexer::new(&chain)),
+            Err(ParsingError::TooManyOperators(1024))
+        ));
+
+        let negated = format!("{}1h", "-".repeat(1000));
+        assert!(matches!(
+            parse(Lexer::new(&negated)),
+            Err(ParsingError::TooDeep(256))
+        ));
+
+        let nested = format!("{}1h{}", "(".repeat(100), ")".repeat(100));
+        assert!(parse(Lexer::new(&nested)).is_ok());
     }
 
     #[test]
-    fn test_parse_time_12pm() {
-        let lexer = Lexer::new("12pm");
-        let expr = parse(lexer).unwrap();
-        assert_eq!(expr, Expr::Time(12, 0));
+    fn test_parse_rejects_oversized_input() {
+        let long = format!("1h{}", " ".repeat(64 * 1024));
+        assert!(matches!(
+            parse(Lexer::new(&long)),
+            Err(ParsingError::TooLong(65536))
+        ));
+
+        let many = vec!["1h"; 5000].join(" + ");
+        assert!(matches!(
+            parse(Lexer::new(&many)),
+            Err(ParsingError::TooManyTokens(8192))
+        ));
     }
 
     #[test]
-    fn test_parse_time_invalid_hour_overflow() {
-        let lexer = Lexer::new("34pm");
-        assert!(parse(lexer).is_err());
+    fn test_parse_and_binds_tighter_than_or() {
+        let lexer = Lexer::new("true or false and not false");
+        let expr = parse(lexer).unwrap();
+        assert_eq!(
+            expr,
+            Expr::BinOp(
+                Box::new(Expr::Bool(true)),
+                Op::Or,
+                Box::new(Expr::BinOp(
+                    Box::new(Expr::Bool(false)),
+                    Op::And,
+                    Box::new(Expr::UnaryOp(UnaryOp::Not, Box::new(Expr::Bool(false))))
+                ))
+            )
+        );
     }
 
     #[test]
-    fn test_parse_time_invalid_hour_zero() {
-        let lexer = Lexer::new("0am");
+    fn test_parse_rejects_chained_comparison() {
+        let lexer = Lexer::new("1h < 2h < 3h");
         assert!(parse(lexer).is_err());
     }
 
     #[test]
-    fn test_parse_duration_hours() {
-        let lexer = Lexer::new("2h");
+    fn test_parse_unary_minus() {
+        let lexer = Lexer::new("-2h");
         let expr = parse(lexer).unwrap();
-        assert_eq!(expr, Expr::Duration(2, Unit::Hours));
+        assert_eq!(
+            expr,
+            Expr::UnaryOp(UnaryOp::Neg, Box::new(Expr::Duration(2, Unit::Hours)))
+        );
     }
 
     #[test]
-    fn test_parse_duration_minutes() {
-        let lexer = Lexer::new("30m");
+    fn test_parse_unary_minus_as_right_operand() {
+        let lexer = Lexer::new("today + -3d");
         let expr = parse(lexer).unwrap();
-        assert_eq!(expr, Expr::Duration(30, Unit::Minutes));
+        assert_eq!(
+            expr,
+            Expr::BinOp(
+                Box::new(Expr::Keyword(Keyword::Today)),
+                Op::Add,
+                Box::new(Expr::UnaryOp(
+                    UnaryOp::Neg,
+                    Box::new(Expr::Duration(3, Unit::Days))
+                ))
+            )
+        );
     }
 
     #[test]
-    fn test_parse_duration_working_days() {
-        let lexer = Lexer::new("40wd");
+    fn test_parse_article_as_one() {
+        let lexer = Lexer::new("today + a day - an hour");
         let expr = parse(lexer).unwrap();
-        assert_eq!(expr, Expr::Duration(40, Unit::WorkingDays));
+        assert_eq!(
+            expr,
+            Expr::BinOp(
+                Box::new(Expr::BinOp(
+                    Box::new(Expr::Keyword(Keyword::Today)),
+                    Op::Add,
+                    Box::new(Expr::Duration(1, Unit::Days))
+                )),
+                Op::Sub,
+                Box::new(Expr::Duration(1, Unit::Hours))
+            )
+        );
     }
 
     #[test]
-    fn test_parse_keyword_today() {
-        let lexer = Lexer::new("today");
-        let expr = parse(lexer).unwrap();
-        assert_eq!(expr, Expr::Keyword(Keyword::Today));
+    fn test_parse_article_requires_unit() {
+        let lexer = Lexer::new("a");
+        assert!(parse(lexer).is_err());
     }
 
     #[test]
-    fn test_parse_keyword_tomorrow() {
-        let lexer = Lexer::new("tomorrow");
+    fn test_parse_half_an_hour() {
+        let lexer = Lexer::new("half an hour");
         let expr = parse(lexer).unwrap();
-        assert_eq!(expr, Expr::Keyword(Keyword::Tomorrow));
+        assert_eq!(expr, Expr::Duration(30, Unit::Minutes));
     }
 
     #[test]
-    fn test_parse_datetime() {
-        let lexer = Lexer::new("2023/01/01 14:30");
+    fn test_parse_quarter_of_an_hour() {
+        let lexer = Lexer::new("quarter of an hour");
         let expr = parse(lexer).unwrap();
-        assert_eq!(expr, Expr::DateTime(2023, 1, 1, 14, 30));
+        assert_eq!(expr, Expr::Duration(15, Unit::Minutes));
     }
 
     #[test]
-    fn test_parse_addition() {
-        let lexer = Lexer::new("today + 2h");
+    fn test_parse_a_day_and_a_half() {
+        let lexer = Lexer::new("today + a day and a half");
         let expr = parse(lexer).unwrap();
         assert_eq!(
             expr,
             Expr::BinOp(
                 Box::new(Expr::Keyword(Keyword::Today)),
                 Op::Add,
-                Box::new(Expr::Duration(2, Unit::Hours))
+                Box::new(Expr::Duration(36, Unit::Hours))
             )
         );
     }
 
     #[test]
-    fn test_parse_subtraction() {
-        let lexer = Lexer::new("2am - 30m");
+    fn test_parse_a_week_and_a_half() {
+        let lexer = Lexer::new("a week and a half");
+        assert_eq!(parse(lexer).unwrap(), Expr::Duration(252, Unit::Hours));
+    }
+
+    #[test]
+    fn test_parse_half_a_week_steps_down_to_hours() {
+        let lexer = Lexer::new("half a week");
+        assert_eq!(parse(lexer).unwrap(), Expr::Duration(84, Unit::Hours));
+    }
+
+    #[test]
+    fn test_parse_duration_and_condition_is_not_a_fraction() {
+        let lexer = Lexer::new("1h < 2h and true");
         let expr = parse(lexer).unwrap();
+        assert!(matches!(expr, Expr::BinOp(_, Op::And, _)));
+    }
+
+    #[test]
+    fn test_parse_half_rejects_inexact_units() {
+        let lexer = Lexer::new("half a month");
+        assert!(parse(lexer).is_err());
+    }
+
+    #[test]
+    fn test_parse_day_part_keywords() {
+        let lexer = Lexer::new("this morning");
+        assert_eq!(parse(lexer).unwrap(), Expr::Keyword(Keyword::Morning));
+
+        let lexer = Lexer::new("tonight");
+        assert_eq!(parse(lexer).unwrap(), Expr::Keyword(Keyword::Tonight));
+    }
+
+    #[test]
+    fn test_parse_this_rejects_unknown_day_part() {
+        let lexer = Lexer::new("this noon");
+        assert!(parse(lexer).is_err());
+    }
+
+    #[test]
+    fn test_parse_relative_anchors() {
+        let lexer = Lexer::new("next week");
         assert_eq!(
-            expr,
-            Expr::BinOp(
-                Box::new(Expr::Time(2, 0)),
-                Op::Sub,
-                Box::new(Expr::Duration(30, Unit::Minutes))
-            )
+            parse(lexer).unwrap(),
+            Expr::Keyword(Keyword::Next(Period::Week))
+        );
+
+        let lexer = Lexer::new("last month");
+        assert_eq!(
+            parse(lexer).unwrap(),
+            Expr::Keyword(Keyword::Last(Period::Month))
+        );
+
+        let lexer = Lexer::new("this year");
+        assert_eq!(
+            parse(lexer).unwrap(),
+            Expr::Keyword(Keyword::This(Period::Year))
         );
     }
 
     #[test]
-    fn test_parse_chained_operations() {
-        let lexer = Lexer::new("today - 2h + 30m");
+    fn test_parse_duration_weeks() {
+        let lexer = Lexer::new("1 week");
         let expr = parse(lexer).unwrap();
-        // Should be: ((today - 2h) + 30m)
+        assert_eq!(expr, Expr::Duration(1, Unit::Weeks));
+    }
+
+    #[test]
+    fn test_parse_military_time_with_prefix() {
+        let lexer = Lexer::new("t1430");
+        let expr = parse(lexer).unwrap();
+        assert_eq!(expr, Expr::Time(14, 30));
+    }
+
+    #[test]
+    fn test_parse_datetime_military_time() {
+        let lexer = Lexer::new("2023/01/01 0930h");
+        let expr = parse(lexer).unwrap();
+        assert_eq!(expr, Expr::DateTime(2023, 1, 1, 9, 30));
+
+        let lexer = Lexer::new("2023/01/01 1430");
+        let expr = parse(lexer).unwrap();
+        assert_eq!(expr, Expr::DateTime(2023, 1, 1, 14, 30));
+    }
+
+    #[test]
+    fn test_parse_military_time_rejects_invalid_minutes() {
+        let lexer = Lexer::new("t1275");
+        assert!(parse(lexer).is_err());
+
+        let lexer = Lexer::new("t2400");
+        assert!(parse(lexer).is_err());
+    }
+
+    #[cfg(feature = "locales")]
+    fn parse_in(input: &str, locale: Locale) -> Result<Expr, ParsingError> {
+        let options = Options {
+            locale: Some(locale),
+            ..Options::default()
+        };
+        parse_with_options(Lexer::new(input), &options)
+    }
+
+    #[test]
+    #[cfg(feature = "locales")]
+    fn test_parse_localized_month_name() {
+        let expr = parse_in("5 gennaio 2024", Locale::ItIt).unwrap();
+        assert_eq!(expr, Expr::Date(2024, 1, 5));
+    }
+
+    #[test]
+    #[cfg(feature = "locales")]
+    fn test_parse_localized_month_requires_locale() {
+        let lexer = Lexer::new("5 gennaio 2024");
+        assert!(parse(lexer).is_err());
+    }
+
+    #[test]
+    fn test_parse_english_month_first_date() {
+        let lexer = Lexer::new("January 5 2024");
+        assert_eq!(parse(lexer).unwrap(), Expr::Date(2024, 1, 5));
+    }
+
+    #[test]
+    fn test_parse_t_separated_datetime() {
+        for input in ["2024/05/01T14:30", "2024-05-01T14:30", "2024-05-01t1430"] {
+            assert_eq!(
+                parse(Lexer::new(input)).unwrap(),
+                Expr::DateTime(2024, 5, 1, 14, 30)
+            );
+        }
+        let lexer = Lexer::new("2024-05-01");
+        assert!(matches!(parse(lexer), Ok(Expr::BinOp(..))));
+    }
+
+    #[test]
+    fn test_parse_keyword_with_time() {
+        let lexer = Lexer::new("yesterday 17:00");
+        assert_eq!(
+            parse(lexer).unwrap(),
+            Expr::KeywordTime(Keyword::Yesterday, 17, 0)
+        );
+        let lexer = Lexer::new("today 9am");
+        assert_eq!(
+            parse(lexer).unwrap(),
+            Expr::KeywordTime(Keyword::Today, 9, 0)
+        );
+        let lexer = Lexer::new("today 3d");
+        assert!(parse(lexer).is_err());
+    }
+
+    #[test]
+    fn test_parse_ordinal_day() {
+        for input in [
+            "november 3rd 2024",
+            "3rd of november 2024",
+            "3rd November 2024",
+        ] {
+            assert_eq!(parse(Lexer::new(input)).unwrap(), Expr::Date(2024, 11, 3));
+        }
+        for (input, day) in [
+            ("november 21st", 21),
+            ("3rd of november", 3),
+            ("12th november", 12),
+        ] {
+            assert_eq!(parse(Lexer::new(input)).unwrap(), Expr::MonthDay(11, day));
+        }
+        for input in ["november 1nd 2024", "3th of november", "11st november"] {
+            let err = parse(Lexer::new(input)).unwrap_err();
+            assert!(matches!(err, ParsingError::OrdinalSuffix(..)), "{input}");
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "locales")]
+    fn test_parse_localized_next_weekday() {
+        let expr = parse_in("nächsten Montag", Locale::DeDe).unwrap();
+        assert_eq!(expr, Expr::Keyword(Keyword::NextWeekday(Weekday::Monday)));
+
+        let expr = parse_in("lunedì scorso", Locale::ItIt).unwrap();
+        assert_eq!(expr, Expr::Keyword(Keyword::LastWeekday(Weekday::Monday)));
+    }
+
+    #[test]
+    fn test_parse_next_weekday_english() {
+        let lexer = Lexer::new("next friday");
+        assert_eq!(
+            parse(lexer).unwrap(),
+            Expr::Keyword(Keyword::NextWeekday(Weekday::Friday))
+        );
+    }
+
+    #[test]
+    fn test_parse_date_order() {
+        let parse_as = |order| {
+            let options = Options {
+                date_order: order,
+                ..Options::default()
+            };
+            parse_with_options(Lexer::new("03/04/2024"), &options)
+        };
+
+        assert_eq!(parse_as(DateOrder::Dmy).unwrap(), Expr::Date(2024, 4, 3));
+        assert_eq!(parse_as(DateOrder::Mdy).unwrap(), Expr::Date(2024, 3, 4));
+        assert!(parse_as(DateOrder::Ymd).is_err());
+    }
+
+    #[test]
+    fn test_parse_readings_of_ambiguous_dates() {
+        let readings = parse_readings(Lexer::new("03/04/2024 + 1d"), &Options::default());
+        let exprs = readings
+            .iter()
+            .map(|reading| (reading.date_order, reading.expr.to_string()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            exprs,
+            [
+                (DateOrder::Dmy, "2024/04/03 + 1d".to_string()),
+                (DateOrder::Mdy, "2024/03/04 + 1d".to_string()),
+            ]
+        );
+
+        assert_eq!(
+            parse_readings(Lexer::new("05/05/2024"), &Options::default()).len(),
+            1
+        );
+        assert!(parse_readings(Lexer::new("2024/13/01"), &Options::default()).is_empty());
+    }
+
+    #[test]
+    fn test_parse_month_day_when_assuming_year() {
+        let options = Options {
+            assume_missing: AssumeMissing::Current,
+            ..Options::default()
+        };
+        let parse_as = |input| parse_with_options(Lexer::new(input), &options);
+
+        assert_eq!(parse_as("jan 5").unwrap(), Expr::MonthDay(1, 5));
+        assert_eq!(parse_as("5th of march").unwrap(), Expr::MonthDay(3, 5));
+        assert_eq!(parse_as("29 feb").unwrap(), Expr::MonthDay(2, 29));
+        assert_eq!(parse_as("jan 2024").unwrap(), Expr::YearMonth(2024, 1));
+        assert_eq!(parse_as("jan 5 2024").unwrap(), Expr::Date(2024, 1, 5));
+        assert!(parse_as("feb 30th").is_err());
+        assert!(parse(Lexer::new("5 jan")).is_err());
+    }
+
+    #[test]
+    fn test_parse_date_order_keeps_year_first_dates() {
+        let options = Options {
+            date_order: DateOrder::Dmy,
+            ..Options::default()
+        };
+        let expr = parse_with_options(Lexer::new("2024/03/04"), &options).unwrap();
+        assert_eq!(expr, Expr::Date(2024, 3, 4));
+    }
+
+    #[test]
+    fn test_parse_two_digit_year_is_strict_by_default() {
+        let lexer = Lexer::new("24/3/5");
+        assert!(matches!(parse(lexer), Err(ParsingError::TwoDigitYear(24))));
+    }
+
+    #[test]
+    fn test_parse_two_digit_year_with_pivot() {
+        let options = Options {
+            two_digit_years: TwoDigitYears::Pivot(70),
+            ..Options::default()
+        };
+        let parse_pivot = |input| parse_with_options(Lexer::new(input), &options).unwrap();
+
+        assert_eq!(parse_pivot("24/3/5"), Expr::Date(2024, 3, 5));
+        assert_eq!(parse_pivot("69/3/5"), Expr::Date(2069, 3, 5));
+        assert_eq!(parse_pivot("70/3/5"), Expr::Date(1970, 3, 5));
+    }
+
+    #[test]
+    fn test_parse_between() {
+        let expr = parse(Lexer::new("between 2024/01/01 and today + 1d")).unwrap();
         assert_eq!(
             expr,
+            Expr::Call(
+                "between".to_string(),
+                vec![
+                    Expr::Date(2024, 1, 1),
+                    Expr::BinOp(
+                        Box::new(Expr::Keyword(Keyword::Today)),
+                        Op::Add,
+                        Box::new(Expr::Duration(1, Unit::Days)),
+                    ),
+                ],
+            )
+        );
+
+        let expr = parse(Lexer::new("(between 10:00 and 12:00) > 1h and true")).unwrap();
+        assert!(matches!(expr, Expr::BinOp(_, Op::And, _)));
+        assert!(parse(Lexer::new("between today")).is_err());
+    }
+
+    #[test]
+    fn test_parse_era_years() {
+        let lexer = Lexer::new("15 march 44 BC");
+        assert_eq!(parse(lexer).unwrap(), Expr::Date(-43, 3, 15));
+
+        let lexer = Lexer::new("august 79 ad");
+        assert_eq!(parse(lexer).unwrap(), Expr::YearMonth(79, 8));
+
+        let lexer = Lexer::new("1 january 0 bc");
+        assert!(matches!(parse(lexer), Err(ParsingError::InvalidYear(0))));
+    }
+
+    #[test]
+    fn test_parse_year_out_of_range() {
+        let err = parse(Lexer::new("10000/01/01")).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid year '10000', expected -9999 to 9999"
+        );
+
+        let lexer = Lexer::new("1 january 10001 bc");
+        assert!(matches!(
+            parse(lexer),
+            Err(ParsingError::InvalidYear(-10000))
+        ));
+    }
+
+    #[test]
+    fn test_parse_year_month() {
+        let lexer = Lexer::new("2024/03");
+        assert_eq!(parse(lexer).unwrap(), Expr::YearMonth(2024, 3));
+
+        let lexer = Lexer::new("jan 2024");
+        assert_eq!(parse(lexer).unwrap(), Expr::YearMonth(2024, 1));
+    }
+
+    #[test]
+    fn test_parse_year_month_follows_date_order() {
+        let options = Options {
+            date_order: DateOrder::Mdy,
+            ..Options::default()
+        };
+        let expr = parse_with_options(Lexer::new("03/2024"), &options).unwrap();
+        assert_eq!(expr, Expr::YearMonth(2024, 3));
+    }
+
+    #[test]
+    fn test_parse_call() {
+        let lexer = Lexer::new("days_in(2024/02) + 1d");
+        assert_eq!(
+            parse(lexer).unwrap(),
             Expr::BinOp(
-                Box::new(Expr::BinOp(
-                    Box::new(Expr::Keyword(Keyword::Today)),
-                    Op::Sub,
-                    Box::new(Expr::Duration(2, Unit::Hours))
+                Box::new(Expr::Call(
+                    "days_in".to_string(),
+                    vec![Expr::YearMonth(2024, 2)]
                 )),
                 Op::Add,
-                Box::new(Expr::Duration(30, Unit::Minutes))
+                Box::new(Expr::Duration(1, Unit::Days))
             )
         );
     }
 
     #[test]
-    fn test_parse_duration_addition() {
-        let lexer = Lexer::new("2h + 30m");
-        let expr = parse(lexer).unwrap();
+    fn test_parse_string() {
+        let lexer = Lexer::new("format(today, \"%d.%m.\")");
         assert_eq!(
-            expr,
-            Expr::BinOp(
-                Box::new(Expr::Duration(2, Unit::Hours)),
-                Op::Add,
-                Box::new(Expr::Duration(30, Unit::Minutes))
+            parse(lexer).unwrap(),
+            Expr::Call(
+                "format".to_string(),
+                vec![
+                    Expr::Keyword(Keyword::Today),
+                    Expr::Str("%d.%m.".to_string())
+                ]
             )
         );
     }
 
     #[test]
-    fn test_parse_date_arithmetic() {
-        let lexer = Lexer::new("2023/12/25 + 7d");
-        let expr = parse(lexer).unwrap();
+    fn test_parse_pipeline() {
+        let lexer = Lexer::new("2024/01/01 + 90d |> year");
         assert_eq!(
-            expr,
+            parse(lexer).unwrap(),
+            Expr::Call(
+                "year".to_string(),
+                vec![Expr::BinOp(
+                    Box::new(Expr::Date(2024, 1, 1)),
+                    Op::Add,
+                    Box::new(Expr::Duration(90, Unit::Days))
+                )]
+            )
+        );
+
+        let lexer = Lexer::new("2024/01/01 |> between(2024/03/01) |> negate");
+        assert_eq!(
+            parse(lexer).unwrap(),
+            Expr::Call(
+                "negate".to_string(),
+                vec![Expr::Call(
+                    "between".to_string(),
+                    vec![Expr::Date(2024, 1, 1), Expr::Date(2024, 3, 1)]
+                )]
+            )
+        );
+
+        assert!(parse(Lexer::new("1h |>")).is_err());
+        assert!(parse(Lexer::new("1h |> 2h")).is_err());
+    }
+
+    #[test]
+    fn test_parse_call_rejects_unclosed_arguments() {
+        let lexer = Lexer::new("year(2024");
+        assert!(parse(lexer).is_err());
+    }
+
+    #[test]
+    fn test_parse_reports_lex_error() {
+        let lexer = Lexer::new("today + 2d $ 1h");
+        assert_eq!(
+            parse(lexer).unwrap_err().to_string(),
+            "unexpected character '$' at 1:12"
+        );
+    }
+
+    #[test]
+    fn test_parse_compound_duration() {
+        let lexer = Lexer::new("today + 2h45m");
+        assert_eq!(
+            parse(lexer).unwrap(),
             Expr::BinOp(
-                Box::new(Expr::Date(2023, 12, 25)),
+                Box::new(Expr::Keyword(Keyword::Today)),
                 Op::Add,
-                Box::new(Expr::Duration(7, Unit::Days))
+                Box::new(Expr::Duration(165, Unit::Minutes))
             )
         );
+
+        let lexer = Lexer::new("1d 2 months");
+        assert!(matches!(
+            parse(lexer),
+            Err(ParsingError::InvalidDuration(_))
+        ));
     }
 }
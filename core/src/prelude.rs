@@ -0,0 +1,35 @@
+//! The names most programs need, in one import:
+//!
+//! ```
+//! use tcalc_core::prelude::*;
+//!
+//! let options = Options::default();
+//! let compiled = compile_with_options("2024/03/01 + 3wd", &options).unwrap();
+//! assert_eq!(compiled.run(&options).unwrap(), "2024-03-06");
+//!
+//! let evaluation = evaluate("2024/01/31 + 1 month", &options).unwrap();
+//! assert_eq!(evaluation.warnings.len(), 1);
+//!
+//! let mut context = Context::new(&options, None);
+//! let days = call("days_in", &[Value::Year(2024)], &mut context).unwrap();
+//! assert_eq!(days.display(&options).to_string(), "366d");
+//! assert!(matches!(
+//!     call("days_in", &[], &mut context),
+//!     Err(EvalError::Arguments(..))
+//! ));
+//! ```
+//!
+//! These are the stable surface of the crate: they only change in a
+//! breaking way with a major version. The lexer, parser and syntax tree
+//! types at the crate root serve tooling built on tcalc's internals, and may
+//! change as the language grows.
+
+#[cfg(feature = "std")]
+pub use crate::Session;
+#[cfg(feature = "config")]
+pub use crate::options_from_toml;
+pub use crate::{
+    Calendar, CompiledExpr, Context, ConvertError, EvalError, Evaluation, NegativeDuration,
+    Options, ParsingError, Template, Value, Warning, Zone, call, compile, compile_template,
+    compile_with_options, evaluate, run, run_with_options,
+};
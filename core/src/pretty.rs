@@ -0,0 +1,369 @@
+//! Renders an [`Expr`] back into canonical expression syntax.
+
+use alloc::{string::ToString, vec::Vec};
+use core::fmt;
+
+use time::{Month, Weekday};
+
+use crate::parser::{Expr, Keyword, Op, Period, Recurrence, SprintEdge, UnaryOp, Unit};
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Expr::Number(n) => write!(f, "{}", n),
+            Expr::Date(year, month, day) => write_date(f, *year, *month, Some(*day)),
+            Expr::YearMonth(year, month) => write_date(f, *year, *month, None),
+            Expr::MonthDay(month, day) => {
+                let name =
+                    Month::try_from(*month).map_or_else(|_| month.to_string(), |m| m.to_string());
+                write!(f, "{} {}", day, name.to_lowercase())
+            }
+            Expr::Time(hour, minute) => write!(f, "{:02}:{:02}", hour, minute),
+            Expr::DateTime(year, month, day, hour, minute) => {
+                write_date(f, *year, *month, Some(*day))?;
+                write!(f, " {:02}:{:02}", hour, minute)
+            }
+            Expr::ZonedDateTime(year, month, day, hour, minute, zone) => {
+                write_date(f, *year, *month, Some(*day))?;
+                write!(f, " {:02}:{:02} {}", hour, minute, zone)
+            }
+            Expr::Keyword(keyword) => write_keyword(f, keyword),
+            Expr::KeywordTime(keyword, hour, minute) => {
+                write_keyword(f, keyword)?;
+                write!(f, " {:02}:{:02}", hour, minute)
+            }
+            Expr::Payday(true) => write!(f, "next payday"),
+            Expr::Payday(false) => write!(f, "last payday"),
+            Expr::Sprint(SprintEdge::Start, Some(number)) => write!(f, "sprint {}", number),
+            Expr::Sprint(edge, number) => {
+                match edge {
+                    SprintEdge::Start => write!(f, "start of sprint")?,
+                    SprintEdge::End => write!(f, "end of sprint")?,
+                }
+                match number {
+                    Some(number) => write!(f, " {}", number),
+                    None => Ok(()),
+                }
+            }
+            Expr::Duration(value, unit) => write_duration(f, *value, unit),
+            Expr::Percent(percent) => write!(f, "{}%", percent),
+            Expr::Recurrence(recurrence) => write!(f, "every {}", recurrence),
+            Expr::Bool(value) => write!(f, "{}", value),
+            Expr::History(number) => write!(f, "%{}", number),
+            Expr::Ans => write!(f, "ans"),
+            Expr::Placeholder(name) => write!(f, "{{{}}}", name),
+            Expr::Str(text) => write!(f, "\"{}\"", text),
+            Expr::UnaryOp(op, operand) => {
+                match op {
+                    UnaryOp::Not => write!(f, "not ")?,
+                    UnaryOp::Neg => write!(f, "-")?,
+                }
+                write_operand(f, operand, op.precedence(), false)
+            }
+            Expr::BinOp(..) => write_chain(f, self),
+            Expr::Call(name, args) if name == "sum" => match args.as_slice() {
+                [amount, recurrence @ Expr::Recurrence(_), from, to] => {
+                    write!(f, "sum({} {} from {} to {})", amount, recurrence, from, to)
+                }
+                _ => write_call(f, name, args),
+            },
+            Expr::Call(name, args) => write_call(f, name, args),
+        }
+    }
+}
+
+impl fmt::Display for Recurrence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Recurrence::Day => write!(f, "day"),
+            Recurrence::Weekday => write!(f, "weekday"),
+            Recurrence::WorkingDay => write!(f, "working day"),
+            Recurrence::On(weekday) => write!(f, "{}", weekday_name(*weekday)),
+            Recurrence::Payday => write!(f, "payday"),
+        }
+    }
+}
+
+fn write_call(f: &mut fmt::Formatter, name: &str, args: &[Expr]) -> fmt::Result {
+    write!(f, "{}(", name)?;
+    for (index, arg) in args.iter().enumerate() {
+        if index > 0 {
+            write!(f, ", ")?;
+        }
+        write!(f, "{}", arg)?;
+    }
+    write!(f, ")")
+}
+
+/// Writes a date, or a month when `day` is `None`. Years below 100 would
+/// read back as two-digit shorthand, so they name the month and the era
+/// instead (`15 march 44 bc`).
+fn write_date(f: &mut fmt::Formatter, year: i32, month: u8, day: Option<u8>) -> fmt::Result {
+    if year >= 100 {
+        write!(f, "{:04}/{:02}", year, month)?;
+        return match day {
+            Some(day) => write!(f, "/{:02}", day),
+            None => Ok(()),
+        };
+    }
+
+    if let Some(day) = day {
+        write!(f, "{} ", day)?;
+    }
+    let name = Month::try_from(month).map_or_else(|_| month.to_string(), |m| m.to_string());
+    match year {
+        ..=0 => write!(f, "{} {} bc", name.to_lowercase(), 1 - year),
+        _ => write!(f, "{} {} ad", name.to_lowercase(), year),
+    }
+}
+
+/// Whether `expr` may print ending in a bare number, which a `/` after it
+/// would read on into a date: `(8) / 2` is a division, `8 / 2` is a date.
+fn ends_in_number(expr: &Expr) -> bool {
+    match expr {
+        Expr::Number(_) | Expr::YearMonth(..) => true,
+        Expr::UnaryOp(_, operand) | Expr::BinOp(_, _, operand) => ends_in_number(operand),
+        _ => false,
+    }
+}
+
+/// Writes a binary operator and the ones down its left side that print
+/// without parentheses, as in `1h + 2h + 3h`, in a loop, so a long chain
+/// takes no more stack than one operator.
+fn write_chain(f: &mut fmt::Formatter, expr: &Expr) -> fmt::Result {
+    let mut links = Vec::new();
+    let mut first = expr;
+    while let Expr::BinOp(left, op, right) = first {
+        links.push((op, right));
+        first = left;
+        if parenthesized(left, op.precedence(), op.is_comparison())
+            || (*op == Op::Div && ends_in_number(left))
+        {
+            break;
+        }
+    }
+
+    match links.last() {
+        Some((Op::Div, _)) if ends_in_number(first) => write!(f, "({})", first)?,
+        Some((op, _)) => write_operand(f, first, op.precedence(), op.is_comparison())?,
+        None => write!(f, "{}", first)?,
+    }
+    for (op, right) in links.iter().rev() {
+        write!(f, " {} ", op)?;
+        write_operand(f, right, op.precedence(), true)?;
+    }
+    Ok(())
+}
+
+/// Writes an operand of an operator with the given precedence, in
+/// parentheses when it would otherwise bind differently.
+fn write_operand(
+    f: &mut fmt::Formatter,
+    operand: &Expr,
+    precedence: u8,
+    strict: bool,
+) -> fmt::Result {
+    if parenthesized(operand, precedence, strict) {
+        write!(f, "({})", operand)
+    } else {
+        write!(f, "{}", operand)
+    }
+}
+
+/// Whether an operand of an operator with the given precedence needs
+/// parentheses. `strict` also parenthesizes operands of equal precedence,
+/// as needed on the right of a left-associative operator.
+fn parenthesized(operand: &Expr, precedence: u8, strict: bool) -> bool {
+    let inner = match operand {
+        Expr::BinOp(_, op, _) => op.precedence(),
+        Expr::UnaryOp(op, _) => op.precedence(),
+        _ => u8::MAX,
+    };
+    inner < precedence || (strict && inner == precedence)
+}
+
+fn write_keyword(f: &mut fmt::Formatter, keyword: &Keyword) -> fmt::Result {
+    match keyword {
+        Keyword::Today => write!(f, "today"),
+        Keyword::Now => write!(f, "now"),
+        Keyword::Tomorrow => write!(f, "tomorrow"),
+        Keyword::Yesterday => write!(f, "yesterday"),
+        Keyword::Morning => write!(f, "this morning"),
+        Keyword::Afternoon => write!(f, "this afternoon"),
+        Keyword::Evening => write!(f, "this evening"),
+        Keyword::Tonight => write!(f, "tonight"),
+        Keyword::EndOfDay => write!(f, "end of day"),
+        Keyword::This(period) => write!(f, "this {}", period_name(period)),
+        Keyword::Next(period) => write!(f, "next {}", period_name(period)),
+        Keyword::Last(period) => write!(f, "last {}", period_name(period)),
+        Keyword::NextWeekday(weekday) => write!(f, "next {}", weekday_name(*weekday)),
+        Keyword::LastWeekday(weekday) => write!(f, "last {}", weekday_name(*weekday)),
+    }
+}
+
+/// Durations use the short unit suffix, except months which have none. Days,
+/// hours, minutes, seconds and their fractions are split into compound form
+/// (`1d12h`, `1s250ms`).
+fn write_duration(f: &mut fmt::Formatter, value: i64, unit: &Unit) -> fmt::Result {
+    if let Some(nanos) = unit
+        .nanoseconds()
+        .filter(|_| !matches!(unit, Unit::Weeks | Unit::Fortnights) && value != 0)
+    {
+        if value < 0 {
+            write!(f, "-")?;
+        }
+        let mut remaining = value.unsigned_abs() as u128 * nanos as u128;
+        for (suffix, length) in [
+            ("d", 86_400_000_000_000),
+            ("h", 3_600_000_000_000),
+            ("m", 60_000_000_000),
+            ("s", 1_000_000_000),
+            ("ms", 1_000_000),
+            ("us", 1_000),
+            ("ns", 1),
+        ] {
+            if remaining >= length {
+                write!(f, "{}{}", remaining / length, suffix)?;
+                remaining %= length;
+            }
+        }
+        return Ok(());
+    }
+
+    match unit {
+        Unit::Centuries if value == 1 => write!(f, "1 century"),
+        Unit::Centuries => write!(f, "{} centuries", value),
+        Unit::Decades if value == 1 => write!(f, "1 decade"),
+        Unit::Decades => write!(f, "{} decades", value),
+        Unit::Years => write!(f, "{}y", value),
+        Unit::Quarters if value == 1 => write!(f, "1 quarter"),
+        Unit::Quarters => write!(f, "{} quarters", value),
+        Unit::Months if value == 1 => write!(f, "1 month"),
+        Unit::Months => write!(f, "{} months", value),
+        Unit::Fortnights if value == 1 => write!(f, "1 fortnight"),
+        Unit::Fortnights => write!(f, "{} fortnights", value),
+        Unit::Weeks => write!(f, "{}w", value),
+        Unit::Days => write!(f, "{}d", value),
+        Unit::WorkingDays => write!(f, "{}wd", value),
+        Unit::Weekdays => write!(f, "{} weekdays", value),
+        Unit::Hours => write!(f, "{}h", value),
+        Unit::Minutes => write!(f, "{}m", value),
+        Unit::Seconds => write!(f, "{}s", value),
+        Unit::Milliseconds => write!(f, "{}ms", value),
+        Unit::Microseconds => write!(f, "{}us", value),
+        Unit::Nanoseconds => write!(f, "{}ns", value),
+    }
+}
+
+fn period_name(period: &Period) -> &'static str {
+    match period {
+        Period::Week => "week",
+        Period::Month => "month",
+        Period::Year => "year",
+    }
+}
+
+fn weekday_name(weekday: Weekday) -> &'static str {
+    match weekday {
+        Weekday::Monday => "monday",
+        Weekday::Tuesday => "tuesday",
+        Weekday::Wednesday => "wednesday",
+        Weekday::Thursday => "thursday",
+        Weekday::Friday => "friday",
+        Weekday::Saturday => "saturday",
+        Weekday::Sunday => "sunday",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::lexer::Lexer;
+    use crate::parser::{Expr, Op, parse};
+
+    fn canonical(input: &str) -> String {
+        parse(Lexer::new(input)).unwrap().to_string()
+    }
+
+    #[test]
+    fn test_normalizes_spacing_and_units() {
+        assert_eq!(canonical("today+2 hours -30minutes"), "today + 2h - 30m");
+        assert_eq!(canonical("2024/3/7   9:05"), "2024/03/07 09:05");
+        assert_eq!(canonical("a day and a half"), "1d12h");
+        assert_eq!(canonical("90 minutes"), "1h30m");
+        assert_eq!(canonical("1500 ms"), "1s500ms");
+        assert_eq!(
+            canonical("((today - 1d)) - (2h - 30m)"),
+            "today - 1d - (2h - 30m)"
+        );
+        assert_eq!(canonical("250ms*4"), "250ms * 4");
+    }
+
+    #[test]
+    fn test_names_era_of_early_years() {
+        assert_eq!(canonical("15 March 44 BC"), "15 march 44 bc");
+        assert_eq!(canonical("3 aug 79 ce 9:00"), "3 august 79 ad 09:00");
+        assert_eq!(canonical("1 jan 100 ad"), "0100/01/01");
+    }
+
+    #[test]
+    fn test_keywords_and_calls() {
+        assert_eq!(canonical("next Friday"), "next friday");
+        assert_eq!(
+            canonical("not true or 5 January 2024 > this month"),
+            "not true or 2024/01/05 > this month"
+        );
+        assert_eq!(
+            canonical("days_in( jan 2024 )+1 month"),
+            "days_in(2024/01) + 1 month"
+        );
+    }
+
+    #[test]
+    fn test_output_parses_to_same_expression() {
+        for input in [
+            "-2h + 3 months",
+            "tonight - 1wd",
+            "end of day - 2h",
+            "t1430",
+            "yesterday 5pm",
+            "2024/01/05 > 2024/01/04 and true",
+            "(today - 1d) - (2h - 30m)",
+            "not (true or false) and true",
+            "-(1h + 2h) * 3",
+            "(1h < 2h) == true",
+            "15 march 44 bc",
+            "3 august 79 ad 12:30",
+            "1 january 1 bc",
+            "2024/03/30 12:00 Europe/Rome + 1d",
+            "between today and 2024/12/25 - 1d",
+            "%1 % %2 + ans",
+            "25% of (8h + 2h) - 10% * 1h",
+            "90m / (8h / 2) * 3",
+            "{start} + {len} * 2 > {deadline_2}",
+            "next friday + 2 weekdays - 1wd",
+            "next payday - last payday + paydays(2025) * 1d",
+            "sum(8h every payday from today to 2025/12/31)",
+            "end of sprint - start of sprint 3 + (sprint 14 - end of sprint 2)",
+            "sum(2h every weekday from 2025/01/06 to 2025/01/31)",
+            "sum(1h, 30m) + sum(1h every working day from today to today + 1 month)",
+        ] {
+            let expr = parse(Lexer::new(input)).unwrap();
+            assert_eq!(parse(Lexer::new(&expr.to_string())).unwrap(), expr);
+        }
+    }
+
+    #[test]
+    fn test_parenthesizes_numbers_before_division() {
+        let expr = Expr::BinOp(
+            Box::new(Expr::Number(8)),
+            Op::Div,
+            Box::new(Expr::Number(2)),
+        );
+        assert_eq!(expr.to_string(), "(8) / 2");
+
+        for input in ["(8) / 2", "(2024/01) / (8) - (1h * 3) / 2", "-(8) / 2h"] {
+            let expr = parse(Lexer::new(input)).unwrap();
+            assert_eq!(parse(Lexer::new(&expr.to_string())).unwrap(), expr);
+        }
+    }
+}
@@ -0,0 +1,128 @@
+//! An on-call rotation: participants taking turns in equal shifts, for
+//! answering who is on call at a given time and when someone's next shift
+//! starts.
+
+use alloc::{string::String, vec::Vec};
+
+use time::{Duration, OffsetDateTime};
+
+use crate::meeting::Slot;
+
+/// Participants on call one after another, each for one `period`, in order
+/// from `start` and round again after the last.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rotation {
+    pub participants: Vec<String>,
+    /// When the first participant's first shift begins. Shifts before it
+    /// follow the same order backwards.
+    pub start: OffsetDateTime,
+    /// How long each shift lasts, positive.
+    pub period: Duration,
+}
+
+impl Rotation {
+    /// Who is on call at `at`, and the shift they are on.
+    pub fn on_call(&self, at: OffsetDateTime) -> Option<(&str, Slot)> {
+        let (index, shift) = self.shift_at(at)?;
+        Some((self.participant(index)?, shift))
+    }
+
+    /// The first shift of `participant` that starts after `at`.
+    pub fn next_shift(&self, participant: &str, at: OffsetDateTime) -> Option<Slot> {
+        let position = self
+            .participants
+            .iter()
+            .position(|name| name == participant)?;
+        let (index, current) = self.shift_at(at)?;
+        let count = i128::try_from(self.participants.len()).ok()?;
+        let position = i128::try_from(position).ok()?;
+        let ahead = 1 + (position - index - 1).rem_euclid(count);
+        let start = current
+            .start
+            .checked_add(self.period.checked_mul(i32::try_from(ahead).ok()?)?)?;
+        Some(Slot {
+            start,
+            end: start.checked_add(self.period)?,
+        })
+    }
+
+    /// The number of the shift `at` falls in, counting from 0 at `start`,
+    /// and the shift itself.
+    fn shift_at(&self, at: OffsetDateTime) -> Option<(i128, Slot)> {
+        let period = self.period.whole_nanoseconds();
+        if period <= 0 {
+            return None;
+        }
+        let since = (at - self.start).whole_nanoseconds();
+        let into = Duration::nanoseconds(i64::try_from(since.rem_euclid(period)).ok()?);
+        let start = at.checked_sub(into)?;
+        let shift = Slot {
+            start,
+            end: start.checked_add(self.period)?,
+        };
+        Some((since.div_euclid(period), shift))
+    }
+
+    fn participant(&self, index: i128) -> Option<&str> {
+        let count = i128::try_from(self.participants.len()).ok()?;
+        let position = usize::try_from(index.checked_rem_euclid(count)?).ok()?;
+        Some(&self.participants[position])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+    use time::{Date, Month, Time};
+
+    fn october(day: u8, hour: u8, minute: u8) -> OffsetDateTime {
+        Date::from_calendar_date(2024, Month::October, day)
+            .unwrap()
+            .with_time(Time::from_hms(hour, minute, 0).unwrap())
+            .assume_utc()
+    }
+
+    fn rotation() -> Rotation {
+        Rotation {
+            participants: ["alice", "bob", "carol"].map(ToString::to_string).to_vec(),
+            start: october(14, 9, 0),
+            period: Duration::WEEK,
+        }
+    }
+
+    #[test]
+    fn test_finds_who_is_on_call() {
+        let rotation = rotation();
+
+        let (name, shift) = rotation.on_call(october(22, 3, 0)).unwrap();
+        assert_eq!(name, "bob");
+        assert_eq!(shift.start, october(21, 9, 0));
+        assert_eq!(shift.end, october(28, 9, 0));
+
+        assert_eq!(rotation.on_call(october(14, 9, 0)).unwrap().0, "alice");
+        assert_eq!(rotation.on_call(october(14, 8, 59)).unwrap().0, "carol");
+        assert_eq!(rotation.on_call(october(31, 12, 0)).unwrap().0, "carol");
+
+        let empty = Rotation {
+            participants: Vec::new(),
+            ..rotation
+        };
+        assert_eq!(empty.on_call(october(14, 9, 0)), None);
+    }
+
+    #[test]
+    fn test_finds_the_next_shift() {
+        let rotation = rotation();
+        let at = october(22, 3, 0);
+
+        let carol = rotation.next_shift("carol", at).unwrap();
+        assert_eq!(carol.start, october(28, 9, 0));
+        assert_eq!(carol.length(), Duration::WEEK);
+        let alice = rotation.next_shift("alice", at).unwrap();
+        assert_eq!(alice.start, october(28, 9, 0) + Duration::WEEK);
+        let bob = rotation.next_shift("bob", at).unwrap();
+        assert_eq!(bob.start, october(28, 9, 0) + Duration::WEEK * 2);
+        assert_eq!(rotation.next_shift("dave", at), None);
+    }
+}
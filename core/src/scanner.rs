@@ -0,0 +1,100 @@
+//! A cursor over a string for the lexer, usable without `std`.
+
+/// Steps through a string one `char` at a time. The cursor is a byte offset
+/// that always sits on a `char` boundary.
+#[derive(Clone, Copy, Debug)]
+pub struct Scanner<'a> {
+    string: &'a str,
+    cursor: usize,
+}
+
+impl<'a> Scanner<'a> {
+    pub fn new(string: &'a str) -> Self {
+        Scanner { string, cursor: 0 }
+    }
+
+    pub fn string(&self) -> &'a str {
+        self.string
+    }
+
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    /// The text from `start` up to the cursor.
+    pub fn from(&self, start: usize) -> &'a str {
+        &self.string[start..self.cursor]
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.string[self.cursor..]
+    }
+
+    /// The `n`th char after the cursor, `0` being the next one.
+    pub fn scout(&self, n: usize) -> Option<char> {
+        self.rest().chars().nth(n)
+    }
+
+    pub fn at(&self, c: char) -> bool {
+        self.scout(0) == Some(c)
+    }
+
+    pub fn eat(&mut self) -> Option<char> {
+        let c = self.scout(0)?;
+        self.cursor += c.len_utf8();
+        Some(c)
+    }
+
+    /// Steps back over the char before the cursor.
+    pub fn uneat(&mut self) {
+        if let Some(c) = self.string[..self.cursor].chars().next_back() {
+            self.cursor -= c.len_utf8();
+        }
+    }
+
+    pub fn eat_if(&mut self, c: char) -> bool {
+        let found = self.at(c);
+        if found {
+            self.cursor += c.len_utf8();
+        }
+        found
+    }
+
+    /// Eats chars while `pred` holds and returns them.
+    pub fn eat_while(&mut self, mut pred: impl FnMut(char) -> bool) -> &'a str {
+        let start = self.cursor;
+        let rest = self.rest();
+        let len = rest.find(|c| !pred(c)).unwrap_or(rest.len());
+        self.cursor += len;
+        self.from(start)
+    }
+
+    /// Eats chars up to, but not including, `end` or the end of input.
+    pub fn eat_until(&mut self, end: char) -> &'a str {
+        self.eat_while(|c| c != end)
+    }
+
+    pub fn eat_whitespace(&mut self) -> &'a str {
+        self.eat_while(char::is_whitespace)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_steps_over_multibyte_chars() {
+        let mut s = Scanner::new("µs 12");
+
+        assert_eq!(s.eat(), Some('µ'));
+        s.uneat();
+        assert_eq!(s.eat_while(char::is_alphabetic), "µs");
+        assert_eq!(s.eat_whitespace(), " ");
+        assert!(s.at('1'));
+        assert_eq!(s.scout(1), Some('2'));
+        assert_eq!(s.eat_until('\n'), "12");
+        assert_eq!(s.eat(), None);
+        assert_eq!(s.from(0), "µs 12");
+    }
+}
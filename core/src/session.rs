@@ -0,0 +1,441 @@
+//! Shares settings and cached work across many evaluations.
+
+use std::collections::HashMap;
+
+use time::OffsetDateTime;
+
+use crate::evaluator::{Evaluation, Value, evaluate_with};
+#[cfg(feature = "serde")]
+use crate::format::{DurationStyle, HourFormat, Precision};
+#[cfg(feature = "serde")]
+use crate::locale::{DateStyle, Locale};
+use crate::options::Options;
+use crate::zone::Zone;
+use crate::{CompiledExpr, compile_with_options};
+
+/// Most compiled expressions a [`Session`] keeps before starting over.
+const MAX_CACHED: usize = 1024;
+
+/// Most changes a [`Session`] can undo.
+const MAX_UNDO: usize = 100;
+
+/// Evaluates a series of expressions, such as the lines of a REPL or a
+/// file, against the same [`Options`]. The reference time is resolved once
+/// when the session starts, so every expression sees the same `now`, and
+/// repeated inputs reuse their parsed form. Holiday calendars and unit
+/// aliases need no cache of their own, as they are read once into the
+/// options, and zone data is compiled into the crate. Each result is kept
+/// for later expressions to refer to as `%1` or `ans`, and an input written
+/// `name = expression` keeps its value for later ones to refer to as
+/// `{name}`.
+#[derive(Debug, Clone)]
+pub struct Session {
+    options: Options,
+    /// Whether `now` came from the options rather than the clock.
+    fixed_now: bool,
+    compiled: HashMap<String, CompiledExpr>,
+    variables: Vec<(String, Value)>,
+    results: Vec<Value>,
+    /// The input each result came from, in the same order.
+    history: Vec<String>,
+    failures: usize,
+    zone: Option<Zone>,
+    undo: Vec<Snapshot>,
+    redo: Vec<Snapshot>,
+}
+
+/// The variables, zone and options of a [`Session`] at one point, to go
+/// back to with [`Session::restore`]. Results and the reference time are
+/// not part of it.
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    variables: Vec<(String, Value)>,
+    zone: Option<Zone>,
+    options: Options,
+}
+
+/// The part of a [`Session`] worth resuming later, made by
+/// [`Session::save`]: its variables, zone and display settings. Results are
+/// left out, as relative ones would have moved on by then.
+#[cfg(feature = "serde")]
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct SavedSession {
+    pub variables: Vec<(String, Value)>,
+    pub zone: Option<Zone>,
+    pub hour_format: HourFormat,
+    pub locale: Option<Locale>,
+    pub date_style: DateStyle,
+    pub show_weekday: bool,
+    pub duration_style: DurationStyle,
+    pub precision: Precision,
+}
+
+impl Session {
+    pub fn new(mut options: Options) -> Self {
+        let fixed_now = options.now.is_some();
+        options.now.get_or_insert_with(OffsetDateTime::now_utc);
+        Session {
+            options,
+            fixed_now,
+            compiled: HashMap::new(),
+            variables: Vec::new(),
+            results: Vec::new(),
+            history: Vec::new(),
+            failures: 0,
+            zone: None,
+            undo: Vec::new(),
+            redo: Vec::new(),
+        }
+    }
+
+    /// The options every expression is evaluated with, including the
+    /// resolved reference time.
+    pub fn options(&self) -> &Options {
+        &self.options
+    }
+
+    /// Changes the options later expressions are evaluated with, such as
+    /// how results are formatted.
+    pub fn configure(&mut self, change: impl FnOnce(&mut Options)) {
+        self.record();
+        change(&mut self.options);
+        // Parse-time settings may have changed too.
+        self.compiled.clear();
+    }
+
+    /// The zone date-time results are shown in, if not their own offset.
+    pub fn zone(&self) -> Option<Zone> {
+        self.zone
+    }
+
+    pub fn set_zone(&mut self, zone: Option<Zone>) {
+        self.record();
+        self.zone = zone;
+    }
+
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            variables: self.variables.clone(),
+            zone: self.zone,
+            options: self.options.clone(),
+        }
+    }
+
+    /// Goes back to the variables, zone and options in `snapshot`, keeping
+    /// the results and the reference time.
+    pub fn restore(&mut self, snapshot: Snapshot) {
+        let now = self.options.now;
+        self.variables = snapshot.variables;
+        self.zone = snapshot.zone;
+        self.options = Options {
+            now,
+            ..snapshot.options
+        };
+        self.compiled.clear();
+    }
+
+    /// Reverts the latest change to the variables, the zone or the options,
+    /// returning whether there was one to revert.
+    pub fn undo(&mut self) -> bool {
+        let Some(snapshot) = self.undo.pop() else {
+            return false;
+        };
+        self.redo.push(self.snapshot());
+        self.restore(snapshot);
+        true
+    }
+
+    /// Makes the latest change reverted by [`Session::undo`] again.
+    pub fn redo(&mut self) -> bool {
+        let Some(snapshot) = self.redo.pop() else {
+            return false;
+        };
+        self.undo.push(self.snapshot());
+        self.restore(snapshot);
+        true
+    }
+
+    #[cfg(feature = "serde")]
+    pub fn save(&self) -> SavedSession {
+        SavedSession {
+            variables: self.variables.clone(),
+            zone: self.zone,
+            hour_format: self.options.hour_format,
+            locale: self.options.locale,
+            date_style: self.options.date_style,
+            show_weekday: self.options.show_weekday,
+            duration_style: self.options.duration_style,
+            precision: self.options.precision,
+        }
+    }
+
+    /// Takes on the variables, zone and display settings in `saved`, as a
+    /// change [`Session::undo`] can revert.
+    #[cfg(feature = "serde")]
+    pub fn load(&mut self, saved: SavedSession) {
+        self.record();
+        self.variables = saved.variables;
+        self.zone = saved.zone;
+        self.options.hour_format = saved.hour_format;
+        self.options.locale = saved.locale;
+        self.options.date_style = saved.date_style;
+        self.options.show_weekday = saved.show_weekday;
+        self.options.duration_style = saved.duration_style;
+        self.options.precision = saved.precision;
+        // The locale decides which month and weekday names parse.
+        self.compiled.clear();
+    }
+
+    /// Keeps the current state for [`Session::undo`], before a change.
+    fn record(&mut self) {
+        if self.undo.len() >= MAX_UNDO {
+            self.undo.remove(0);
+        }
+        self.undo.push(self.snapshot());
+        self.redo.clear();
+    }
+
+    pub fn now(&self) -> OffsetDateTime {
+        self.options.now.unwrap_or_else(OffsetDateTime::now_utc)
+    }
+
+    /// Reads the clock again, for long-running sessions. A reference time
+    /// fixed in the original options is kept.
+    pub fn refresh_now(&mut self) {
+        if !self.fixed_now {
+            self.options.now = Some(OffsetDateTime::now_utc());
+        }
+    }
+
+    /// Parses `input`, or returns the expression already parsed for it.
+    pub fn compile(&mut self, input: &str) -> Result<&CompiledExpr, String> {
+        if !self.compiled.contains_key(input) {
+            let compiled = compile_with_options(input, &self.options)?;
+            if self.compiled.len() >= MAX_CACHED {
+                self.compiled.clear();
+            }
+            self.compiled.insert(input.to_string(), compiled);
+        }
+        Ok(&self.compiled[input])
+    }
+
+    /// The results evaluated so far, oldest first; `%1` is the first.
+    pub fn results(&self) -> &[Value] {
+        &self.results
+    }
+
+    /// The input each of [`Session::results`] came from.
+    pub fn history(&self) -> &[String] {
+        &self.history
+    }
+
+    /// How many inputs failed to parse or evaluate.
+    pub fn failures(&self) -> usize {
+        self.failures
+    }
+
+    /// The variables defined so far, in order of first definition.
+    pub fn variables(&self) -> &[(String, Value)] {
+        &self.variables
+    }
+
+    pub fn evaluate(&mut self, input: &str) -> Result<Evaluation, String> {
+        let result = self.evaluate_input(input);
+        match &result {
+            Ok(evaluation) => {
+                self.results.push(evaluation.value.clone());
+                self.history.push(input.to_string());
+            }
+            Err(_) => self.failures += 1,
+        }
+        result
+    }
+
+    pub fn run(&mut self, input: &str) -> Result<String, String> {
+        let value = self.evaluate(input)?.value;
+        Ok(value.display(&self.options).to_string())
+    }
+
+    fn evaluate_input(&mut self, input: &str) -> Result<Evaluation, String> {
+        let (name, expr) = match assignment(input) {
+            Some((name, expr)) => (Some(name), expr),
+            None => (None, input),
+        };
+        self.compile(expr)?;
+        let bindings = self
+            .variables
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.clone()))
+            .collect::<Vec<_>>();
+        let mut evaluation = evaluate_with(
+            &self.compiled[expr].ast,
+            &self.options,
+            &bindings,
+            &self.results,
+            false,
+        )
+        .map_err(|err| format!("failed to evaluate expression: {}", err))?;
+
+        if let (Some(zone), &Value::DateTime(at)) = (self.zone, &evaluation.value)
+            && let Some(local) = zone.at(at)
+        {
+            evaluation.value = Value::Zoned(local, zone);
+        }
+        if let Some(name) = name {
+            self.record();
+            match self
+                .variables
+                .iter_mut()
+                .find(|(defined, _)| defined == name)
+            {
+                Some((_, value)) => *value = evaluation.value.clone(),
+                None => self
+                    .variables
+                    .push((name.to_string(), evaluation.value.clone())),
+            }
+        }
+        Ok(evaluation)
+    }
+}
+
+/// Splits an input written `name = expression` into the name and the
+/// expression.
+fn assignment(input: &str) -> Option<(&str, &str)> {
+    let (name, expr) = input.split_once('=')?;
+    let name = name.trim();
+    let mut chars = name.chars();
+    let is_name = chars
+        .next()
+        .is_some_and(|first| first.is_alphabetic() || first == '_')
+        && chars.all(|c| c.is_alphanumeric() || c == '_');
+    (is_name && !expr.starts_with('=')).then_some((name, expr))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::format::DurationStyle;
+
+    #[test]
+    fn test_resolves_now_once() {
+        let mut session = Session::new(Options::default());
+        let now = session.now();
+
+        assert_eq!(session.run("now").unwrap(), session.run("now").unwrap());
+        assert_eq!(session.now(), now);
+        assert_eq!(session.options().now, Some(now));
+    }
+
+    #[test]
+    fn test_keeps_fixed_now_on_refresh() {
+        let now = OffsetDateTime::UNIX_EPOCH;
+        let mut session = Session::new(Options {
+            now: Some(now),
+            ..Options::default()
+        });
+
+        session.refresh_now();
+
+        assert_eq!(session.run("today").unwrap(), "1970-01-01");
+    }
+
+    #[test]
+    fn test_refers_to_earlier_results() {
+        let mut session = Session::new(Options::default());
+
+        assert!(session.run("ans").is_err());
+        session.run("2024/01/31").unwrap();
+        session.run("3d").unwrap();
+
+        assert_eq!(session.run("%1 + 1d").unwrap(), "2024-02-01");
+        assert_eq!(session.run("ans + %2").unwrap(), "2024-02-04");
+        assert!(session.run("%9").is_err());
+        assert_eq!(session.results().len(), 4);
+    }
+
+    #[test]
+    fn test_defines_variables() {
+        let mut session = Session::new(Options::default());
+
+        assert_eq!(session.run("start = 2024/03/01").unwrap(), "2024-03-01");
+        assert_eq!(session.run("length = 3wd").unwrap(), "3wd");
+        assert_eq!(session.run("{start} + {length}").unwrap(), "2024-03-06");
+        assert_eq!(session.run("start = {start} + 1d").unwrap(), "2024-03-02");
+        assert_eq!(session.run("1d == 1d").unwrap(), "true");
+        assert!(session.run("{end}").is_err());
+
+        let names = session.variables().iter().map(|(name, _)| name.as_str());
+        assert_eq!(names.collect::<Vec<_>>(), ["start", "length"]);
+        assert_eq!(session.history().len(), session.results().len());
+        assert_eq!(session.history()[2], "{start} + {length}");
+        assert_eq!(session.failures(), 1);
+    }
+
+    #[test]
+    fn test_undoes_changes() {
+        let mut session = Session::new(Options::default());
+        session.run("start = 2024/03/01").unwrap();
+        session.run("start = 2024/04/01").unwrap();
+        session.configure(|options| options.duration_style = DurationStyle::Words);
+
+        assert!(session.undo());
+        assert_eq!(session.run("1d").unwrap(), "1d");
+        assert!(session.undo());
+        assert_eq!(session.run("{start}").unwrap(), "2024-03-01");
+        assert!(session.undo());
+        assert!(session.run("{start}").is_err());
+        assert!(!session.undo());
+
+        assert!(session.redo());
+        assert!(session.redo());
+        assert_eq!(session.run("{start}").unwrap(), "2024-04-01");
+        session.run("start = 2024/05/01").unwrap();
+        assert!(!session.redo());
+        assert_eq!(session.results().len(), 6);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_resumes_saved_sessions() {
+        let mut session = Session::new(Options::default());
+        session.run("start = 2024/03/01 12:00").unwrap();
+        session.configure(|options| options.duration_style = DurationStyle::Words);
+        let saved = serde_json::to_string(&session.save()).unwrap();
+
+        let mut resumed = Session::new(Options::default());
+        resumed.load(serde_json::from_str(&saved).unwrap());
+        assert_eq!(
+            resumed.run("{start} + 1d").unwrap(),
+            "2024-03-02 12:00 +00:00"
+        );
+        assert_eq!(resumed.run("90m").unwrap(), "1 hour, 30 minutes");
+        assert!(resumed.results().len() == 2 && resumed.undo());
+        assert!(resumed.run("{start}").is_err());
+
+        assert!(serde_json::from_str::<SavedSession>("{}").is_ok());
+    }
+
+    #[cfg(all(feature = "serde", feature = "locales"))]
+    #[test]
+    fn test_reparses_after_loading_another_locale() {
+        let mut session = Session::new(Options {
+            locale: Some(Locale::ItIt),
+            ..Options::default()
+        });
+        assert_eq!(session.run("3 marzo 2024").unwrap(), "03/03/2024");
+
+        session.load(SavedSession::default());
+        assert!(session.run("3 marzo 2024").is_err());
+    }
+
+    #[test]
+    fn test_reuses_compiled_expressions() {
+        let mut session = Session::new(Options::default());
+
+        session.compile("2h + 30m").unwrap();
+        assert_eq!(session.run("2h + 30m").unwrap(), "2h30m");
+        assert_eq!(session.compiled.len(), 1);
+        assert!(session.run("2024/13/01").is_err());
+    }
+}
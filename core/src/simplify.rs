@@ -0,0 +1,140 @@
+//! Folds constant sub-expressions of an [`Expr`] ahead of evaluation.
+
+use crate::parser::{Expr, Op, UnaryOp, Unit, sum_durations};
+use alloc::boxed::Box;
+
+/// Returns an equivalent expression with pure duration arithmetic folded
+/// (`2h + 30m + 15m` becomes `2h45m`) and literal negations resolved.
+///
+/// Durations are only folded with other durations: `today + 12h + 12h` keeps
+/// both steps because adding hours to a date truncates to whole days.
+pub fn simplify(expr: &Expr) -> Expr {
+    match expr {
+        Expr::BinOp(left, op, right) => {
+            let left = simplify(left);
+            let right = simplify(right);
+            fold_binary(&left, op, &right)
+                .unwrap_or_else(|| Expr::BinOp(Box::new(left), op.clone(), Box::new(right)))
+        }
+        Expr::UnaryOp(op, operand) => {
+            let operand = simplify(operand);
+            match (op, operand) {
+                (UnaryOp::Not, Expr::Bool(value)) => Expr::Bool(!value),
+                (UnaryOp::Not, Expr::UnaryOp(UnaryOp::Not, inner)) => *inner,
+                (UnaryOp::Neg, Expr::UnaryOp(UnaryOp::Neg, inner)) => *inner,
+                (op, operand) => Expr::UnaryOp(op.clone(), Box::new(operand)),
+            }
+        }
+        Expr::Call(name, args) => Expr::Call(name.clone(), args.iter().map(simplify).collect()),
+        _ => expr.clone(),
+    }
+}
+
+fn fold_binary(left: &Expr, op: &Op, right: &Expr) -> Option<Expr> {
+    match (left, op, right) {
+        (Expr::Bool(left), Op::And, Expr::Bool(right)) => Some(Expr::Bool(*left && *right)),
+        (Expr::Bool(left), Op::Or, Expr::Bool(right)) => Some(Expr::Bool(*left || *right)),
+        _ => {
+            let (left_value, left_unit) = as_duration(left)?;
+            let (right_value, right_unit) = as_duration(right)?;
+            let right_value = match op {
+                Op::Add => right_value,
+                Op::Sub => right_value.checked_neg()?,
+                _ => return None,
+            };
+            let (value, unit) = sum_units((left_value, &left_unit), (right_value, &right_unit))?;
+            duration(value, unit)
+        }
+    }
+}
+
+fn sum_units(left: (i64, &Unit), right: (i64, &Unit)) -> Option<(i64, Unit)> {
+    match (left.1, right.1) {
+        (unit @ (Unit::WorkingDays | Unit::Weekdays), right_unit) if unit == right_unit => {
+            Some((left.0.checked_add(right.0)?, unit.clone()))
+        }
+        (left_unit, right_unit) if left_unit == right_unit && left_unit.months().is_some() => {
+            Some((left.0.checked_add(right.0)?, left_unit.clone()))
+        }
+        (left_unit, right_unit)
+            if left_unit.months().is_some() && right_unit.months().is_some() =>
+        {
+            Some((
+                in_months(left)?.checked_add(in_months(right)?)?,
+                Unit::Months,
+            ))
+        }
+        _ => sum_durations(left, right),
+    }
+}
+
+fn in_months((value, unit): (i64, &Unit)) -> Option<i64> {
+    value.checked_mul(unit.months()?)
+}
+
+/// Reads a duration literal, including a negated one.
+fn as_duration(expr: &Expr) -> Option<(i64, Unit)> {
+    match expr {
+        Expr::Duration(value, unit) => Some((*value, unit.clone())),
+        Expr::UnaryOp(UnaryOp::Neg, operand) => match operand.as_ref() {
+            Expr::Duration(value, unit) => Some((value.checked_neg()?, unit.clone())),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Builds a duration literal, negating it through `UnaryOp::Neg` like the
+/// parser does. Returns `None` for `i64::MIN`, which has no literal to negate.
+fn duration(value: i64, unit: Unit) -> Option<Expr> {
+    if value < 0 {
+        let literal = Expr::Duration(value.checked_neg()?, unit);
+        Some(Expr::UnaryOp(UnaryOp::Neg, Box::new(literal)))
+    } else {
+        Some(Expr::Duration(value, unit))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::Lexer;
+    use crate::parser::parse;
+
+    fn simplified(input: &str) -> String {
+        simplify(&parse(Lexer::new(input)).unwrap()).to_string()
+    }
+
+    #[test]
+    fn test_folds_duration_chain() {
+        assert_eq!(simplified("2h + 30m + 15m"), "2h45m");
+        assert_eq!(simplified("30m - 2h"), "-1h30m");
+        assert_eq!(simplified("1y + 2 months"), "14 months");
+        assert_eq!(simplified("1 century - 1 decade"), "1080 months");
+        assert_eq!(simplified("days_in(2024/02) + 1d"), "days_in(2024/02) + 1d");
+    }
+
+    #[test]
+    fn test_keeps_steps_applied_to_dates() {
+        assert_eq!(simplified("today + 12h + 12h"), "today + 12h + 12h");
+    }
+
+    #[test]
+    fn test_folds_literal_logic() {
+        assert_eq!(simplified("not true or false"), "false");
+        assert_eq!(simplified("--2h"), "2h");
+    }
+
+    #[test]
+    fn test_keeps_sums_without_a_literal() {
+        let expr = Expr::BinOp(
+            Box::new(Expr::UnaryOp(
+                UnaryOp::Neg,
+                Box::new(Expr::Duration(i64::MAX, Unit::Seconds)),
+            )),
+            Op::Sub,
+            Box::new(Expr::Duration(1, Unit::Seconds)),
+        );
+        assert_eq!(simplify(&expr), expr);
+    }
+}
@@ -0,0 +1,116 @@
+//! The names durations can be written with. Each name stands for a multiple
+//! of one [`Unit`], and keeps that unit's semantics: hours and days have a
+//! fixed length, months and years move along the calendar.
+
+use alloc::{borrow::ToOwned, string::String, vec::Vec};
+
+use crate::parser::Unit;
+
+/// The built-in names, each for one of its unit.
+const BUILTIN: &[(&[&str], Unit)] = &[
+    (&["centuries", "century"], Unit::Centuries),
+    (&["decades", "decade"], Unit::Decades),
+    (&["years", "year", "y"], Unit::Years),
+    #[cfg(feature = "colloquial")]
+    (&["quarters", "quarter"], Unit::Quarters),
+    (&["months", "month"], Unit::Months),
+    #[cfg(feature = "colloquial")]
+    (&["fortnights", "fortnight"], Unit::Fortnights),
+    (&["weeks", "week", "w"], Unit::Weeks),
+    (&["days", "day", "d"], Unit::Days),
+    (
+        &["workingdays", "workingday", "workdays", "workday", "wd"],
+        Unit::WorkingDays,
+    ),
+    (&["weekdays", "weekday"], Unit::Weekdays),
+    (&["hours", "hour", "h"], Unit::Hours),
+    (&["minutes", "minute", "m"], Unit::Minutes),
+    (&["seconds", "second", "s"], Unit::Seconds),
+    (&["milliseconds", "millisecond", "ms"], Unit::Milliseconds),
+    (
+        &["microseconds", "microsecond", "us", "µs"],
+        Unit::Microseconds,
+    ),
+    (&["nanoseconds", "nanosecond", "ns"], Unit::Nanoseconds),
+];
+
+/// A unit defined on top of the built-in ones, as in a `pomodoro` of 25
+/// minutes.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UnitName {
+    pub name: String,
+    /// Other names for it, such as a plural or an abbreviation.
+    pub aliases: Vec<String>,
+    /// How many of `unit` one of it is.
+    pub amount: i64,
+    pub unit: Unit,
+}
+
+impl UnitName {
+    fn is_called(&self, name: &str) -> bool {
+        self.name == name || self.aliases.iter().any(|alias| alias == name)
+    }
+}
+
+/// The unit names expressions can use: the built-in ones, and any defined
+/// with [`Units::define`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Units {
+    defined: Vec<UnitName>,
+}
+
+impl Units {
+    /// Adds a unit called `name` or any of `aliases`, standing for `amount`
+    /// of `unit`. It takes precedence over earlier definitions and the
+    /// built-in names.
+    pub fn define(&mut self, name: &str, aliases: &[&str], amount: i64, unit: Unit) {
+        self.defined.push(UnitName {
+            name: name.to_owned(),
+            aliases: aliases.iter().map(|alias| (*alias).to_owned()).collect(),
+            amount,
+            unit,
+        });
+    }
+
+    /// The units added with [`Units::define`], in order.
+    pub fn defined(&self) -> &[UnitName] {
+        &self.defined
+    }
+
+    /// What `name` stands for, as an amount of a built-in unit.
+    pub fn get(&self, name: &str) -> Option<(i64, Unit)> {
+        self.defined
+            .iter()
+            .rev()
+            .find(|defined| defined.is_called(name))
+            .map(|defined| (defined.amount, defined.unit.clone()))
+            .or_else(|| builtin(name).map(|unit| (1, unit)))
+    }
+}
+
+/// The built-in unit called `name`.
+pub(crate) fn builtin(name: &str) -> Option<Unit> {
+    BUILTIN
+        .iter()
+        .find(|(names, _)| names.contains(&name))
+        .map(|(_, unit)| unit.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_defined_units_shadow_builtin_ones() {
+        let mut units = Units::default();
+        assert_eq!(units.get("h"), Some((1, Unit::Hours)));
+        assert_eq!(units.get("pomodoro"), None);
+
+        units.define("pomodoro", &["pomodoros"], 25, Unit::Minutes);
+        units.define("week", &[], 5, Unit::WorkingDays);
+        assert_eq!(units.get("pomodoros"), Some((25, Unit::Minutes)));
+        assert_eq!(units.get("week"), Some((5, Unit::WorkingDays)));
+        assert_eq!(units.get("weeks"), Some((1, Unit::Weeks)));
+    }
+}
@@ -0,0 +1,235 @@
+//! Named time zones from the IANA database, available with the `tz` feature.
+
+use alloc::string::String;
+use core::fmt;
+
+use time::{OffsetDateTime, PrimitiveDateTime};
+
+/// How far ahead [`Zone::next_transition`] looks, in days.
+#[cfg(feature = "tz")]
+const TRANSITION_SEARCH_DAYS: i64 = 2 * 366;
+
+/// A named time zone such as `Europe/Rome`. Without the `tz` feature no zone
+/// can be looked up, so no value of this type exists.
+#[derive(Clone, Copy)]
+pub struct Zone(
+    #[cfg(feature = "tz")] &'static time_tz::Tz,
+    #[cfg(not(feature = "tz"))] Never,
+);
+
+#[cfg(not(feature = "tz"))]
+#[derive(Clone, Copy)]
+enum Never {}
+
+#[cfg(feature = "tz")]
+impl Zone {
+    /// Looks a zone up by its IANA name, ignoring case.
+    pub fn from_name(name: &str) -> Option<Zone> {
+        time_tz::timezones::get_by_name(name)
+            .or_else(|| {
+                time_tz::timezones::iter()
+                    .find(|zone| time_tz::TimeZone::name(*zone).eq_ignore_ascii_case(name))
+            })
+            .map(Zone)
+    }
+
+    /// Every zone in the database, in no particular order.
+    pub fn all() -> impl Iterator<Item = Zone> {
+        time_tz::timezones::iter().map(Zone)
+    }
+
+    pub fn name(self) -> &'static str {
+        time_tz::TimeZone::name(self.0)
+    }
+
+    /// The abbreviation in use at `instant`, such as `CEST`.
+    pub fn abbreviation(self, instant: OffsetDateTime) -> String {
+        let offset = time_tz::TimeZone::get_offset_utc(self.0, &instant);
+        String::from(time_tz::Offset::name(&offset))
+    }
+
+    /// Whether daylight saving time is in effect at `instant`.
+    pub fn is_dst(self, instant: OffsetDateTime) -> bool {
+        let offset = time_tz::TimeZone::get_offset_utc(self.0, &instant);
+        time_tz::Offset::is_dst(&offset)
+    }
+
+    /// The first instant after `after` at which the offset changes, if one
+    /// comes within the next two years.
+    pub fn next_transition(self, after: OffsetDateTime) -> Option<OffsetDateTime> {
+        let offset = |instant: OffsetDateTime| {
+            let offset = time_tz::TimeZone::get_offset_utc(self.0, &instant);
+            (
+                time_tz::Offset::to_utc(&offset),
+                time_tz::Offset::is_dst(&offset),
+            )
+        };
+        let current = offset(after);
+
+        // Find the day the offset changes in, then narrow it to the second.
+        let mut before = after;
+        let mut changed = None;
+        for _ in 0..TRANSITION_SEARCH_DAYS {
+            let next = before.checked_add(time::Duration::DAY)?;
+            if offset(next) != current {
+                changed = Some(next);
+                break;
+            }
+            before = next;
+        }
+        let mut changed = changed?;
+        while changed - before > time::Duration::SECOND {
+            let middle = before + (changed - before) / 2;
+            if offset(middle) == current {
+                before = middle;
+            } else {
+                changed = middle;
+            }
+        }
+        changed.replace_nanosecond(0).ok()
+    }
+
+    /// The same instant, with the offset this zone has at that instant, if
+    /// that still fits the supported range.
+    pub fn at(self, instant: OffsetDateTime) -> Option<OffsetDateTime> {
+        let offset = time_tz::TimeZone::get_offset_utc(self.0, &instant);
+        instant.checked_to_offset(time_tz::Offset::to_utc(&offset))
+    }
+
+    /// Resolves a wall-clock time in this zone. A time the clocks went back
+    /// over happened twice and resolves to the earlier one; a time they
+    /// skipped moves forward by the length of the gap, so 02:30 on a
+    /// spring-forward night is 03:30.
+    pub fn local(self, local: PrimitiveDateTime) -> Option<OffsetDateTime> {
+        match time_tz::PrimitiveDateTimeExt::assume_timezone(&local, self.0) {
+            time_tz::OffsetResult::Some(datetime) => Some(datetime),
+            time_tz::OffsetResult::Ambiguous(earlier, _) => Some(earlier),
+            time_tz::OffsetResult::None => {
+                // Read the wall clock with the offset from before the gap.
+                let before = local.assume_utc().checked_sub(time::Duration::DAY)?;
+                let offset = self.at(before)?.offset();
+                self.at(local.assume_offset(offset))
+            }
+        }
+    }
+}
+
+#[cfg(not(feature = "tz"))]
+impl Zone {
+    pub fn from_name(_name: &str) -> Option<Zone> {
+        None
+    }
+
+    pub fn all() -> impl Iterator<Item = Zone> {
+        core::iter::empty()
+    }
+
+    pub fn name(self) -> &'static str {
+        match self.0 {}
+    }
+
+    pub fn abbreviation(self, _instant: OffsetDateTime) -> String {
+        match self.0 {}
+    }
+
+    pub fn is_dst(self, _instant: OffsetDateTime) -> bool {
+        match self.0 {}
+    }
+
+    pub fn next_transition(self, _after: OffsetDateTime) -> Option<OffsetDateTime> {
+        match self.0 {}
+    }
+
+    pub fn at(self, _instant: OffsetDateTime) -> Option<OffsetDateTime> {
+        match self.0 {}
+    }
+
+    pub fn local(self, _local: PrimitiveDateTime) -> Option<OffsetDateTime> {
+        match self.0 {}
+    }
+}
+
+impl fmt::Debug for Zone {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl fmt::Display for Zone {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl PartialEq for Zone {
+    fn eq(&self, other: &Self) -> bool {
+        self.name() == other.name()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Zone {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.name())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Zone {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = <alloc::string::String as serde::Deserialize>::deserialize(deserializer)?;
+        Zone::from_name(&name)
+            .ok_or_else(|| serde::de::Error::custom(alloc::format!("unknown time zone '{}'", name)))
+    }
+}
+
+#[cfg(all(test, feature = "tz"))]
+mod tests {
+    use super::*;
+    use time::{Date, Month, Time, UtcOffset};
+
+    fn local(month: Month, day: u8, hour: u8, minute: u8) -> PrimitiveDateTime {
+        let date = Date::from_calendar_date(2024, month, day).unwrap();
+        date.with_time(Time::from_hms(hour, minute, 0).unwrap())
+    }
+
+    fn offset(hours: i8) -> UtcOffset {
+        UtcOffset::from_hms(hours, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn test_resolves_local_times_around_transitions() {
+        let rome = Zone::from_name("europe/rome").unwrap();
+        assert_eq!(rome.name(), "Europe/Rome");
+
+        assert_eq!(
+            rome.local(local(Month::March, 31, 2, 30)),
+            Some(local(Month::March, 31, 3, 30).assume_offset(offset(2)))
+        );
+        assert_eq!(
+            rome.local(local(Month::October, 27, 2, 30)),
+            Some(local(Month::October, 27, 2, 30).assume_offset(offset(2)))
+        );
+        assert_eq!(
+            rome.at(local(Month::July, 1, 10, 0).assume_utc()),
+            Some(local(Month::July, 1, 12, 0).assume_offset(offset(2)))
+        );
+    }
+
+    #[test]
+    fn test_finds_next_transition() {
+        let rome = Zone::from_name("Europe/Rome").unwrap();
+        let summer = local(Month::July, 1, 10, 0).assume_utc();
+
+        assert!(rome.is_dst(summer));
+        assert_eq!(rome.abbreviation(summer), "CEST");
+        assert_eq!(
+            rome.next_transition(summer),
+            Some(local(Month::October, 27, 1, 0).assume_utc())
+        );
+
+        let tokyo = Zone::from_name("Asia/Tokyo").unwrap();
+        assert_eq!(tokyo.next_transition(summer), None);
+        assert!(Zone::all().any(|zone| zone.name() == "Asia/Tokyo"));
+    }
+}
@@ -1,6 +1,75 @@
 use proptest::prelude::*;
-use tcalc_core::run;
-use time::{Date, Duration, Month, Weekday};
+use tcalc_core::{Options, Value, compile, run, run_with_options};
+use time::{Date, Duration, Month, PrimitiveDateTime, UtcOffset, Weekday};
+
+/// Pieces of valid syntax, so joined sequences often parse.
+const TOKENS: &[&str] = &[
+    "2024/02/29",
+    "1999/12/31 23:59",
+    "jan 2024",
+    "3rd of march 2024",
+    "14:30",
+    "9am",
+    "t0930",
+    "today",
+    "yesterday 5pm",
+    "next friday",
+    "this month",
+    "1",
+    "300",
+    "2h45m",
+    "90 minutes",
+    "1 month",
+    "3y",
+    "2wd",
+    "1 century",
+    "250ms",
+    "true",
+    "days_in(",
+    "year(",
+    ",",
+    "(",
+    ")",
+    "+",
+    "-",
+    "*",
+    "%",
+    "<",
+    "==",
+    "and",
+    "or",
+    "not",
+    "tomorrow",
+    "end of day",
+    "last week",
+    "next year",
+    "2 weekdays",
+    "25%",
+    "of",
+    "/",
+    "date(",
+    "time(",
+    "datetime(",
+    "between(",
+    "relative(",
+    "abs(",
+    "negate(",
+    "sum(",
+    "every",
+    "weekday",
+    "from",
+    "to",
+    "9999/12/31",
+    "-9999/01/01",
+    "9223372036854775807",
+    "9223372036854775807d",
+    "100000wd",
+    "1 decade",
+];
+
+fn token_soup() -> impl Strategy<Value = String> {
+    prop::collection::vec(prop::sample::select(TOKENS), 1..10).prop_map(|tokens| tokens.join(" "))
+}
 
 fn date(year: i32, month: u8, day: u8) -> Date {
     Date::from_calendar_date(year, Month::try_from(month).unwrap(), day).unwrap()
@@ -95,4 +164,75 @@ proptest! {
 
         prop_assert_eq!(run(&input, None).unwrap(), expected);
     }
+
+    #[test]
+    fn large_offsets_never_panic(
+        amount in 0i64..=i64::MAX,
+        unit in prop::sample::select(vec!["y", "months", "w", "d", "h", "m", "s"]),
+        op in prop::sample::select(vec!["+", "-"]),
+    ) {
+        for base in ["2024/01/31", "2024/01/31 12:00", "12:00", "1d"] {
+            let input = format!("{base} {op} {amount}{unit}");
+            let _ = run(&input, None);
+        }
+    }
+
+    #[test]
+    fn extreme_reference_times_never_panic(
+        input in token_soup(),
+        now in prop::sample::select(vec![
+            PrimitiveDateTime::MIN.assume_utc(),
+            PrimitiveDateTime::MAX.assume_utc(),
+            PrimitiveDateTime::MIN.assume_offset(UtcOffset::from_hms(25, 59, 59).unwrap()),
+            PrimitiveDateTime::MAX.assume_offset(UtcOffset::from_hms(-25, -59, -59).unwrap()),
+        ]),
+    ) {
+        let options = Options { now: Some(now), ..Options::default() };
+        let _ = run_with_options(&input, &options);
+    }
+
+    #[test]
+    fn pure_evaluation_matches_evaluation_at_the_same_time(
+        input in token_soup(),
+        seconds in -1_000_000_000i64..4_000_000_000,
+    ) {
+        if let Ok(compiled) = compile(&input) {
+            let now = time::OffsetDateTime::from_unix_timestamp(seconds).unwrap();
+            let options = Options { now: Some(now), ..Options::default() };
+            let show = |result: Result<Value, String>| result.map(|value| value.to_string());
+            prop_assert_eq!(show(compiled.eval_pure(&options)), show(compiled.eval(&options)));
+            // Whatever evaluates without a reference time cannot depend on it.
+            if let Ok(timeless) = compiled.eval_pure(&Options::default()) {
+                prop_assert_eq!(Ok(timeless.to_string()), show(compiled.eval(&options)));
+            }
+        }
+    }
+
+    #[test]
+    fn arbitrary_text_never_panics(input in "\\PC{0,40}") {
+        let _ = run(&input, None);
+    }
+
+    #[test]
+    fn token_soup_never_panics(input in token_soup()) {
+        let _ = run(&input, None);
+    }
+
+    #[cfg(feature = "unstable-ast")]
+    #[test]
+    fn printed_expressions_reparse(input in token_soup()) {
+        if let Ok(compiled) = compile(&input) {
+            let printed = compiled.expr().to_string();
+            let reparsed = compile(&printed);
+            prop_assert!(reparsed.is_ok(), "'{}' printed as '{}'", input, printed);
+            // Durations may print in larger units, so compare canonical forms.
+            prop_assert_eq!(reparsed.unwrap().expr().to_string(), printed);
+        }
+    }
+
+    #[test]
+    fn printed_durations_reparse(nanos in any::<i64>()) {
+        let printed = Value::Duration(Duration::nanoseconds(nanos)).to_string();
+        prop_assert_eq!(run(&printed, None).unwrap(), printed);
+    }
 }
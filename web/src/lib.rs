@@ -1,4 +1,4 @@
-use tcalc_core::run;
+use tcalc_core::{Locale, Options, compile, run, run_with_options};
 use wasm_bindgen::prelude::*;
 
 #[wasm_bindgen]
@@ -14,3 +14,63 @@ pub fn run_web(input: String) -> String {
         Err(e) => format!("Error: {}", e),
     }
 }
+
+#[wasm_bindgen]
+pub fn run_web_localized(input: String, locale: String) -> String {
+    let locale = match locale.parse::<Locale>() {
+        Ok(locale) => locale,
+        Err(e) => return format!("Error: {}", e),
+    };
+    let options = Options {
+        locale: Some(locale),
+        ..Options::default()
+    };
+
+    match run_with_options(&input, &options) {
+        Ok(result) => result,
+        Err(e) => format!("Error: {}", e),
+    }
+}
+
+/// Evaluates `input` and returns one `expression = value` line per evaluated
+/// sub-expression, ending with the full expression.
+#[wasm_bindgen]
+pub fn explain_web(input: String) -> String {
+    let options = Options::default();
+    match compile(&input).and_then(|compiled| compiled.explain(&options)) {
+        Ok(evaluation) => evaluation
+            .trace
+            .iter()
+            .map(|step| format!("{} = {}", step.expr, step.value.display(&options)))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        Err(e) => format!("Error: {}", e),
+    }
+}
+
+/// The outcome of [`run_web_with_warnings`]: the result (or error) text and
+/// any approximation warnings.
+#[wasm_bindgen(getter_with_clone)]
+pub struct WebResult {
+    pub result: String,
+    pub warnings: Vec<String>,
+}
+
+#[wasm_bindgen]
+pub fn run_web_with_warnings(input: String) -> WebResult {
+    let options = Options::default();
+    match compile(&input).and_then(|compiled| compiled.evaluate(&options)) {
+        Ok(evaluation) => WebResult {
+            result: evaluation.value.display(&options).to_string(),
+            warnings: evaluation
+                .warnings
+                .iter()
+                .map(ToString::to_string)
+                .collect(),
+        },
+        Err(e) => WebResult {
+            result: format!("Error: {}", e),
+            warnings: Vec::new(),
+        },
+    }
+}